@@ -0,0 +1,98 @@
+use async_trait::async_trait;
+use derive_new::new;
+use sqlx::PgPool;
+use uuid::Uuid;
+use homelab_core::admin_domain::console_integrity_issue::ConsoleIntegrityIssue;
+use crate::helpers::data_error::DataError;
+
+#[async_trait]
+pub trait IntegrityIssueRepo: Send + Sync {
+    async fn log_issue(&self, issue: ConsoleIntegrityIssue) -> Result<(), DataError>;
+    async fn get_issues(&self) -> Result<Vec<ConsoleIntegrityIssue>, DataError>;
+    async fn get_issues_for_file(&self, file_id: Uuid) -> Result<Vec<ConsoleIntegrityIssue>, DataError>;
+}
+
+#[derive(new)]
+pub struct IntegrityIssueRepoImpl {
+    pool: PgPool
+}
+
+#[async_trait]
+impl IntegrityIssueRepo for IntegrityIssueRepoImpl {
+    async fn log_issue(&self, issue: ConsoleIntegrityIssue) -> Result<(), DataError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO console_integrity_issue (
+                                                 id,
+                                                 file_id,
+                                                 owner_id,
+                                                 status,
+                                                 expected_checksum,
+                                                 actual_checksum,
+                                                 detected_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            issue.id,
+            issue.file_id,
+            issue.owner_id,
+            issue.status as _,
+            issue.expected_checksum,
+            issue.actual_checksum,
+            issue.detected_at
+        )
+            .execute(&self.pool)
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(())
+    }
+
+    async fn get_issues(&self) -> Result<Vec<ConsoleIntegrityIssue>, DataError> {
+        let issues = sqlx::query_as!(
+            ConsoleIntegrityIssue,
+            r#"
+            SELECT
+                id,
+                file_id,
+                owner_id,
+                status as "status: _",
+                expected_checksum,
+                actual_checksum,
+                detected_at
+            FROM console_integrity_issue
+            ORDER BY detected_at DESC
+            "#
+        )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(issues)
+    }
+
+    async fn get_issues_for_file(&self, file_id: Uuid) -> Result<Vec<ConsoleIntegrityIssue>, DataError> {
+        let issues = sqlx::query_as!(
+            ConsoleIntegrityIssue,
+            r#"
+            SELECT
+                id,
+                file_id,
+                owner_id,
+                status as "status: _",
+                expected_checksum,
+                actual_checksum,
+                detected_at
+            FROM console_integrity_issue
+            WHERE file_id = $1
+            ORDER BY detected_at DESC
+            "#,
+            file_id
+        )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(issues)
+    }
+}
@@ -1,3 +1,5 @@
 pub mod wlu_repo;
 pub mod user_repo;
-pub mod file_repo;
\ No newline at end of file
+pub mod file_repo;
+pub mod integrity_issue_repo;
+pub mod preview_failure_repo;
\ No newline at end of file
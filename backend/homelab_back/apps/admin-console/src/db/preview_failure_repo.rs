@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+use derive_new::new;
+use sqlx::PgPool;
+use homelab_core::admin_domain::console_preview_failure::ConsolePreviewFailure;
+use crate::helpers::data_error::DataError;
+
+#[async_trait]
+pub trait PreviewFailureRepo: Send + Sync {
+    async fn log_failure(&self, failure: ConsolePreviewFailure) -> Result<(), DataError>;
+    async fn get_failures(&self) -> Result<Vec<ConsolePreviewFailure>, DataError>;
+}
+
+#[derive(new)]
+pub struct PreviewFailureRepoImpl {
+    pool: PgPool
+}
+
+#[async_trait]
+impl PreviewFailureRepo for PreviewFailureRepoImpl {
+    async fn log_failure(&self, failure: ConsolePreviewFailure) -> Result<(), DataError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO console_preview_failure (
+                                                 id,
+                                                 file_id,
+                                                 owner_id,
+                                                 file_name,
+                                                 attempts,
+                                                 error,
+                                                 failed_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            failure.id,
+            failure.file_id,
+            failure.owner_id,
+            failure.file_name,
+            failure.attempts,
+            failure.error,
+            failure.failed_at
+        )
+            .execute(&self.pool)
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(())
+    }
+
+    async fn get_failures(&self) -> Result<Vec<ConsolePreviewFailure>, DataError> {
+        let failures = sqlx::query_as!(
+            ConsolePreviewFailure,
+            r#"
+            SELECT
+                id,
+                file_id,
+                owner_id,
+                file_name,
+                attempts,
+                error,
+                failed_at
+            FROM console_preview_failure
+            ORDER BY failed_at DESC
+            "#
+        )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(failures)
+    }
+}
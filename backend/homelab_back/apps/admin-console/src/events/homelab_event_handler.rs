@@ -1,9 +1,11 @@
 use std::sync::Arc;
 use async_trait::async_trait;
 use derive_new::new;
-use homelab_core::events::{FileUpdatedEvent, FileUploadedEvent, UserCreatedEvent, UserUpdatedEvent, WhiteListedUserCreatedEvent, WhiteListedUserUpdatedEvent};
+use homelab_core::events::{FileIntegrityFailedEvent, FilePreviewFailedEvent, FileUpdatedEvent, FileUploadedEvent, UserCreatedEvent, UserUpdatedEvent, WhiteListedUserCreatedEvent, WhiteListedUserUpdatedEvent};
 use homelab_core::helpers::event_handler::EventHandler;
 use crate::service::file_service::FileService;
+use crate::service::integrity_service::IntegrityService;
+use crate::service::preview_failure_service::PreviewFailureService;
 use crate::service::user_service::UserService;
 use crate::service::wlu_service::WluService;
 
@@ -11,7 +13,9 @@ use crate::service::wlu_service::WluService;
 pub struct HomelabEventHandler {
     user_service: Arc<dyn UserService>,
     wlu_service: Arc<dyn WluService>,
-    file_service: Arc<dyn FileService>
+    file_service: Arc<dyn FileService>,
+    integrity_service: Arc<dyn IntegrityService>,
+    preview_failure_service: Arc<dyn PreviewFailureService>
 }
 
 #[async_trait]
@@ -82,6 +86,28 @@ impl EventHandler for HomelabEventHandler {
                 self.file_service.log_updated_file(event).await
                     .map_err(|e| format!("Error handling updated WhiteListedUser: {}", e))?;
 
+                Ok(())
+            },
+            "file.integrity.failed" => {
+                let event: FileIntegrityFailedEvent = serde_json::from_slice(data)
+                    .map_err(|e| format!("Json Error: {}", e))?;
+
+                println!("⚠️ Handling Integrity Issue: {}", event.file_id);
+
+                self.integrity_service.log_issue(event).await
+                    .map_err(|e| format!("Error handling integrity issue: {}", e))?;
+
+                Ok(())
+            },
+            "file.preview.failed" => {
+                let event: FilePreviewFailedEvent = serde_json::from_slice(data)
+                    .map_err(|e| format!("Json Error: {}", e))?;
+
+                println!("⚠️ Handling Preview Failure: {}", event.file_id);
+
+                self.preview_failure_service.log_failure(event).await
+                    .map_err(|e| format!("Error handling preview failure: {}", e))?;
+
                 Ok(())
             }
 
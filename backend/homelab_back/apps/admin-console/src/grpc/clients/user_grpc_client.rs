@@ -1,9 +1,13 @@
 use std::error::Error;
+use std::sync::Arc;
 use async_trait::async_trait;
 use derive_new::new;
 use tonic::Request;
+use tonic::service::interceptor::InterceptedService;
 use tonic::transport::Channel;
 use uuid::Uuid;
+use homelab_core::auth::grpc_interceptor::ServiceTokenInterceptor;
+use homelab_core::auth::jwt::JwtKeys;
 use homelab_proto::user::ToggleBlockStatusRequest;
 use homelab_proto::user::user_service_client::UserServiceClient;
 use crate::helpers::proto_mappers::map_id_to_proto;
@@ -16,12 +20,13 @@ pub trait UserRemoteClient: Send + Sync {
 
 #[derive(new)]
 pub struct UserRemoteClientImpl {
-    client: UserServiceClient<Channel>
+    client: UserServiceClient<InterceptedService<Channel, ServiceTokenInterceptor>>
 }
 
 impl UserRemoteClientImpl {
-    pub async fn connect(addr: String) -> Result<Self, Box<dyn Error>> {
-        let client = UserServiceClient::connect(addr).await?;
+    pub async fn connect(addr: String, jwt_keys: Arc<JwtKeys>) -> Result<Self, Box<dyn Error>> {
+        let channel = Channel::from_shared(addr)?.connect().await?;
+        let client = UserServiceClient::with_interceptor(channel, ServiceTokenInterceptor::new(jwt_keys));
         Ok(UserRemoteClientImpl { client })
     }
 }
@@ -1,9 +1,13 @@
 use std::error::Error;
+use std::sync::Arc;
 use async_trait::async_trait;
 use derive_new::new;
 use tonic::Request;
+use tonic::service::interceptor::InterceptedService;
 use tonic::transport::Channel;
 use uuid::Uuid;
+use homelab_core::auth::grpc_interceptor::ServiceTokenInterceptor;
+use homelab_core::auth::jwt::JwtKeys;
 use homelab_proto::user::ConfirmWhiteListedUsersRequest;
 use homelab_proto::user::white_listed_user_service_client::WhiteListedUserServiceClient;
 use crate::helpers::proto_mappers::{map_id_to_proto};
@@ -15,12 +19,13 @@ pub trait WluRemoteClient: Send + Sync {
 
 #[derive(new, Clone)]
 pub struct WluRemoteClientImpl {
-    client: WhiteListedUserServiceClient<Channel>
+    client: WhiteListedUserServiceClient<InterceptedService<Channel, ServiceTokenInterceptor>>
 }
 
 impl WluRemoteClientImpl {
-    pub async fn connect(addr: String) -> Result<Self, Box<dyn Error>> {
-        let client = WhiteListedUserServiceClient::connect(addr).await?;
+    pub async fn connect(addr: String, jwt_keys: Arc<JwtKeys>) -> Result<Self, Box<dyn Error>> {
+        let channel = Channel::from_shared(addr)?.connect().await?;
+        let client = WhiteListedUserServiceClient::with_interceptor(channel, ServiceTokenInterceptor::new(jwt_keys));
         Ok(Self { client })
     }
 }
@@ -2,9 +2,9 @@ use std::sync::Arc;
 use derive_new::new;
 use tonic::{Request, Response, Status};
 use homelab_proto::admin::console_file_service_server::ConsoleFileService;
-use homelab_proto::admin::{ConsoleFileListResponse, ConsoleFileResponse, GetAllFileVersionsRequest, GetLatestFileVersionRequest};
+use homelab_proto::admin::{ConsoleFileListResponse, ConsoleFileResponse, ConsoleIntegrityIssueListResponse, ConsolePreviewFailureListResponse, GetAllFileVersionsRequest, GetFileIntegrityIssuesRequest, GetLatestFileVersionRequest};
 use crate::AppState;
-use crate::helpers::proto_mappers::{map_console_file, map_entity_id};
+use crate::helpers::proto_mappers::{map_console_file, map_console_integrity_issue, map_console_preview_failure, map_entity_id};
 
 #[derive(new)]
 pub struct GrpcFileService {
@@ -43,4 +43,31 @@ impl ConsoleFileService for GrpcFileService {
         
         Ok(Response::new(ConsoleFileListResponse { files: proto_files }))
     }
+    async fn get_integrity_issues(&self, _: Request<()>) -> Result<Response<ConsoleIntegrityIssueListResponse>, Status> {
+        let issues = self.app_state.integrity_service.get_all_issues().await?;
+
+        let proto_issues = issues.into_iter().map(|i| map_console_integrity_issue(i)).collect();
+
+        Ok(Response::new(ConsoleIntegrityIssueListResponse { issues: proto_issues }))
+    }
+
+    async fn get_file_integrity_issues(&self, request: Request<GetFileIntegrityIssuesRequest>) -> Result<Response<ConsoleIntegrityIssueListResponse>, Status> {
+        let req = request.into_inner();
+
+        let file_id = map_entity_id(req.file_id)?;
+
+        let issues = self.app_state.integrity_service.get_issues_for_file(file_id).await?;
+
+        let proto_issues = issues.into_iter().map(|i| map_console_integrity_issue(i)).collect();
+
+        Ok(Response::new(ConsoleIntegrityIssueListResponse { issues: proto_issues }))
+    }
+
+    async fn get_preview_failures(&self, _: Request<()>) -> Result<Response<ConsolePreviewFailureListResponse>, Status> {
+        let failures = self.app_state.preview_failure_service.get_all_failures().await?;
+
+        let proto_failures = failures.into_iter().map(|f| map_console_preview_failure(f)).collect();
+
+        Ok(Response::new(ConsolePreviewFailureListResponse { failures: proto_failures }))
+    }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
 pub mod user_grpc_service;
 pub mod wlu_grpc_service;
 pub mod clients;
-mod file_grpc_service;
\ No newline at end of file
+pub mod file_grpc_service;
\ No newline at end of file
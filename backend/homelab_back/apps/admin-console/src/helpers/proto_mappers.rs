@@ -1,11 +1,14 @@
 use homelab_core::admin_domain::console_user::ConsoleUser;
-use homelab_proto::admin::{ConsoleFileResponse, ConsoleUserResponse, ConsoleWluResponse};
+use homelab_proto::admin::{ConsoleFileResponse, ConsoleIntegrityIssueResponse, ConsolePreviewFailureResponse, ConsoleUserResponse, ConsoleWluResponse, IntegrityStatus as ProtoIntegrityStatus};
 use homelab_proto::common::EntityId;
 use tonic::Status;
 use uuid::Uuid;
 use homelab_core::admin_domain::console_file::ConsoleFile;
+use homelab_core::admin_domain::console_integrity_issue::ConsoleIntegrityIssue;
+use homelab_core::admin_domain::console_preview_failure::ConsolePreviewFailure;
 use homelab_core::admin_domain::console_wlu::ConsoleWhiteListedUser;
 use homelab_core::file::{FileType as DomainFileType, UploadStatus as DomainUploadStatus};
+use homelab_core::file_integrity::IntegrityStatus as DomainIntegrityStatus;
 use homelab_proto::nas::{
     FileType as ProtoFileType, UploadStatus as ProtoUploadStatus,
 };
@@ -86,6 +89,40 @@ pub fn map_console_wlu(u: ConsoleWhiteListedUser) -> ConsoleWluResponse {
     }
 }
 
+pub fn map_console_integrity_issue(i: ConsoleIntegrityIssue) -> ConsoleIntegrityIssueResponse {
+    ConsoleIntegrityIssueResponse {
+        id: Option::from(map_id_to_proto(i.id)),
+        file_id: Option::from(map_id_to_proto(i.file_id)),
+        owner_id: Option::from(map_id_to_proto(i.owner_id)),
+        status: match i.status {
+            DomainIntegrityStatus::Ok => ProtoIntegrityStatus::Ok,
+            DomainIntegrityStatus::Missing => ProtoIntegrityStatus::Missing,
+            DomainIntegrityStatus::Corrupted => ProtoIntegrityStatus::Corrupted,
+        } as i32,
+        expected_checksum: i.expected_checksum,
+        actual_checksum: i.actual_checksum,
+        detected_at: Some(prost_types::Timestamp {
+            seconds: i.detected_at.unix_timestamp(),
+            nanos: i.detected_at.nanosecond() as i32,
+        }),
+    }
+}
+
+pub fn map_console_preview_failure(f: ConsolePreviewFailure) -> ConsolePreviewFailureResponse {
+    ConsolePreviewFailureResponse {
+        id: Option::from(map_id_to_proto(f.id)),
+        file_id: Option::from(map_id_to_proto(f.file_id)),
+        owner_id: Option::from(map_id_to_proto(f.owner_id)),
+        file_name: f.file_name,
+        attempts: f.attempts,
+        error: f.error,
+        failed_at: Some(prost_types::Timestamp {
+            seconds: f.failed_at.unix_timestamp(),
+            nanos: f.failed_at.nanosecond() as i32,
+        }),
+    }
+}
+
 pub fn map_id_to_proto(id: Uuid) -> EntityId {
     EntityId {
         value: id.to_string(),
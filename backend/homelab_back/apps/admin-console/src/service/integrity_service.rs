@@ -0,0 +1,45 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use derive_new::new;
+use uuid::Uuid;
+use homelab_core::admin_domain::console_integrity_issue::ConsoleIntegrityIssue;
+use homelab_core::events::FileIntegrityFailedEvent;
+use crate::db::integrity_issue_repo::IntegrityIssueRepo;
+use crate::helpers::data_error::DataError;
+
+#[async_trait]
+pub trait IntegrityService: Send + Sync {
+    async fn log_issue(&self, event: FileIntegrityFailedEvent) -> Result<(), DataError>;
+    async fn get_all_issues(&self) -> Result<Vec<ConsoleIntegrityIssue>, DataError>;
+    async fn get_issues_for_file(&self, file_id: Uuid) -> Result<Vec<ConsoleIntegrityIssue>, DataError>;
+}
+
+#[derive(new)]
+pub struct IntegrityServiceImpl {
+    integrity_issue_repo: Arc<dyn IntegrityIssueRepo>
+}
+
+#[async_trait]
+impl IntegrityService for IntegrityServiceImpl {
+    async fn log_issue(&self, event: FileIntegrityFailedEvent) -> Result<(), DataError> {
+        let issue = ConsoleIntegrityIssue::new(
+            Uuid::new_v4(),
+            event.file_id,
+            event.owner_id,
+            event.status,
+            event.expected_checksum,
+            event.actual_checksum,
+            event.detected_at
+        );
+
+        self.integrity_issue_repo.log_issue(issue).await
+    }
+
+    async fn get_all_issues(&self) -> Result<Vec<ConsoleIntegrityIssue>, DataError> {
+        self.integrity_issue_repo.get_issues().await
+    }
+
+    async fn get_issues_for_file(&self, file_id: Uuid) -> Result<Vec<ConsoleIntegrityIssue>, DataError> {
+        self.integrity_issue_repo.get_issues_for_file(file_id).await
+    }
+}
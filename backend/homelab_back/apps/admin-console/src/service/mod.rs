@@ -1,3 +1,5 @@
 pub mod wlu_service;
 pub mod user_service;
-pub mod file_service;
\ No newline at end of file
+pub mod file_service;
+pub mod integrity_service;
+pub mod preview_failure_service;
\ No newline at end of file
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use derive_new::new;
+use uuid::Uuid;
+use homelab_core::admin_domain::console_preview_failure::ConsolePreviewFailure;
+use homelab_core::events::FilePreviewFailedEvent;
+use crate::db::preview_failure_repo::PreviewFailureRepo;
+use crate::helpers::data_error::DataError;
+
+#[async_trait]
+pub trait PreviewFailureService: Send + Sync {
+    async fn log_failure(&self, event: FilePreviewFailedEvent) -> Result<(), DataError>;
+    async fn get_all_failures(&self) -> Result<Vec<ConsolePreviewFailure>, DataError>;
+}
+
+#[derive(new)]
+pub struct PreviewFailureServiceImpl {
+    preview_failure_repo: Arc<dyn PreviewFailureRepo>
+}
+
+#[async_trait]
+impl PreviewFailureService for PreviewFailureServiceImpl {
+    async fn log_failure(&self, event: FilePreviewFailedEvent) -> Result<(), DataError> {
+        let failure = ConsolePreviewFailure::new(
+            Uuid::new_v4(),
+            event.file_id,
+            event.owner_id,
+            event.file_name,
+            event.attempts,
+            event.error,
+            event.failed_at
+        );
+
+        self.preview_failure_repo.log_failure(failure).await
+    }
+
+    async fn get_all_failures(&self) -> Result<Vec<ConsolePreviewFailure>, DataError> {
+        self.preview_failure_repo.get_failures().await
+    }
+}
@@ -0,0 +1,88 @@
+use dotenvy::dotenv;
+use homelab_core::blob::Blob;
+use homelab_core::file::{build_preview_key, UploadStatus};
+use nas_server::db::blob_repository::{BlobRepository, BlobRepositoryImpl};
+use nas_server::db::file_repository::{FileRepository, FileRepositoryImpl};
+use nas_server::storage::encrypted_storage_backend::EncryptedStorageBackend;
+use nas_server::storage::storage_config::{build_base_storage_backend, load_master_key};
+use sqlx::postgres::PgPoolOptions;
+use std::env;
+use std::path::PathBuf;
+
+// Encrypts content stored before encryption at rest was turned on, objects that are already encrypted are skipped
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set in .env file");
+
+    let storage_dir =
+        env::var("ROOT_FOLDER_PATH").expect("ROOT_FOLDER_PATH must be set in .env file");
+
+    let master_key = load_master_key().ok_or("ENCRYPTION_MASTER_KEY must be set in .env file")?;
+
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&database_url)
+        .await
+        .expect("Failed to create database pool");
+
+    let storage = EncryptedStorageBackend::new(
+        build_base_storage_backend(PathBuf::from(storage_dir)),
+        master_key,
+    );
+
+    let blobs = BlobRepositoryImpl::new(pool.clone())
+        .get_all()
+        .await
+        .map_err(|e| format!("Failed to load blobs: {:?}", e))?;
+
+    let files = FileRepositoryImpl::new(pool.clone())
+        .get_all_including_deleted()
+        .await
+        .map_err(|e| format!("Failed to load files: {:?}", e))?;
+
+    let mut keys = Vec::new();
+
+    for blob in blobs {
+        keys.push(Blob::storage_key(blob.id));
+        keys.push(blob.preview_key());
+    }
+
+    // Files stored before blobs existed keep their content under their own id
+    for file in files {
+        if file.blob_id.is_none() && file.upload_status == UploadStatus::Completed {
+            let key = file.upload_key();
+
+            keys.push(build_preview_key(&key));
+            keys.push(key);
+        }
+    }
+
+    let mut encrypted = 0;
+    let mut failed = 0;
+
+    for key in &keys {
+        match storage.encrypt_in_place(key).await {
+            Ok(true) => encrypted += 1,
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("Failed to encrypt {}: {:?}", key, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "🔒 Encrypted {} of {} stored objects, {} failed",
+        encrypted,
+        keys.len(),
+        failed
+    );
+
+    if failed > 0 {
+        return Err(format!("{} objects could not be encrypted", failed).into());
+    }
+
+    Ok(())
+}
@@ -37,6 +37,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         user_id,
         "admin@homelab.local",
         "Admin User",
+        // Legacy format (password: "password"), rehashed with Argon2 on first login
         "hashed_password"
     )
         .execute(&pool)
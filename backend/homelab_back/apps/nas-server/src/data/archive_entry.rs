@@ -0,0 +1,12 @@
+use derive_new::new;
+use serde::Serialize;
+use sqlx::types::time::OffsetDateTime;
+
+// One entry inside an uploaded zip or tar archive, paths use "/" whatever the archive was made on
+#[derive(Debug, Clone, Serialize, new)]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub size: u64,
+    pub modified_at: Option<OffsetDateTime>,
+    pub is_dir: bool,
+}
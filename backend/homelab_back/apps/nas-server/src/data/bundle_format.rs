@@ -0,0 +1,25 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleFormat {
+    #[default]
+    Zip,
+    TarGz,
+}
+
+impl BundleFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BundleFormat::Zip => "zip",
+            BundleFormat::TarGz => "tar.gz",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            BundleFormat::Zip => "application/zip",
+            BundleFormat::TarGz => "application/gzip",
+        }
+    }
+}
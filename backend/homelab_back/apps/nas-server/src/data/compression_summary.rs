@@ -0,0 +1,9 @@
+use derive_new::new;
+use serde::Serialize;
+
+#[derive(Debug, Default, Serialize, new)]
+pub struct CompressionSummary {
+    pub compressed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
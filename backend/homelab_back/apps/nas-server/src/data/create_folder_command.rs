@@ -6,5 +6,4 @@ use uuid::Uuid;
 pub struct CreateFolderCommand {
     pub parent_folder_id: Uuid,
     pub name: String,
-    pub owner_id: Uuid,
 }
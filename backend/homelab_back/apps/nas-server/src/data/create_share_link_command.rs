@@ -0,0 +1,13 @@
+use serde::Deserialize;
+use sqlx::types::time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateShareLinkCommand {
+    pub file_id: Option<Uuid>,
+    pub folder_id: Option<Uuid>,
+    pub password: Option<String>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+    pub max_downloads: Option<i32>,
+}
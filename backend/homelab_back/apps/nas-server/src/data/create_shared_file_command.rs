@@ -1,9 +1,11 @@
+use derive_new::new;
+use homelab_core::shared_file::SharedFileAccessType;
 use serde::Deserialize;
 use uuid::Uuid;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, new)]
 pub struct CreateSharedFileCommand {
     pub user_id: Uuid,
-    pub owner_id: Uuid,
     pub file_id: Uuid,
+    pub access_type: SharedFileAccessType,
 }
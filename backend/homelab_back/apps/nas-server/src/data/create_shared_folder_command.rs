@@ -0,0 +1,11 @@
+use derive_new::new;
+use homelab_core::shared_file::SharedFileAccessType;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, new)]
+pub struct CreateSharedFolderCommand {
+    pub user_id: Uuid,
+    pub folder_id: Uuid,
+    pub access_type: SharedFileAccessType,
+}
@@ -0,0 +1,10 @@
+use crate::data::bundle_format::BundleFormat;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadBundleCommand {
+    pub files_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub format: BundleFormat,
+}
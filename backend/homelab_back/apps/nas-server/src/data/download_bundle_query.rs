@@ -0,0 +1,8 @@
+use crate::data::bundle_format::BundleFormat;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadBundleQuery {
+    #[serde(default)]
+    pub format: BundleFormat,
+}
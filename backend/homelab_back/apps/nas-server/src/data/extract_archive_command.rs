@@ -0,0 +1,11 @@
+use derive_new::new;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, new)]
+pub struct ExtractArchiveCommand {
+    pub target_folder_id: Uuid,
+    // Paths of the entries to extract, a directory brings everything below it along. Empty extracts all
+    #[serde(default)]
+    pub entries: Vec<String>,
+}
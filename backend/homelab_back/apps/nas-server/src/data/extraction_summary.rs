@@ -0,0 +1,9 @@
+use derive_new::new;
+use serde::Serialize;
+
+#[derive(Debug, Default, Serialize, new)]
+pub struct ExtractionSummary {
+    pub folders_created: usize,
+    pub files_created: usize,
+    pub extracted_bytes: u64,
+}
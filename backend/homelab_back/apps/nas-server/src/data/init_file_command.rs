@@ -5,8 +5,9 @@ use uuid::Uuid;
 #[derive(Debug, Deserialize, new)]
 pub struct InitFileCommand {
     pub destination: Uuid,
-    pub owner_id: Uuid,
     pub name: String,
     pub expected_size: i64,
     pub is_global: bool,
+    #[serde(default)]
+    pub checksum: Option<String>,
 }
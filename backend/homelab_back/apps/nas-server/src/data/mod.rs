@@ -1,15 +1,36 @@
+pub mod archive_entry;
+pub mod bundle_format;
 pub mod change_label_command;
+pub mod compression_summary;
 pub mod copy_file_command;
 pub mod create_file_label_command;
 pub mod create_folder_command;
 pub mod create_label_command;
+pub mod create_share_link_command;
 pub mod create_shared_file_command;
+pub mod create_shared_folder_command;
 pub mod delete_chosen_files_command;
 pub mod delete_chosen_folders_command;
+pub mod download_bundle_command;
+pub mod download_bundle_query;
+pub mod extract_archive_command;
+pub mod extraction_summary;
 pub mod filter_files_by_filetype_command;
 pub mod init_file_command;
 pub mod move_file_command;
 pub mod move_folder_command;
+pub mod preview;
+pub mod reconciliation_options;
+pub mod reconciliation_report;
+pub mod regenerate_previews_command;
+pub mod scrub_summary;
 pub mod search_query;
+pub mod set_compression_policy_command;
+pub mod share_link_content;
+pub mod share_link_query;
+pub mod update_file_content_command;
 pub mod update_file_name_command;
 pub mod update_folder_name_command;
+pub mod upload_chunk_query;
+pub mod usage_recalculation_summary;
+pub mod version_retention_policy;
@@ -0,0 +1,9 @@
+use derive_new::new;
+
+// Thumbnails are small, so they are handed out whole rather than streamed
+#[derive(Debug, new)]
+pub struct Preview {
+    pub content: Vec<u8>,
+    pub content_type: String,
+    pub etag: String,
+}
@@ -0,0 +1,9 @@
+use derive_new::new;
+use std::time::Duration;
+
+#[derive(Debug, Clone, new)]
+pub struct ReconciliationOptions {
+    pub repair: bool,
+    // Anything younger than this may still belong to an upload in flight and is left alone
+    pub stale_after: Duration,
+}
@@ -0,0 +1,32 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Default, Serialize)]
+pub struct ReconciliationReport {
+    pub orphan_blobs: Vec<String>,
+    pub stale_temp_files: Vec<String>,
+    pub orphan_partial_uploads: Vec<String>,
+    pub stray_previews: Vec<String>,
+    pub missing_blobs: Vec<Uuid>,
+    pub misplaced_blobs: Vec<Uuid>,
+    pub stuck_uploads: Vec<Uuid>,
+    pub unreferenced_blobs: Vec<Uuid>,
+    pub repaired: bool,
+}
+
+impl ReconciliationReport {
+    pub fn drift_count(&self) -> usize {
+        self.orphan_blobs.len()
+            + self.stale_temp_files.len()
+            + self.orphan_partial_uploads.len()
+            + self.stray_previews.len()
+            + self.missing_blobs.len()
+            + self.misplaced_blobs.len()
+            + self.stuck_uploads.len()
+            + self.unreferenced_blobs.len()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.drift_count() == 0
+    }
+}
@@ -0,0 +1,10 @@
+use derive_new::new;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, new)]
+pub struct RegeneratePreviewsCommand {
+    // Every file when left empty
+    pub file_ids: Vec<Uuid>,
+    pub only_failed: bool,
+}
@@ -0,0 +1,9 @@
+use derive_new::new;
+use serde::Serialize;
+
+#[derive(Debug, Default, Serialize, new)]
+pub struct ScrubSummary {
+    pub checked: usize,
+    pub missing: usize,
+    pub corrupted: usize,
+}
@@ -0,0 +1,14 @@
+use derive_new::new;
+use homelab_core::file::{ArchiveCodec, FileType};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, new)]
+pub struct SetCompressionPolicyCommand {
+    pub min_idle_days: i32,
+    pub min_size: i64,
+    pub codec: ArchiveCodec,
+    // Types whose content is compressed already and would not shrink anyway
+    #[serde(default)]
+    pub excluded_types: Vec<FileType>,
+    pub is_enabled: bool,
+}
@@ -0,0 +1,11 @@
+use derive_new::new;
+use homelab_core::file::File;
+use homelab_core::folder::Folder;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, new)]
+pub struct ShareLinkContent {
+    pub file: Option<File>,
+    pub folders: Vec<Folder>,
+    pub files: Vec<File>,
+}
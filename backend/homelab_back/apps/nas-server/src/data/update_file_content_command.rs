@@ -0,0 +1,31 @@
+use derive_new::new;
+use homelab_core::file::File;
+use sqlx::types::time::OffsetDateTime;
+
+// What the client last saw of the file, the new content is only accepted while the stored copy still matches
+#[derive(Debug, Clone, new)]
+pub struct UpdateFileContentCommand {
+    pub expected_updated_at: Option<OffsetDateTime>,
+    pub expected_checksum: Option<String>,
+}
+
+impl UpdateFileContentCommand {
+    pub fn has_expectation(&self) -> bool {
+        self.expected_updated_at.is_some() || self.expected_checksum.is_some()
+    }
+
+    pub fn is_met_by(&self, f: &File) -> bool {
+        let updated_at_matches = self
+            .expected_updated_at
+            .is_none_or(|expected| expected == f.updated_at);
+
+        // A file without a recorded checksum cannot prove it is the copy the client saw
+        let checksum_matches = self.expected_checksum.as_ref().is_none_or(|expected| {
+            f.checksum
+                .as_ref()
+                .is_some_and(|actual| actual.eq_ignore_ascii_case(expected))
+        });
+
+        updated_at_matches && checksum_matches
+    }
+}
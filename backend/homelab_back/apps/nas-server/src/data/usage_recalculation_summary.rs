@@ -0,0 +1,8 @@
+use derive_new::new;
+use serde::Serialize;
+
+#[derive(Debug, Default, Serialize, new)]
+pub struct UsageRecalculationSummary {
+    pub checked: usize,
+    pub corrected: usize,
+}
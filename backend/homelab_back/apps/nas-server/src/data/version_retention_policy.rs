@@ -0,0 +1,10 @@
+use derive_new::new;
+use std::time::Duration;
+
+#[derive(Debug, Clone, new)]
+pub struct VersionRetentionPolicy {
+    // Newest versions kept per file, older ones are pruned regardless of their age
+    pub keep_count: i64,
+    // Versions older than this are pruned even if the file has fewer than `keep_count`
+    pub keep_for: Duration,
+}
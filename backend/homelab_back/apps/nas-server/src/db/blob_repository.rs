@@ -0,0 +1,170 @@
+use crate::helpers::data_error::DataError;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::blob::Blob;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+#[async_trait]
+pub trait BlobRepository: Send + Sync {
+    async fn get_all(&self) -> Result<Vec<Blob>, DataError>;
+    async fn get_by_id(&self, blob_id: Uuid) -> Result<Option<Blob>, DataError>;
+    async fn acquire(&self, checksum: &str, size: i64) -> Result<Blob, DataError>;
+    async fn add_ref(&self, blob_id: Uuid) -> Result<Option<Blob>, DataError>;
+    async fn release(&self, blob_id: Uuid) -> Result<Option<Blob>, DataError>;
+    async fn delete_unreferenced(&self, blob_id: Uuid) -> Result<Option<Blob>, DataError>;
+}
+
+#[derive(new)]
+pub struct BlobRepositoryImpl {
+    pool: PgPool,
+}
+
+#[async_trait]
+impl BlobRepository for BlobRepositoryImpl {
+    async fn get_all(&self) -> Result<Vec<Blob>, DataError> {
+        let blobs = sqlx::query_as!(
+            Blob,
+            r#"
+            SELECT id, checksum, size, ref_count, created_at
+            FROM blobs
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(blobs)
+    }
+
+    async fn get_by_id(&self, blob_id: Uuid) -> Result<Option<Blob>, DataError> {
+        let blob = sqlx::query_as!(
+            Blob,
+            r#"
+            SELECT id, checksum, size, ref_count, created_at
+            FROM blobs
+            WHERE id = $1
+            "#,
+            blob_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(blob)
+    }
+
+    // Takes a reference on the blob holding this content, the blob is created when the content is new
+    async fn acquire(&self, checksum: &str, size: i64) -> Result<Blob, DataError> {
+        let blob = sqlx::query_as!(
+            Blob,
+            r#"
+            INSERT INTO blobs (id, checksum, size, ref_count)
+            VALUES ($1, $2, $3, 1)
+            ON CONFLICT (checksum) DO UPDATE SET ref_count = blobs.ref_count + 1
+            RETURNING id, checksum, size, ref_count, created_at
+            "#,
+            Uuid::new_v4(),
+            checksum,
+            size
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(blob)
+    }
+
+    async fn add_ref(&self, blob_id: Uuid) -> Result<Option<Blob>, DataError> {
+        let blob = sqlx::query_as!(
+            Blob,
+            r#"
+            UPDATE blobs
+            SET ref_count = ref_count + 1
+            WHERE id = $1
+            RETURNING id, checksum, size, ref_count, created_at
+            "#,
+            blob_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(blob)
+    }
+
+    async fn release(&self, blob_id: Uuid) -> Result<Option<Blob>, DataError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        let blob = release_blob_ref(&mut tx, blob_id).await?;
+
+        tx.commit().await.map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(blob)
+    }
+
+    // Drops a blob that no file or version points at, e.g. one acquired by an upload that never completed
+    async fn delete_unreferenced(&self, blob_id: Uuid) -> Result<Option<Blob>, DataError> {
+        let blob = sqlx::query_as!(
+            Blob,
+            r#"
+            DELETE FROM blobs b
+            WHERE b.id = $1
+              AND NOT EXISTS (SELECT 1 FROM files f WHERE f.blob_id = b.id)
+              AND NOT EXISTS (SELECT 1 FROM file_versions v WHERE v.blob_id = b.id)
+            RETURNING b.id, b.checksum, b.size, b.ref_count, b.created_at
+            "#,
+            blob_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(blob)
+    }
+}
+
+/// Gives one reference back inside the caller's transaction. Returns the blob
+/// once its last reference is gone, its content can be removed from disk then.
+pub async fn release_blob_ref(
+    conn: &mut PgConnection,
+    blob_id: Uuid,
+) -> Result<Option<Blob>, DataError> {
+    let remaining = sqlx::query_scalar!(
+        r#"
+        UPDATE blobs
+        SET ref_count = ref_count - 1
+        WHERE id = $1 AND ref_count > 0
+        RETURNING ref_count
+        "#,
+        blob_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| DataError::DatabaseError(e))?;
+
+    if remaining != Some(0) {
+        return Ok(None);
+    }
+
+    let blob = sqlx::query_as!(
+        Blob,
+        r#"
+        DELETE FROM blobs b
+        WHERE b.id = $1 AND b.ref_count = 0
+          AND NOT EXISTS (SELECT 1 FROM files f WHERE f.blob_id = b.id)
+          AND NOT EXISTS (SELECT 1 FROM file_versions v WHERE v.blob_id = b.id)
+        RETURNING b.id, b.checksum, b.size, b.ref_count, b.created_at
+        "#,
+        blob_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| DataError::DatabaseError(e))?;
+
+    Ok(blob)
+}
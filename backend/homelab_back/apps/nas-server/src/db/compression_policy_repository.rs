@@ -0,0 +1,146 @@
+use crate::helpers::data_error::DataError;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::compression_policy::CompressionPolicy;
+use homelab_core::file::FileType;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait CompressionPolicyRepository: Send + Sync {
+    async fn get_all_enabled(&self) -> Result<Vec<CompressionPolicy>, DataError>;
+    async fn get_by_user_id(
+        &self,
+        user_id: Option<Uuid>,
+    ) -> Result<Option<CompressionPolicy>, DataError>;
+    async fn get_effective(&self, user_id: Uuid) -> Result<Option<CompressionPolicy>, DataError>;
+    async fn save(&self, policy: CompressionPolicy) -> Result<CompressionPolicy, DataError>;
+    async fn update(&self, policy: CompressionPolicy) -> Result<CompressionPolicy, DataError>;
+    async fn delete_by_user_id(&self, user_id: Option<Uuid>) -> Result<bool, DataError>;
+}
+
+#[derive(new)]
+pub struct CompressionPolicyRepositoryImpl {
+    pool: PgPool,
+}
+
+#[async_trait]
+impl CompressionPolicyRepository for CompressionPolicyRepositoryImpl {
+    async fn get_all_enabled(&self) -> Result<Vec<CompressionPolicy>, DataError> {
+        let policies = sqlx::query_as!(
+            CompressionPolicy,
+            r#"
+            SELECT id, user_id, min_idle_days, min_size, codec as "codec: _", excluded_types as "excluded_types: Vec<FileType>", is_enabled, updated_at
+            FROM compression_policies
+            WHERE is_enabled = TRUE
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(policies)
+    }
+
+    async fn get_by_user_id(
+        &self,
+        user_id: Option<Uuid>,
+    ) -> Result<Option<CompressionPolicy>, DataError> {
+        let policy = sqlx::query_as!(
+            CompressionPolicy,
+            r#"
+            SELECT id, user_id, min_idle_days, min_size, codec as "codec: _", excluded_types as "excluded_types: Vec<FileType>", is_enabled, updated_at
+            FROM compression_policies
+            WHERE user_id IS NOT DISTINCT FROM $1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(policy)
+    }
+
+    async fn get_effective(&self, user_id: Uuid) -> Result<Option<CompressionPolicy>, DataError> {
+        // The user's own policy wins over the global one, even when it is disabled
+        let policy = sqlx::query_as!(
+            CompressionPolicy,
+            r#"
+            SELECT id, user_id, min_idle_days, min_size, codec as "codec: _", excluded_types as "excluded_types: Vec<FileType>", is_enabled, updated_at
+            FROM compression_policies
+            WHERE user_id = $1 OR user_id IS NULL
+            ORDER BY user_id NULLS LAST
+            LIMIT 1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(policy)
+    }
+
+    async fn save(&self, policy: CompressionPolicy) -> Result<CompressionPolicy, DataError> {
+        let policy = sqlx::query_as!(
+            CompressionPolicy,
+            r#"
+            INSERT INTO compression_policies (id, user_id, min_idle_days, min_size, codec, excluded_types, is_enabled, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, user_id, min_idle_days, min_size, codec as "codec: _", excluded_types as "excluded_types: Vec<FileType>", is_enabled, updated_at
+            "#,
+            policy.id,
+            policy.user_id,
+            policy.min_idle_days,
+            policy.min_size,
+            policy.codec as _,
+            &policy.excluded_types as &[FileType],
+            policy.is_enabled,
+            policy.updated_at
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(policy)
+    }
+
+    async fn update(&self, policy: CompressionPolicy) -> Result<CompressionPolicy, DataError> {
+        let policy = sqlx::query_as!(
+            CompressionPolicy,
+            r#"
+            UPDATE compression_policies
+            SET min_idle_days = $2, min_size = $3, codec = $4, excluded_types = $5, is_enabled = $6, updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, user_id, min_idle_days, min_size, codec as "codec: _", excluded_types as "excluded_types: Vec<FileType>", is_enabled, updated_at
+            "#,
+            policy.id,
+            policy.min_idle_days,
+            policy.min_size,
+            policy.codec as _,
+            &policy.excluded_types as &[FileType],
+            policy.is_enabled
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(policy)
+    }
+
+    async fn delete_by_user_id(&self, user_id: Option<Uuid>) -> Result<bool, DataError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM compression_policies
+            WHERE user_id IS NOT DISTINCT FROM $1
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,83 @@
+use crate::helpers::data_error::DataError;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::file::File;
+use homelab_core::file_integrity::FileIntegrity;
+use sqlx::PgPool;
+
+#[async_trait]
+pub trait FileIntegrityRepository: Send + Sync {
+    async fn get_files_due_for_check(&self, limit: i64) -> Result<Vec<File>, DataError>;
+    async fn save(&self, integrity: &FileIntegrity) -> Result<FileIntegrity, DataError>;
+}
+
+#[derive(new)]
+pub struct FileIntegrityRepositoryImpl {
+    pool: PgPool,
+}
+
+#[async_trait]
+impl FileIntegrityRepository for FileIntegrityRepositoryImpl {
+    async fn get_files_due_for_check(&self, limit: i64) -> Result<Vec<File>, DataError> {
+        // Files that were never scrubbed come first, then the ones checked the longest time ago
+        let files = sqlx::query_as!(
+            File,
+            r#"
+            SELECT
+                f.id,
+                f.name,
+                f.owner_id,
+                f.parent_folder_id,
+                f.file_type as "file_type: _",
+                f.is_deleted,
+                f.ttl,
+                f.size,
+                f.upload_status as "upload_status: _",
+                f.created_at,
+                f.updated_at,
+                f.checksum,
+                f.blob_id,
+                f.archive_codec as "archive_codec: _",
+                f.original_size,
+                f.preview_status as "preview_status: _"
+            FROM files f
+            LEFT JOIN file_integrity fi ON fi.file_id = f.id
+            WHERE f.upload_status = 'completed' AND f.is_deleted = FALSE
+            ORDER BY fi.checked_at ASC NULLS FIRST
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(files)
+    }
+
+    async fn save(&self, integrity: &FileIntegrity) -> Result<FileIntegrity, DataError> {
+        let integrity = sqlx::query_as!(
+            FileIntegrity,
+            r#"
+            INSERT INTO file_integrity (file_id, status, expected_checksum, actual_checksum, checked_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (file_id) DO UPDATE
+            SET status = EXCLUDED.status,
+                expected_checksum = EXCLUDED.expected_checksum,
+                actual_checksum = EXCLUDED.actual_checksum,
+                checked_at = EXCLUDED.checked_at
+            RETURNING file_id, status as "status: _", expected_checksum, actual_checksum, checked_at
+            "#,
+            integrity.file_id,
+            integrity.status as _,
+            integrity.expected_checksum,
+            integrity.actual_checksum,
+            integrity.checked_at
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(integrity)
+    }
+}
@@ -1,7 +1,12 @@
+use crate::db::blob_repository::release_blob_ref;
 use crate::helpers::data_error::DataError;
 use async_trait::async_trait;
 use derive_new::new;
-use homelab_core::file::File;
+use homelab_core::blob::Blob;
+use homelab_core::compression_policy::CompressionPolicy;
+use homelab_core::file::{File, FileType, PreviewStatus};
+use homelab_core::file_version::FileVersion;
+use sqlx::types::time::OffsetDateTime;
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -11,7 +16,11 @@ pub trait FileRepository: Send + Sync {
     async fn get_all_deleted(&self, user_id: Uuid) -> Result<Vec<File>, DataError>;
     async fn get_deleted_by_id (&self, file_id: Uuid) -> Result<Option<File>, DataError>;
     async fn get_all_by_ids(&self, file_ids: &[Uuid]) -> Result<Vec<File>, DataError>;
-    async fn search_by_name(&self, search_query: String) -> Result<Vec<File>, DataError>;
+    async fn search_by_name(
+        &self,
+        search_query: String,
+        owner_id: Uuid,
+    ) -> Result<Vec<File>, DataError>;
     async fn get_by_folder_and_file_name(
         &self,
         folder_id: Uuid,
@@ -20,13 +29,47 @@ pub trait FileRepository: Send + Sync {
     async fn save(&self, file: File) -> Result<File, DataError>;
     async fn update(&self, file: File) -> Result<File, DataError>;
     async fn delete_by_id(&self, file_id: Uuid) -> Result<(), DataError>;
-    async fn delete_by_ids(&self, file_ids: &[Uuid]) -> Result<(), DataError>;
+    async fn delete_by_ids(
+        &self,
+        file_ids: &[Uuid],
+    ) -> Result<(Vec<Blob>, Vec<FileVersion>), DataError>;
     async fn get_all_files_by_label(
         &self,
         label_id: Uuid,
         owner_id: Uuid,
     ) -> Result<Vec<File>, DataError>;
     async fn get_expired_files(&self) -> Result<Vec<File>, DataError>;
+    async fn backfill_checksum(&self, file_id: Uuid, checksum: &str) -> Result<(), DataError>;
+    async fn get_all_including_deleted(&self) -> Result<Vec<File>, DataError>;
+    async fn get_all_completed_by_owner(&self, owner_id: Uuid) -> Result<Vec<File>, DataError>;
+    async fn get_stale_pending_uploads(
+        &self,
+        older_than: OffsetDateTime,
+    ) -> Result<Vec<File>, DataError>;
+    async fn save_with_reservation(&self, file: File) -> Result<File, DataError>;
+    async fn complete_reservation(&self, file: File) -> Result<File, DataError>;
+    async fn release_reservation(&self, file: File) -> Result<File, DataError>;
+    async fn delete_pending(&self, file_id: Uuid) -> Result<Option<File>, DataError>;
+    async fn replace_content(
+        &self,
+        file: File,
+        previous_blob_id: Option<Uuid>,
+    ) -> Result<(File, Option<Blob>), DataError>;
+    async fn is_legacy_archive(&self, file_id: Uuid) -> Result<bool, DataError>;
+    async fn mark_accessed(&self, file_ids: &[Uuid]) -> Result<(), DataError>;
+    async fn get_compression_candidates(
+        &self,
+        policy: &CompressionPolicy,
+        idle_since: OffsetDateTime,
+        limit: i64,
+    ) -> Result<Vec<File>, DataError>;
+    async fn mark_compression_attempted(&self, file_id: Uuid) -> Result<(), DataError>;
+    async fn set_preview_status(
+        &self,
+        file: &File,
+        status: PreviewStatus,
+    ) -> Result<(), DataError>;
+    async fn inherit_preview_status(&self, file_id: Uuid) -> Result<PreviewStatus, DataError>;
 }
 
 #[derive(new)]
@@ -40,7 +83,7 @@ impl FileRepository for FileRepositoryImpl {
         let file = sqlx::query_as!(
         File,
             r#"
-            SELECT id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at
+            SELECT id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
             FROM files
             WHERE id = $1 AND is_deleted = FALSE
             "#,
@@ -57,7 +100,7 @@ impl FileRepository for FileRepositoryImpl {
         let f: Vec<File> = sqlx::query_as!(
             File,
             r#"
-            SELECT id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at
+            SELECT id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
             FROM files
             WHERE is_deleted = TRUE AND owner_id = $1
             "#,
@@ -74,7 +117,7 @@ impl FileRepository for FileRepositoryImpl {
         let f = sqlx::query_as!(
             File,
             r#"
-            SELECT id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at
+            SELECT id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
             FROM files
             WHERE id = $1
             "#,
@@ -91,7 +134,7 @@ impl FileRepository for FileRepositoryImpl {
         let f: Vec<File> = sqlx::query_as!(
             File,
             r#"
-            SELECT id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at
+            SELECT id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
             FROM files
             WHERE is_deleted = FALSE AND id = ANY($1)
             "#,
@@ -104,15 +147,20 @@ impl FileRepository for FileRepositoryImpl {
         Ok(f)
     }
 
-    async fn search_by_name(&self, search_query: String) -> Result<Vec<File>, DataError> {
+    async fn search_by_name(
+        &self,
+        search_query: String,
+        owner_id: Uuid,
+    ) -> Result<Vec<File>, DataError> {
         let f: Vec<File> = sqlx::query_as!(
             File,
             r#"
-            SELECT id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at
+            SELECT id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
             FROM files
-            WHERE LOWER(name) LIKE LOWER($1) AND is_deleted = FALSE
+            WHERE LOWER(name) LIKE LOWER($1) AND owner_id = $2 AND is_deleted = FALSE
             "#,
-            search_query
+            search_query,
+            owner_id
         )
             .fetch_all(&self.pool)
             .await
@@ -129,7 +177,7 @@ impl FileRepository for FileRepositoryImpl {
         let file = sqlx::query_as!(
             File,
             r#"
-            SELECT id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at
+            SELECT id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
             FROM files
             WHERE parent_folder_id = $1 AND name = $2 AND is_deleted = FALSE
             "#,
@@ -147,9 +195,9 @@ impl FileRepository for FileRepositoryImpl {
         let f = sqlx::query_as!(
         File,
         r#"
-        INSERT INTO files (id, name, owner_id, parent_folder_id, file_type, is_deleted, size, upload_status, created_at, updated_at)
-        VALUES ($1,$2, $3, $4, $5, $6, $7, $8, $9, $10)
-        RETURNING id, name, owner_id,parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at
+        INSERT INTO files (id, name, owner_id, parent_folder_id, file_type, is_deleted, size, upload_status, created_at, updated_at, checksum, blob_id, archive_codec, original_size, preview_status)
+        VALUES ($1,$2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        RETURNING id, name, owner_id,parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
         "#,
             file.id,
             file.name,
@@ -160,7 +208,12 @@ impl FileRepository for FileRepositoryImpl {
             file.size,
             file.upload_status as _,
             file.created_at,
-            file.updated_at
+            file.updated_at,
+            file.checksum,
+            file.blob_id,
+            file.archive_codec as _,
+            file.original_size,
+            file.preview_status as _
     )
             .fetch_one(&self.pool)
             .await
@@ -174,9 +227,9 @@ impl FileRepository for FileRepositoryImpl {
             File,
             r#"
             UPDATE files
-            SET name = $1, owner_id = $2, file_type = $3, parent_folder_id = $4, is_deleted = $5, ttl = $6, size = $7, upload_status = $8, created_at = $10, updated_at = $11
+            SET name = $1, owner_id = $2, file_type = $3, parent_folder_id = $4, is_deleted = $5, ttl = $6, size = $7, upload_status = $8, created_at = $10, updated_at = $11, checksum = $12
             WHERE id = $9
-            RETURNING id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at
+            RETURNING id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
             "#,
             file.name,
             file.owner_id,
@@ -188,7 +241,8 @@ impl FileRepository for FileRepositoryImpl {
             file.upload_status as _,
             file.id,
             file.created_at,
-            file.updated_at
+            file.updated_at,
+            file.checksum
         )
             .fetch_one(&self.pool)
             .await
@@ -211,19 +265,56 @@ impl FileRepository for FileRepositoryImpl {
         Ok(())
     }
 
-    async fn delete_by_ids(&self, file_ids: &[Uuid]) -> Result<(), DataError> {
-        sqlx::query!(
+    // Every removed row gives its blob reference back, the blobs nobody points at anymore are returned
+    async fn delete_by_ids(
+        &self,
+        file_ids: &[Uuid],
+    ) -> Result<(Vec<Blob>, Vec<FileVersion>), DataError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        // Versions go together with their file, each one gives its reference back as well
+        let versions = sqlx::query_as!(
+            FileVersion,
+            r#"
+        DELETE FROM file_versions
+        WHERE file_id = ANY($1)
+        RETURNING id, file_id, blob_id, size, checksum, created_at
+        "#,
+            file_ids
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        let blob_ids = sqlx::query_scalar!(
             r#"
         DELETE FROM files 
         WHERE id = ANY($1)
+        RETURNING blob_id
         "#,
             file_ids
         )
-        .execute(&self.pool)
+        .fetch_all(&mut *tx)
         .await
         .map_err(|e| DataError::DatabaseError(e))?;
 
-        Ok(())
+        let mut released = Vec::new();
+
+        let version_blob_ids = versions.iter().map(|v| v.blob_id);
+
+        for blob_id in blob_ids.into_iter().flatten().chain(version_blob_ids) {
+            if let Some(blob) = release_blob_ref(&mut tx, blob_id).await? {
+                released.push(blob);
+            }
+        }
+
+        tx.commit().await.map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok((released, versions))
     }
 
     async fn get_all_files_by_label(
@@ -245,7 +336,12 @@ impl FileRepository for FileRepositoryImpl {
                 f.size,
                 f.upload_status as "upload_status: _", 
                 f.created_at, 
-                f.updated_at
+                f.updated_at,
+                f.checksum,
+                f.blob_id,
+                f.archive_codec as "archive_codec: _",
+                f.original_size,
+                f.preview_status as "preview_status: _"
             FROM files f
             INNER JOIN file_labels fl ON f.id = fl.file_id
             WHERE fl.label_id = $1 AND f.owner_id = $2
@@ -265,7 +361,7 @@ impl FileRepository for FileRepositoryImpl {
             File,
             r#"
             SELECT id, name, owner_id, file_type as "file_type: _", parent_folder_id, 
-               is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at
+               is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
             FROM files
             WHERE is_deleted = TRUE 
               AND ttl IS NOT NULL 
@@ -278,4 +374,459 @@ impl FileRepository for FileRepositoryImpl {
 
         Ok(f)
     }
+
+    async fn backfill_checksum(&self, file_id: Uuid, checksum: &str) -> Result<(), DataError> {
+        // Never overwrites a digest that was stored in the meantime
+        sqlx::query!(
+            r#"
+            UPDATE files
+            SET checksum = $2
+            WHERE id = $1 AND checksum IS NULL
+            "#,
+            file_id,
+            checksum
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(())
+    }
+
+    async fn get_all_including_deleted(&self) -> Result<Vec<File>, DataError> {
+        let f = sqlx::query_as!(
+            File,
+            r#"
+            SELECT id, name, owner_id, file_type as "file_type: _", parent_folder_id,
+               is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
+            FROM files
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(f)
+    }
+
+    // Soft-deleted files are included, they are charged until the cleanup removes them
+    async fn get_all_completed_by_owner(&self, owner_id: Uuid) -> Result<Vec<File>, DataError> {
+        let f = sqlx::query_as!(
+            File,
+            r#"
+            SELECT id, name, owner_id, file_type as "file_type: _", parent_folder_id,
+               is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
+            FROM files
+            WHERE owner_id = $1 AND upload_status = 'completed'
+            "#,
+            owner_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(f)
+    }
+
+    // Resumable uploads count as alive for as long as chunks keep arriving
+    async fn get_stale_pending_uploads(
+        &self,
+        older_than: OffsetDateTime,
+    ) -> Result<Vec<File>, DataError> {
+        let f = sqlx::query_as!(
+            File,
+            r#"
+            SELECT f.id, f.name, f.owner_id, f.file_type as "file_type: _", f.parent_folder_id,
+               f.is_deleted, f.ttl, f.size, f.upload_status as "upload_status: _", f.created_at, f.updated_at, f.checksum, f.blob_id, f.archive_codec as "archive_codec: _", f.original_size, f.preview_status as "preview_status: _"
+            FROM files f
+            LEFT JOIN upload_sessions us ON us.file_id = f.id
+            WHERE f.upload_status = 'pending'
+              AND f.created_at < $1
+              AND (us.updated_at IS NULL OR us.updated_at < $1)
+            "#,
+            older_than
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(f)
+    }
+
+    // The row and the quota reservation for its size are created together or not at all
+    async fn save_with_reservation(&self, file: File) -> Result<File, DataError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        // The conditional update locks the profile row, so concurrent reservations are checked one after another
+        let reserved = sqlx::query!(
+            r#"
+            UPDATE storage_profiles
+            SET reserved_storage = reserved_storage + $2
+            WHERE user_id = $1 AND taken_storage + reserved_storage + $2 <= allowed_storage
+            "#,
+            file.owner_id,
+            file.size
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        if reserved.rows_affected() == 0 {
+            return Err(DataError::NoFreeStorageError);
+        }
+
+        let f = sqlx::query_as!(
+            File,
+            r#"
+            INSERT INTO files (id, name, owner_id, parent_folder_id, file_type, is_deleted, size, upload_status, created_at, updated_at, checksum)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
+            "#,
+            file.id,
+            file.name,
+            file.owner_id,
+            file.parent_folder_id,
+            file.file_type as _,
+            file.is_deleted,
+            file.size,
+            file.upload_status as _,
+            file.created_at,
+            file.updated_at,
+            file.checksum
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        tx.commit().await.map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(f)
+    }
+
+    // Turns the reservation of a pending file into taken storage
+    async fn complete_reservation(&self, file: File) -> Result<File, DataError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        let f = sqlx::query_as!(
+            File,
+            r#"
+            UPDATE files
+            SET upload_status = 'completed', checksum = $2, blob_id = $3, updated_at = NOW()
+            WHERE id = $1 AND upload_status = 'pending'
+            RETURNING id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
+            "#,
+            file.id,
+            file.checksum,
+            file.blob_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?
+        .ok_or_else(|| DataError::ValidationError("File is not pending".to_string()))?;
+
+        sqlx::query!(
+            r#"
+            UPDATE storage_profiles
+            SET reserved_storage = GREATEST(reserved_storage - $2, 0),
+                taken_storage = taken_storage + $2
+            WHERE user_id = $1
+            "#,
+            f.owner_id,
+            f.size
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        tx.commit().await.map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(f)
+    }
+
+    // Fails a pending file and gives its reservation back
+    async fn release_reservation(&self, file: File) -> Result<File, DataError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        let f = sqlx::query_as!(
+            File,
+            r#"
+            UPDATE files
+            SET upload_status = 'failed', updated_at = NOW()
+            WHERE id = $1 AND upload_status = 'pending'
+            RETURNING id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
+            "#,
+            file.id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?
+        .ok_or_else(|| DataError::ValidationError("File is not pending".to_string()))?;
+
+        sqlx::query!(
+            r#"
+            UPDATE storage_profiles
+            SET reserved_storage = GREATEST(reserved_storage - $2, 0)
+            WHERE user_id = $1
+            "#,
+            f.owner_id,
+            f.size
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        tx.commit().await.map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(f)
+    }
+
+    async fn delete_pending(&self, file_id: Uuid) -> Result<Option<File>, DataError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        let f = sqlx::query_as!(
+            File,
+            r#"
+            DELETE FROM files
+            WHERE id = $1 AND upload_status = 'pending'
+            RETURNING id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
+            "#,
+            file_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        if let Some(f) = &f {
+            sqlx::query!(
+                r#"
+                UPDATE storage_profiles
+                SET reserved_storage = GREATEST(reserved_storage - $2, 0)
+                WHERE user_id = $1
+                "#,
+                f.owner_id,
+                f.size
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+        }
+
+        tx.commit().await.map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(f)
+    }
+
+    // Points the file at new content and gives the reference on its previous blob back in one go
+    async fn replace_content(
+        &self,
+        file: File,
+        previous_blob_id: Option<Uuid>,
+    ) -> Result<(File, Option<Blob>), DataError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        let f = sqlx::query_as!(
+            File,
+            r#"
+            UPDATE files
+            SET name = $2, file_type = $3, size = $4, checksum = $5, blob_id = $6, archive_codec = $8, original_size = $9, preview_status = 'pending', updated_at = NOW()
+            WHERE id = $1 AND blob_id IS NOT DISTINCT FROM $7
+            RETURNING id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
+            "#,
+            file.id,
+            file.name,
+            file.file_type as _,
+            file.size,
+            file.checksum,
+            file.blob_id,
+            previous_blob_id,
+            file.archive_codec as _,
+            file.original_size
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?
+        .ok_or_else(|| {
+            DataError::ValidationError("File content was changed in the meantime".to_string())
+        })?;
+
+        // Whatever replaced a gzip left by the old archiving is not that gzip anymore
+        sqlx::query!(
+            r#"
+            DELETE FROM legacy_archives
+            WHERE file_id = $1
+            "#,
+            file.id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        // The new content holds its own reference, even when it ended up in the same blob
+        let released = match previous_blob_id {
+            Some(blob_id) => release_blob_ref(&mut tx, blob_id).await?,
+            None => None,
+        };
+
+        tx.commit().await.map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok((f, released))
+    }
+
+    async fn is_legacy_archive(&self, file_id: Uuid) -> Result<bool, DataError> {
+        let is_legacy = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM legacy_archives WHERE file_id = $1) as "exists!"
+            "#,
+            file_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(is_legacy)
+    }
+
+    async fn mark_accessed(&self, file_ids: &[Uuid]) -> Result<(), DataError> {
+        // Only a coarse moment is needed, so a file read over and over is written once an hour
+        sqlx::query!(
+            r#"
+            UPDATE files
+            SET last_accessed_at = NOW()
+            WHERE id = ANY($1) AND last_accessed_at < NOW() - INTERVAL '1 hour'
+            "#,
+            file_ids
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(())
+    }
+
+    async fn get_compression_candidates(
+        &self,
+        policy: &CompressionPolicy,
+        idle_since: OffsetDateTime,
+        limit: i64,
+    ) -> Result<Vec<File>, DataError> {
+        // The global policy covers only owners that have no policy of their own
+        let f = sqlx::query_as!(
+            File,
+            r#"
+            SELECT f.id, f.name, f.owner_id, f.file_type as "file_type: _", f.parent_folder_id,
+               f.is_deleted, f.ttl, f.size, f.upload_status as "upload_status: _", f.created_at, f.updated_at, f.checksum, f.blob_id, f.archive_codec as "archive_codec: _", f.original_size, f.preview_status as "preview_status: _"
+            FROM files f
+            WHERE f.archive_codec IS NULL
+              AND f.is_deleted = FALSE
+              AND f.upload_status = 'completed'
+              AND f.size >= $1
+              AND f.last_accessed_at < $2
+              AND NOT (f.file_type = ANY($3::file_type[]))
+              AND (f.compression_attempted_at IS NULL OR f.compression_attempted_at < f.updated_at)
+              AND (
+                (f.owner_id = $4)
+                OR ($4::uuid IS NULL AND NOT EXISTS (
+                    SELECT 1 FROM compression_policies p WHERE p.user_id = f.owner_id
+                ))
+              )
+            ORDER BY f.last_accessed_at
+            LIMIT $5
+            "#,
+            policy.min_size,
+            idle_since,
+            &policy.excluded_types as &[FileType],
+            policy.user_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(f)
+    }
+
+    async fn mark_compression_attempted(&self, file_id: Uuid) -> Result<(), DataError> {
+        sqlx::query!(
+            r#"
+            UPDATE files
+            SET compression_attempted_at = NOW()
+            WHERE id = $1
+            "#,
+            file_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(())
+    }
+
+    async fn set_preview_status(
+        &self,
+        file: &File,
+        status: PreviewStatus,
+    ) -> Result<(), DataError> {
+        // Files of the same type sharing the blob share its preview as well
+        sqlx::query!(
+            r#"
+            UPDATE files
+            SET preview_status = $3
+            WHERE id = $1 OR (blob_id = $2 AND file_type = $4)
+            "#,
+            file.id,
+            file.blob_id,
+            status as _,
+            file.file_type.clone() as _
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(())
+    }
+
+    async fn inherit_preview_status(&self, file_id: Uuid) -> Result<PreviewStatus, DataError> {
+        // Content that was stored already has its preview made, or in the making, for another file.
+        // Without such a file the status stays pending and is settled when the preview is asked for
+        let status = sqlx::query_scalar!(
+            r#"
+            UPDATE files f
+            SET preview_status = COALESCE((
+                SELECT s.preview_status
+                FROM files s
+                WHERE s.blob_id = f.blob_id AND s.file_type = f.file_type AND s.id <> f.id
+                ORDER BY s.preview_status = 'pending'
+                LIMIT 1
+            ), 'pending')
+            WHERE f.id = $1
+            RETURNING f.preview_status as "preview_status: PreviewStatus"
+            "#,
+            file_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(status)
+    }
 }
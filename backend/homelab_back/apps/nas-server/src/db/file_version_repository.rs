@@ -0,0 +1,270 @@
+use crate::db::blob_repository::release_blob_ref;
+use crate::helpers::data_error::DataError;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::blob::Blob;
+use homelab_core::file::File;
+use homelab_core::file_version::FileVersion;
+use sqlx::types::time::OffsetDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait FileVersionRepository: Send + Sync {
+    async fn get_by_id(&self, version_id: Uuid) -> Result<Option<FileVersion>, DataError>;
+    async fn get_by_file_id(&self, file_id: Uuid) -> Result<Vec<FileVersion>, DataError>;
+    async fn get_all(&self) -> Result<Vec<FileVersion>, DataError>;
+    async fn get_all_by_owner(&self, owner_id: Uuid) -> Result<Vec<FileVersion>, DataError>;
+    async fn get_expired(
+        &self,
+        keep_count: i64,
+        older_than: OffsetDateTime,
+    ) -> Result<Vec<FileVersion>, DataError>;
+    async fn replace_content(&self, file: File, version: FileVersion) -> Result<File, DataError>;
+    async fn restore(&self, file: File, version: FileVersion) -> Result<File, DataError>;
+    async fn delete(
+        &self,
+        version_id: Uuid,
+    ) -> Result<Option<(FileVersion, Option<Blob>)>, DataError>;
+}
+
+#[derive(new)]
+pub struct FileVersionRepositoryImpl {
+    pool: PgPool,
+}
+
+#[async_trait]
+impl FileVersionRepository for FileVersionRepositoryImpl {
+    async fn get_by_id(&self, version_id: Uuid) -> Result<Option<FileVersion>, DataError> {
+        let version = sqlx::query_as!(
+            FileVersion,
+            r#"
+            SELECT id, file_id, blob_id, size, checksum, created_at
+            FROM file_versions
+            WHERE id = $1
+            "#,
+            version_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(version)
+    }
+
+    async fn get_by_file_id(&self, file_id: Uuid) -> Result<Vec<FileVersion>, DataError> {
+        let versions = sqlx::query_as!(
+            FileVersion,
+            r#"
+            SELECT id, file_id, blob_id, size, checksum, created_at
+            FROM file_versions
+            WHERE file_id = $1
+            ORDER BY created_at DESC
+            "#,
+            file_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(versions)
+    }
+
+    async fn get_all(&self) -> Result<Vec<FileVersion>, DataError> {
+        let versions = sqlx::query_as!(
+            FileVersion,
+            r#"
+            SELECT id, file_id, blob_id, size, checksum, created_at
+            FROM file_versions
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(versions)
+    }
+
+    async fn get_all_by_owner(&self, owner_id: Uuid) -> Result<Vec<FileVersion>, DataError> {
+        let versions = sqlx::query_as!(
+            FileVersion,
+            r#"
+            SELECT v.id, v.file_id, v.blob_id, v.size, v.checksum, v.created_at
+            FROM file_versions v
+            INNER JOIN files f ON f.id = v.file_id
+            WHERE f.owner_id = $1
+            "#,
+            owner_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(versions)
+    }
+
+    // Everything beyond the newest `keep_count` versions of a file, and every version older than `older_than`
+    async fn get_expired(
+        &self,
+        keep_count: i64,
+        older_than: OffsetDateTime,
+    ) -> Result<Vec<FileVersion>, DataError> {
+        let versions = sqlx::query_as!(
+            FileVersion,
+            r#"
+            SELECT id as "id!", file_id as "file_id!", blob_id as "blob_id!", size as "size!",
+                   checksum as "checksum!", created_at as "created_at!"
+            FROM (
+                SELECT id, file_id, blob_id, size, checksum, created_at,
+                       ROW_NUMBER() OVER (PARTITION BY file_id ORDER BY created_at DESC) AS position
+                FROM file_versions
+            ) ranked
+            WHERE position > $1 OR created_at < $2
+            "#,
+            keep_count,
+            older_than
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(versions)
+    }
+
+    // Switches the file over to its new content, the reference on the previous blob moves to the version
+    async fn replace_content(&self, file: File, version: FileVersion) -> Result<File, DataError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        let f = sqlx::query_as!(
+            File,
+            r#"
+            UPDATE files
+            SET size = $2, checksum = $3, blob_id = $4, preview_status = 'pending', updated_at = NOW()
+            WHERE id = $1 AND blob_id = $5
+            RETURNING id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
+            "#,
+            file.id,
+            file.size,
+            file.checksum,
+            file.blob_id,
+            version.blob_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?
+        .ok_or(DataError::ContentConflictError)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO file_versions (id, file_id, blob_id, size, checksum, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            version.id,
+            version.file_id,
+            version.blob_id,
+            version.size,
+            version.checksum,
+            version.created_at
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        tx.commit().await.map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(f)
+    }
+
+    // Swaps the content of the file and the version, so the content being replaced is kept as a version as well.
+    // Both keep holding one reference each, only the owner of the references changes
+    async fn restore(&self, file: File, version: FileVersion) -> Result<File, DataError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        let f = sqlx::query_as!(
+            File,
+            r#"
+            UPDATE files
+            SET size = $2, checksum = $3, blob_id = $4, preview_status = 'pending', updated_at = NOW()
+            WHERE id = $1 AND blob_id IS NOT DISTINCT FROM $5
+            RETURNING id, name, owner_id, file_type as "file_type: _", parent_folder_id, is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
+            "#,
+            file.id,
+            version.size,
+            version.checksum,
+            version.blob_id,
+            file.blob_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?
+        .ok_or(DataError::ContentConflictError)?;
+
+        let swapped = sqlx::query!(
+            r#"
+            UPDATE file_versions
+            SET blob_id = $2, size = $3, checksum = $4, created_at = NOW()
+            WHERE id = $1 AND blob_id = $5
+            "#,
+            version.id,
+            file.blob_id,
+            file.size,
+            file.checksum,
+            version.blob_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        if swapped.rows_affected() == 0 {
+            return Err(DataError::ContentConflictError);
+        }
+
+        tx.commit().await.map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(f)
+    }
+
+    // Returns the removed version and its blob once no one refers to it anymore
+    async fn delete(
+        &self,
+        version_id: Uuid,
+    ) -> Result<Option<(FileVersion, Option<Blob>)>, DataError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| DataError::DatabaseError(e))?;
+
+        let version = sqlx::query_as!(
+            FileVersion,
+            r#"
+            DELETE FROM file_versions
+            WHERE id = $1
+            RETURNING id, file_id, blob_id, size, checksum, created_at
+            "#,
+            version_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        let version = match version {
+            Some(version) => version,
+            None => return Ok(None),
+        };
+
+        let released = release_blob_ref(&mut tx, version.blob_id).await?;
+
+        tx.commit().await.map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(Some((version, released)))
+    }
+}
@@ -10,7 +10,11 @@ pub trait FolderRepository: Send + Sync {
     async fn get_root(&self, user_id: Uuid) -> Result<Option<Folder>, DataError>;
     async fn get_by_id(&self, folder_id: Uuid) -> Result<Option<Folder>, DataError>;
     async fn get_children_by_id(&self, folder_id: Uuid) -> Result<Vec<Folder>, DataError>;
-    async fn search_by_name(&self, search_query: String) -> Result<Vec<Folder>, DataError>;
+    async fn search_by_name(
+        &self,
+        search_query: String,
+        owner_id: Uuid,
+    ) -> Result<Vec<Folder>, DataError>;
     async fn filter_files_in_folder(
         &self,
         file_types: &[FileType],
@@ -21,6 +25,9 @@ pub trait FolderRepository: Send + Sync {
     async fn update_folder(&self, folder: Folder) -> Result<Folder, DataError>;
     async fn delete_all(&self, folder_ids: &[Uuid]) -> Result<(), DataError>;
     async fn delete_by_id(&self, folder_id: Uuid) -> Result<(), DataError>;
+    async fn get_ancestor_ids(&self, folder_id: Uuid) -> Result<Vec<Uuid>, DataError>;
+    async fn get_folder_paths(&self, folder_id: Uuid) -> Result<Vec<(Uuid, String)>, DataError>;
+    async fn get_by_folder_ids(&self, folder_ids: &[Uuid]) -> Result<Vec<File>, DataError>;
 }
 
 pub struct FolderRepositoryImpl {
@@ -83,15 +90,20 @@ impl FolderRepository for FolderRepositoryImpl {
         Ok(folders)
     }
 
-    async fn search_by_name(&self, search_query: String) -> Result<Vec<Folder>, DataError> {
+    async fn search_by_name(
+        &self,
+        search_query: String,
+        owner_id: Uuid,
+    ) -> Result<Vec<Folder>, DataError> {
         let f: Vec<Folder> = sqlx::query_as!(
             Folder,
             r#"
             SELECT id, name, owner_id, created_at, parent_folder_id
             FROM folders
-            WHERE LOWER(name) LIKE LOWER($1)
+            WHERE LOWER(name) LIKE LOWER($1) AND owner_id = $2
             "#,
-            search_query
+            search_query,
+            owner_id
         )
         .fetch_all(&self.pool)
         .await
@@ -108,7 +120,7 @@ impl FolderRepository for FolderRepositoryImpl {
         let files = sqlx::query_as!(
             File,
             r#"
-            SELECT id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at
+            SELECT id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
             FROM files
             WHERE parent_folder_id = $1 AND file_type = ANY($2::file_type[]) AND is_deleted = FALSE
             "#,
@@ -126,7 +138,7 @@ impl FolderRepository for FolderRepositoryImpl {
         let files = sqlx::query_as!(
         File,
         r#"
-        SELECT id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at
+        SELECT id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
         FROM files
         WHERE parent_folder_id = $1 AND is_deleted = FALSE
         "#,
@@ -210,4 +222,69 @@ impl FolderRepository for FolderRepositoryImpl {
 
         Ok(())
     }
+
+    async fn get_ancestor_ids(&self, folder_id: Uuid) -> Result<Vec<Uuid>, DataError> {
+        // Walks up the tree starting with the folder itself, ordered from the folder to the root
+        let ids = sqlx::query_scalar!(
+            r#"
+            WITH RECURSIVE ancestors AS (
+                SELECT id, parent_folder_id, 0 AS depth
+                FROM folders
+                WHERE id = $1
+                UNION ALL
+                SELECT f.id, f.parent_folder_id, a.depth + 1
+                FROM folders f
+                INNER JOIN ancestors a ON f.id = a.parent_folder_id
+            )
+            SELECT id as "id!" FROM ancestors ORDER BY depth
+            "#,
+            folder_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(ids)
+    }
+
+    async fn get_folder_paths(&self, folder_id: Uuid) -> Result<Vec<(Uuid, String)>, DataError> {
+        // Walks down the tree starting with the folder itself, each path starts with the folder's own name
+        let rows = sqlx::query!(
+            r#"
+            WITH RECURSIVE subtree AS (
+                SELECT id, name::TEXT AS path
+                FROM folders
+                WHERE id = $1
+                UNION ALL
+                SELECT f.id, s.path || '/' || f.name
+                FROM folders f
+                INNER JOIN subtree s ON f.parent_folder_id = s.id
+            )
+            SELECT id as "id!", path as "path!" FROM subtree ORDER BY path
+            "#,
+            folder_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(rows.into_iter().map(|row| (row.id, row.path)).collect())
+    }
+
+    async fn get_by_folder_ids(&self, folder_ids: &[Uuid]) -> Result<Vec<File>, DataError> {
+        let files = sqlx::query_as!(
+            File,
+            r#"
+            SELECT id, name, owner_id, parent_folder_id, file_type as "file_type: _", is_deleted, ttl, size, upload_status as "upload_status: _", created_at, updated_at, checksum, blob_id, archive_codec as "archive_codec: _", original_size, preview_status as "preview_status: _"
+            FROM files
+            WHERE parent_folder_id = ANY($1) AND is_deleted = FALSE
+            "#,
+            folder_ids
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(files)
+    }
 }
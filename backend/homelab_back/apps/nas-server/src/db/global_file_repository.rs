@@ -2,11 +2,13 @@ use crate::helpers::data_error::DataError;
 use async_trait::async_trait;
 use homelab_core::global_file::GlobalFile;
 use sqlx::PgPool;
+use uuid::Uuid;
 
 #[async_trait]
 pub trait GlobalFileRepository: Send + Sync {
     async fn save(&self, global_file: GlobalFile) -> Result<GlobalFile, DataError>;
     async fn get_all(&self) -> Result<Vec<GlobalFile>, DataError>;
+    async fn is_global(&self, file_id: Uuid) -> Result<bool, DataError>;
 }
 
 pub struct GlobalFileRepositoryImpl {
@@ -52,4 +54,18 @@ impl GlobalFileRepository for GlobalFileRepositoryImpl {
 
         Ok(global_files)
     }
+
+    async fn is_global(&self, file_id: Uuid) -> Result<bool, DataError> {
+        let is_global = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(SELECT 1 FROM global_files WHERE original_id = $1) as "exists!"
+            "#,
+            file_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(is_global)
+    }
 }
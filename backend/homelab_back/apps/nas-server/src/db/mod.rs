@@ -1,7 +1,15 @@
+pub mod blob_repository;
+pub mod compression_policy_repository;
+pub mod file_integrity_repository;
 pub mod file_label_repository;
 pub mod file_repository;
+pub mod file_version_repository;
 pub mod folder_repository;
 pub mod global_file_repository;
 pub mod label_repository;
+pub mod preview_job_repository;
+pub mod share_link_repository;
 pub mod shared_file_repository;
+pub mod shared_folder_repository;
 pub mod storage_profile_repository;
+pub mod upload_session_repository;
@@ -0,0 +1,158 @@
+use crate::helpers::data_error::DataError;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::preview_job::PreviewJob;
+use sqlx::types::time::OffsetDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait PreviewJobRepository: Send + Sync {
+    async fn enqueue(&self, file_id: Uuid) -> Result<(), DataError>;
+    async fn enqueue_matching(
+        &self,
+        file_ids: &[Uuid],
+        only_failed: bool,
+    ) -> Result<u64, DataError>;
+    async fn claim_next(&self) -> Result<Option<PreviewJob>, DataError>;
+    async fn complete(&self, job: &PreviewJob) -> Result<(), DataError>;
+    async fn retry(
+        &self,
+        job: &PreviewJob,
+        run_after: OffsetDateTime,
+        error: &str,
+    ) -> Result<(), DataError>;
+}
+
+#[derive(new)]
+pub struct PreviewJobRepositoryImpl {
+    pool: PgPool,
+}
+
+#[async_trait]
+impl PreviewJobRepository for PreviewJobRepositoryImpl {
+    async fn enqueue(&self, file_id: Uuid) -> Result<(), DataError> {
+        // A job that is queued again starts over, a worker still holding it no longer owns it
+        sqlx::query!(
+            r#"
+            INSERT INTO preview_jobs (file_id)
+            VALUES ($1)
+            ON CONFLICT (file_id) DO UPDATE
+            SET attempts = 0,
+                run_after = NOW(),
+                locked_at = NULL,
+                last_error = NULL
+            "#,
+            file_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(())
+    }
+
+    async fn enqueue_matching(
+        &self,
+        file_ids: &[Uuid],
+        only_failed: bool,
+    ) -> Result<u64, DataError> {
+        // One file per blob and type is enough, the others share its preview
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO preview_jobs (file_id)
+            SELECT DISTINCT ON (COALESCE(f.blob_id, f.id), f.file_type) f.id
+            FROM files f
+            WHERE f.upload_status = 'completed'
+              AND f.is_deleted = FALSE
+              AND f.archive_codec IS NULL
+              AND f.file_type IN ('image', 'video', 'audio', 'pdf')
+              AND (cardinality($1::uuid[]) = 0 OR f.id = ANY($1))
+              AND ($2::boolean = FALSE OR f.preview_status = 'failed')
+            ORDER BY COALESCE(f.blob_id, f.id), f.file_type, f.id
+            ON CONFLICT (file_id) DO UPDATE
+            SET attempts = 0,
+                run_after = NOW(),
+                locked_at = NULL,
+                last_error = NULL
+            "#,
+            file_ids,
+            only_failed
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn claim_next(&self) -> Result<Option<PreviewJob>, DataError> {
+        // Nothing takes half an hour to render, a job locked for that long belongs to a worker that is gone
+        let job = sqlx::query_as!(
+            PreviewJob,
+            r#"
+            UPDATE preview_jobs
+            SET locked_at = NOW(),
+                attempts = attempts + 1
+            WHERE file_id = (
+                SELECT file_id
+                FROM preview_jobs
+                WHERE run_after <= NOW()
+                  AND (locked_at IS NULL OR locked_at < NOW() - INTERVAL '30 minutes')
+                ORDER BY run_after
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING file_id, attempts, run_after, locked_at, last_error, created_at
+            "#
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(job)
+    }
+
+    async fn complete(&self, job: &PreviewJob) -> Result<(), DataError> {
+        // Only while the job is still held by this run, otherwise it was queued again in between
+        sqlx::query!(
+            r#"
+            DELETE FROM preview_jobs
+            WHERE file_id = $1 AND locked_at = $2
+            "#,
+            job.file_id,
+            job.locked_at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(())
+    }
+
+    async fn retry(
+        &self,
+        job: &PreviewJob,
+        run_after: OffsetDateTime,
+        error: &str,
+    ) -> Result<(), DataError> {
+        sqlx::query!(
+            r#"
+            UPDATE preview_jobs
+            SET locked_at = NULL,
+                run_after = $3,
+                last_error = $4
+            WHERE file_id = $1 AND locked_at = $2
+            "#,
+            job.file_id,
+            job.locked_at,
+            run_after,
+            error
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(())
+    }
+}
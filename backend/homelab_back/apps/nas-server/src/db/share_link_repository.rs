@@ -0,0 +1,137 @@
+use crate::helpers::data_error::DataError;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::share_link::ShareLink;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait ShareLinkRepository: Send + Sync {
+    async fn create(&self, share_link: ShareLink) -> Result<ShareLink, DataError>;
+    async fn get_by_id(&self, id: Uuid) -> Result<Option<ShareLink>, DataError>;
+    async fn get_by_token(&self, token: &str) -> Result<Option<ShareLink>, DataError>;
+    async fn get_all_for_owner(&self, owner_id: Uuid) -> Result<Vec<ShareLink>, DataError>;
+    async fn delete_by_id(&self, id: Uuid) -> Result<(), DataError>;
+    async fn register_download(&self, id: Uuid) -> Result<Option<ShareLink>, DataError>;
+}
+
+#[derive(new)]
+pub struct ShareLinkRepositoryImpl {
+    pool: PgPool,
+}
+
+#[async_trait]
+impl ShareLinkRepository for ShareLinkRepositoryImpl {
+    async fn create(&self, share_link: ShareLink) -> Result<ShareLink, DataError> {
+        let share_link = sqlx::query_as!(
+            ShareLink,
+            r#"
+            INSERT INTO share_links (id, token, owner_id, file_id, folder_id, password_hash, expires_at, max_downloads, download_count, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id, token, owner_id, file_id, folder_id, password_hash, expires_at, max_downloads, download_count, created_at
+            "#,
+            share_link.id,
+            share_link.token,
+            share_link.owner_id,
+            share_link.file_id,
+            share_link.folder_id,
+            share_link.password_hash,
+            share_link.expires_at,
+            share_link.max_downloads,
+            share_link.download_count,
+            share_link.created_at
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(share_link)
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<Option<ShareLink>, DataError> {
+        let share_link = sqlx::query_as!(
+            ShareLink,
+            r#"
+            SELECT id, token, owner_id, file_id, folder_id, password_hash, expires_at, max_downloads, download_count, created_at
+            FROM share_links
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(share_link)
+    }
+
+    async fn get_by_token(&self, token: &str) -> Result<Option<ShareLink>, DataError> {
+        let share_link = sqlx::query_as!(
+            ShareLink,
+            r#"
+            SELECT id, token, owner_id, file_id, folder_id, password_hash, expires_at, max_downloads, download_count, created_at
+            FROM share_links
+            WHERE token = $1
+            "#,
+            token
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(share_link)
+    }
+
+    async fn get_all_for_owner(&self, owner_id: Uuid) -> Result<Vec<ShareLink>, DataError> {
+        let share_links = sqlx::query_as!(
+            ShareLink,
+            r#"
+            SELECT id, token, owner_id, file_id, folder_id, password_hash, expires_at, max_downloads, download_count, created_at
+            FROM share_links
+            WHERE owner_id = $1
+            ORDER BY created_at DESC
+            "#,
+            owner_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(share_links)
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<(), DataError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM share_links WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(())
+    }
+
+    async fn register_download(&self, id: Uuid) -> Result<Option<ShareLink>, DataError> {
+        // Checking the limits and incrementing in one statement keeps concurrent downloads from overshooting max_downloads
+        let share_link = sqlx::query_as!(
+            ShareLink,
+            r#"
+            UPDATE share_links
+            SET download_count = download_count + 1
+            WHERE id = $1
+              AND (max_downloads IS NULL OR download_count < max_downloads)
+              AND (expires_at IS NULL OR expires_at > NOW())
+            RETURNING id, token, owner_id, file_id, folder_id, password_hash, expires_at, max_downloads, download_count, created_at
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(share_link)
+    }
+}
@@ -1,6 +1,7 @@
 use crate::helpers::data_error::DataError;
 use async_trait::async_trait;
-use homelab_core::shared_file::SharedFile;
+use homelab_core::file::File;
+use homelab_core::shared_file::{SharedFile, SharedFileAccessType};
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -8,6 +9,17 @@ use uuid::Uuid;
 pub trait SharedFileRepository: Send + Sync {
     async fn create_shared_file(&self, shared_file: &SharedFile) -> Result<SharedFile, DataError>;
     async fn get_all_for_user(&self, user_id: Uuid) -> Result<Vec<SharedFile>, DataError>;
+    async fn get_files_shared_with(&self, user_id: Uuid) -> Result<Vec<File>, DataError>;
+    async fn get_for_user_and_file(
+        &self,
+        user_id: Uuid,
+        file_id: Uuid,
+    ) -> Result<Option<SharedFile>, DataError>;
+    async fn update_access_type(
+        &self,
+        id: Uuid,
+        access_type: SharedFileAccessType,
+    ) -> Result<SharedFile, DataError>;
 }
 
 pub struct SharedFileRepositoryImpl {
@@ -59,4 +71,86 @@ impl SharedFileRepository for SharedFileRepositoryImpl {
 
         Ok(shared_files)
     }
+
+    async fn get_files_shared_with(&self, user_id: Uuid) -> Result<Vec<File>, DataError> {
+        let files = sqlx::query_as!(
+            File,
+            r#"
+            SELECT
+                f.id,
+                f.name,
+                f.owner_id,
+                f.parent_folder_id,
+                f.file_type as "file_type: _",
+                f.is_deleted,
+                f.ttl,
+                f.size,
+                f.upload_status as "upload_status: _",
+                f.created_at,
+                f.updated_at,
+                f.checksum,
+                f.blob_id,
+                f.archive_codec as "archive_codec: _",
+                f.original_size,
+                f.preview_status as "preview_status: _"
+            FROM files f
+            INNER JOIN shared_file sf ON sf.file_id = f.id
+            WHERE sf.user_id = $1 AND f.is_deleted = FALSE
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(files)
+    }
+
+    async fn get_for_user_and_file(
+        &self,
+        user_id: Uuid,
+        file_id: Uuid,
+    ) -> Result<Option<SharedFile>, DataError> {
+        // The same file can be shared more than once, the strongest grant wins
+        let shared_file = sqlx::query_as!(
+            SharedFile,
+            r#"
+            SELECT id, user_id, owner_id, file_id, access_type as "access_type: _"
+            FROM shared_file
+            WHERE user_id = $1 AND file_id = $2
+            ORDER BY access_type DESC
+            LIMIT 1
+            "#,
+            user_id,
+            file_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(shared_file)
+    }
+
+    async fn update_access_type(
+        &self,
+        id: Uuid,
+        access_type: SharedFileAccessType,
+    ) -> Result<SharedFile, DataError> {
+        let shared_file = sqlx::query_as!(
+            SharedFile,
+            r#"
+            UPDATE shared_file
+            SET access_type = $2
+            WHERE id = $1
+            RETURNING id, user_id, owner_id, file_id, access_type as "access_type: _"
+            "#,
+            id,
+            access_type as _
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(shared_file)
+    }
 }
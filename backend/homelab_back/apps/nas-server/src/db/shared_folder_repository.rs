@@ -0,0 +1,163 @@
+use crate::helpers::data_error::DataError;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::folder::Folder;
+use homelab_core::shared_file::SharedFileAccessType;
+use homelab_core::shared_folder::SharedFolder;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait SharedFolderRepository: Send + Sync {
+    async fn create(&self, shared_folder: &SharedFolder) -> Result<SharedFolder, DataError>;
+    async fn update_access_type(
+        &self,
+        id: Uuid,
+        access_type: SharedFileAccessType,
+    ) -> Result<SharedFolder, DataError>;
+    async fn get_for_user_and_folder(
+        &self,
+        user_id: Uuid,
+        folder_id: Uuid,
+    ) -> Result<Option<SharedFolder>, DataError>;
+    async fn get_strongest_for_folders(
+        &self,
+        user_id: Uuid,
+        folder_ids: &[Uuid],
+    ) -> Result<Option<SharedFolder>, DataError>;
+    async fn get_all_for_user(&self, user_id: Uuid) -> Result<Vec<SharedFolder>, DataError>;
+    async fn get_folders_shared_with(&self, user_id: Uuid) -> Result<Vec<Folder>, DataError>;
+}
+
+#[derive(new)]
+pub struct SharedFolderRepositoryImpl {
+    pool: PgPool,
+}
+
+#[async_trait]
+impl SharedFolderRepository for SharedFolderRepositoryImpl {
+    async fn create(&self, shared_folder: &SharedFolder) -> Result<SharedFolder, DataError> {
+        let shared_folder = sqlx::query_as!(
+            SharedFolder,
+            r#"
+            INSERT INTO shared_folders (id, user_id, owner_id, folder_id, access_type)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, user_id, owner_id, folder_id, access_type as "access_type: _"
+            "#,
+            shared_folder.id,
+            shared_folder.user_id,
+            shared_folder.owner_id,
+            shared_folder.folder_id,
+            shared_folder.access_type as _
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(shared_folder)
+    }
+
+    async fn update_access_type(
+        &self,
+        id: Uuid,
+        access_type: SharedFileAccessType,
+    ) -> Result<SharedFolder, DataError> {
+        let shared_folder = sqlx::query_as!(
+            SharedFolder,
+            r#"
+            UPDATE shared_folders
+            SET access_type = $2
+            WHERE id = $1
+            RETURNING id, user_id, owner_id, folder_id, access_type as "access_type: _"
+            "#,
+            id,
+            access_type as _
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(shared_folder)
+    }
+
+    async fn get_for_user_and_folder(
+        &self,
+        user_id: Uuid,
+        folder_id: Uuid,
+    ) -> Result<Option<SharedFolder>, DataError> {
+        let shared_folder = sqlx::query_as!(
+            SharedFolder,
+            r#"
+            SELECT id, user_id, owner_id, folder_id, access_type as "access_type: _"
+            FROM shared_folders
+            WHERE user_id = $1 AND folder_id = $2
+            "#,
+            user_id,
+            folder_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(shared_folder)
+    }
+
+    async fn get_strongest_for_folders(
+        &self,
+        user_id: Uuid,
+        folder_ids: &[Uuid],
+    ) -> Result<Option<SharedFolder>, DataError> {
+        let shared_folder = sqlx::query_as!(
+            SharedFolder,
+            r#"
+            SELECT id, user_id, owner_id, folder_id, access_type as "access_type: _"
+            FROM shared_folders
+            WHERE user_id = $1 AND folder_id = ANY($2)
+            ORDER BY access_type DESC
+            LIMIT 1
+            "#,
+            user_id,
+            folder_ids
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(shared_folder)
+    }
+
+    async fn get_all_for_user(&self, user_id: Uuid) -> Result<Vec<SharedFolder>, DataError> {
+        let shared_folders = sqlx::query_as!(
+            SharedFolder,
+            r#"
+            SELECT id, user_id, owner_id, folder_id, access_type as "access_type: _"
+            FROM shared_folders
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(shared_folders)
+    }
+
+    async fn get_folders_shared_with(&self, user_id: Uuid) -> Result<Vec<Folder>, DataError> {
+        let folders = sqlx::query_as!(
+            Folder,
+            r#"
+            SELECT f.id, f.name, f.owner_id, f.created_at, f.parent_folder_id
+            FROM folders f
+            INNER JOIN shared_folders sf ON sf.folder_id = f.id
+            WHERE sf.user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(folders)
+    }
+}
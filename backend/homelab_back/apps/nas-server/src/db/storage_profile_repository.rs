@@ -8,12 +8,28 @@ use uuid::Uuid;
 pub trait StorageProfileRepository: Send + Sync {
     async fn create(&self, storage_profile: StorageProfile) -> Result<StorageProfile, DataError>;
     async fn get_by_id(&self, id: Uuid) -> Result<Option<StorageProfile>, DataError>;
-    async fn save(&self, storage_profile: StorageProfile) -> Result<(), DataError>;
+    async fn get_all(&self) -> Result<Vec<StorageProfile>, DataError>;
+    async fn try_charge(
+        &self,
+        user_id: Uuid,
+        size: i64,
+    ) -> Result<Option<StorageProfile>, DataError>;
+    async fn release(&self, user_id: Uuid, size: i64) -> Result<Option<StorageProfile>, DataError>;
+    async fn release_reserved(
+        &self,
+        user_id: Uuid,
+        size: i64,
+    ) -> Result<Option<StorageProfile>, DataError>;
     async fn toggle_blocked(
         &self,
         profile: StorageProfile,
         is_deleted: bool,
     ) -> Result<(), DataError>;
+    async fn recalculate(
+        &self,
+        user_id: Uuid,
+        taken_storage: Option<i64>,
+    ) -> Result<Option<StorageProfile>, DataError>;
 }
 
 pub struct StorageProfileRepositoryImpl {
@@ -32,13 +48,14 @@ impl StorageProfileRepository for StorageProfileRepositoryImpl {
         let sp = sqlx::query_as!(
             StorageProfile,
             r#"
-        INSERT INTO storage_profiles (user_id, allowed_storage, taken_storage, is_blocked)
-        VALUES ($1, $2, $3, $4)
-        RETURNING user_id, allowed_storage, taken_storage, is_blocked
+        INSERT INTO storage_profiles (user_id, allowed_storage, taken_storage, reserved_storage, is_blocked)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING user_id, allowed_storage, taken_storage, reserved_storage, is_blocked
         "#,
             storage_profile.user_id,
             storage_profile.allowed_storage,
             storage_profile.taken_storage,
+            storage_profile.reserved_storage,
             storage_profile.is_blocked,
         )
         .fetch_one(&self.pool)
@@ -51,7 +68,7 @@ impl StorageProfileRepository for StorageProfileRepositoryImpl {
         let sp = sqlx::query_as!(
             StorageProfile,
             r#"
-        SELECT user_id, allowed_storage, taken_storage, is_blocked
+        SELECT user_id, allowed_storage, taken_storage, reserved_storage, is_blocked
         FROM storage_profiles
         WHERE user_id = $1
         "#,
@@ -64,22 +81,85 @@ impl StorageProfileRepository for StorageProfileRepositoryImpl {
         Ok(sp)
     }
 
-    async fn save(&self, storage_profile: StorageProfile) -> Result<(), DataError> {
-        sqlx::query!(
+    async fn get_all(&self) -> Result<Vec<StorageProfile>, DataError> {
+        let profiles = sqlx::query_as!(
+            StorageProfile,
+            r#"
+        SELECT user_id, allowed_storage, taken_storage, reserved_storage, is_blocked
+        FROM storage_profiles
+        "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(profiles)
+    }
+
+    // Only succeeds while the result still fits into allowed_storage, checked in the same statement
+    async fn try_charge(
+        &self,
+        user_id: Uuid,
+        size: i64,
+    ) -> Result<Option<StorageProfile>, DataError> {
+        let sp = sqlx::query_as!(
+            StorageProfile,
             r#"
             UPDATE storage_profiles
-            SET allowed_storage = $1, taken_storage = $2
-            WHERE user_id = $3
+            SET taken_storage = taken_storage + $2
+            WHERE user_id = $1 AND taken_storage + reserved_storage + $2 <= allowed_storage
+            RETURNING user_id, allowed_storage, taken_storage, reserved_storage, is_blocked
             "#,
-            storage_profile.allowed_storage,
-            storage_profile.taken_storage,
-            storage_profile.user_id
+            user_id,
+            size
         )
-        .execute(&self.pool)
+        .fetch_optional(&self.pool)
         .await
         .map_err(|e| DataError::DatabaseError(e))?;
 
-        Ok(())
+        Ok(sp)
+    }
+
+    async fn release(&self, user_id: Uuid, size: i64) -> Result<Option<StorageProfile>, DataError> {
+        let sp = sqlx::query_as!(
+            StorageProfile,
+            r#"
+            UPDATE storage_profiles
+            SET taken_storage = GREATEST(taken_storage - $2, 0)
+            WHERE user_id = $1
+            RETURNING user_id, allowed_storage, taken_storage, reserved_storage, is_blocked
+            "#,
+            user_id,
+            size
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(sp)
+    }
+
+    async fn release_reserved(
+        &self,
+        user_id: Uuid,
+        size: i64,
+    ) -> Result<Option<StorageProfile>, DataError> {
+        let sp = sqlx::query_as!(
+            StorageProfile,
+            r#"
+            UPDATE storage_profiles
+            SET reserved_storage = GREATEST(reserved_storage - $2, 0)
+            WHERE user_id = $1
+            RETURNING user_id, allowed_storage, taken_storage, reserved_storage, is_blocked
+            "#,
+            user_id,
+            size
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(sp)
     }
 
     async fn toggle_blocked(
@@ -102,4 +182,40 @@ impl StorageProfileRepository for StorageProfileRepositoryImpl {
 
         Ok(())
     }
+
+    // Rebuilds the counters from the files table, unless the taken bytes were measured elsewhere
+    async fn recalculate(
+        &self,
+        user_id: Uuid,
+        taken_storage: Option<i64>,
+    ) -> Result<Option<StorageProfile>, DataError> {
+        let sp = sqlx::query_as!(
+            StorageProfile,
+            r#"
+            UPDATE storage_profiles
+            SET taken_storage = COALESCE(
+                    $2::BIGINT,
+                    (SELECT COALESCE(SUM(size), 0)::BIGINT
+                     FROM files
+                     WHERE owner_id = $1 AND upload_status = 'completed')
+                    + (SELECT COALESCE(SUM(v.size), 0)::BIGINT
+                       FROM file_versions v
+                       INNER JOIN files f ON f.id = v.file_id
+                       WHERE f.owner_id = $1)
+                ),
+                reserved_storage = (SELECT COALESCE(SUM(size), 0)::BIGINT
+                                    FROM files
+                                    WHERE owner_id = $1 AND upload_status = 'pending')
+            WHERE user_id = $1
+            RETURNING user_id, allowed_storage, taken_storage, reserved_storage, is_blocked
+            "#,
+            user_id,
+            taken_storage
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(sp)
+    }
 }
@@ -0,0 +1,108 @@
+use crate::helpers::data_error::DataError;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::upload_session::UploadSession;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait UploadSessionRepository: Send + Sync {
+    async fn create(&self, session: UploadSession) -> Result<UploadSession, DataError>;
+    async fn get_by_file_id(&self, file_id: Uuid) -> Result<Option<UploadSession>, DataError>;
+    async fn advance_offset(
+        &self,
+        file_id: Uuid,
+        expected_offset: i64,
+        new_offset: i64,
+    ) -> Result<Option<UploadSession>, DataError>;
+    async fn delete_by_file_id(&self, file_id: Uuid) -> Result<(), DataError>;
+}
+
+#[derive(new)]
+pub struct UploadSessionRepositoryImpl {
+    pool: PgPool,
+}
+
+#[async_trait]
+impl UploadSessionRepository for UploadSessionRepositoryImpl {
+    async fn create(&self, session: UploadSession) -> Result<UploadSession, DataError> {
+        // Two clients starting the same upload end up sharing one session
+        let session = sqlx::query_as!(
+            UploadSession,
+            r#"
+            INSERT INTO upload_sessions (file_id, owner_id, committed_offset, total_size, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (file_id) DO UPDATE SET file_id = EXCLUDED.file_id
+            RETURNING file_id, owner_id, committed_offset, total_size, created_at, updated_at
+            "#,
+            session.file_id,
+            session.owner_id,
+            session.committed_offset,
+            session.total_size,
+            session.created_at,
+            session.updated_at
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(session)
+    }
+
+    async fn get_by_file_id(&self, file_id: Uuid) -> Result<Option<UploadSession>, DataError> {
+        let session = sqlx::query_as!(
+            UploadSession,
+            r#"
+            SELECT file_id, owner_id, committed_offset, total_size, created_at, updated_at
+            FROM upload_sessions
+            WHERE file_id = $1
+            "#,
+            file_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(session)
+    }
+
+    async fn advance_offset(
+        &self,
+        file_id: Uuid,
+        expected_offset: i64,
+        new_offset: i64,
+    ) -> Result<Option<UploadSession>, DataError> {
+        // Only moves forward if nobody else committed in the meantime
+        let session = sqlx::query_as!(
+            UploadSession,
+            r#"
+            UPDATE upload_sessions
+            SET committed_offset = $3, updated_at = NOW()
+            WHERE file_id = $1 AND committed_offset = $2
+            RETURNING file_id, owner_id, committed_offset, total_size, created_at, updated_at
+            "#,
+            file_id,
+            expected_offset,
+            new_offset
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(session)
+    }
+
+    async fn delete_by_file_id(&self, file_id: Uuid) -> Result<(), DataError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM upload_sessions WHERE file_id = $1
+            "#,
+            file_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| DataError::DatabaseError(e))?;
+
+        Ok(())
+    }
+}
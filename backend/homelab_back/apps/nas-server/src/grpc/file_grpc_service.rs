@@ -1,19 +1,36 @@
 use crate::data::copy_file_command::CopyFileCommand;
+use crate::data::extract_archive_command::ExtractArchiveCommand;
 use crate::data::init_file_command::InitFileCommand;
 use crate::data::move_file_command::MoveFileCommand;
+use crate::data::regenerate_previews_command::RegeneratePreviewsCommand;
+use crate::data::set_compression_policy_command::SetCompressionPolicyCommand;
+use crate::data::update_file_content_command::UpdateFileContentCommand;
 use crate::data::update_file_name_command::UpdateFileNameCommand;
-use crate::helpers::proto_mappers::{map_entity_id, map_file_to_proto};
+use crate::helpers::data_error::DataError;
+use crate::helpers::proto_mappers::{map_archive_codec_from_proto, map_archive_entry_to_proto, map_bundle_format_from_proto, map_compression_policy_to_proto, map_entity_id, map_extraction_summary_to_proto, map_file_to_proto, map_file_type_from_proto, map_file_version_to_proto, map_timestamp_from_proto, map_upload_session_to_proto};
 use crate::AppState;
 use async_trait::async_trait;
 use derive_new::new;
+use futures::stream::{self, Stream, StreamExt};
+use homelab_core::auth::caller::CallerIdentity;
+use homelab_core::auth::grpc_interceptor::{require_admin, require_caller};
+use homelab_proto::nas::download_bundle_request::Source as BundleSource;
+use homelab_proto::nas::download_bundle_response::Data as DownloadBundleData;
+use homelab_proto::nas::download_content_response::Data as DownloadContentData;
 use homelab_proto::nas::file_chunk::Data as FileChunkData;
 use homelab_proto::nas::file_service_server::FileService;
-use homelab_proto::nas::{ArchiveFileRequest, CopyFileRequest, DeleteChosenFilesRequest, DeleteFileRequest, FileChunk, FileListResponse, FileResponse, GetDeletedFilesRequest, GetFileRequest, InitFileRequest, MoveFileRequest, RemoveAllDeletedFilesRequest, RemoveDeletedFileRequest, RenameFileRequest, SearchFilesRequest, UnarchiveFileRequest, UndeleteFileRequest};
+use homelab_proto::nas::update_file_content_request::Data as UpdateContentData;
+use homelab_proto::nas::upload_chunk_request::Data as UploadChunkData;
+use homelab_proto::nas::{ArchiveEntryListResponse, ArchiveFileRequest, CompressionPolicyResponse, CopyFileRequest, DeleteChosenFilesRequest, DeleteCompressionPolicyRequest, DeleteFileRequest, DeleteFileVersionRequest, DownloadBundleRequest, DownloadBundleResponse, DownloadContentRequest, DownloadContentResponse, ExtractArchiveRequest, ExtractArchiveResponse, FileChunk, FileListResponse, FileResponse, FileVersionListResponse, GetCompressionPolicyRequest, GetDeletedFilesRequest, GetFileRequest, GetPreviewRequest, GetUploadSessionRequest, InitFileRequest, ListArchiveEntriesRequest, ListFileVersionsRequest, MoveFileRequest, PreviewResponse, RegeneratePreviewsRequest, RegeneratePreviewsResponse, RemoveAllDeletedFilesRequest, RemoveDeletedFileRequest, RenameFileRequest, RestoreFileVersionRequest, SearchFilesRequest, SetCompressionPolicyRequest, UnarchiveFileRequest, UndeleteFileRequest, UpdateFileContentRequest, UploadChunkRequest, UploadSessionResponse};
+use std::pin::Pin;
 use std::sync::Arc;
+use tokio::io::AsyncReadExt;
 use tokio::sync::mpsc;
 use tonic::{Request, Response, Status, Streaming};
 use uuid::Uuid;
 
+const DOWNLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
 #[derive(new)]
 pub struct GrpcFileService {
     pub app_state: Arc<AppState>,
@@ -21,10 +38,17 @@ pub struct GrpcFileService {
 
 #[async_trait]
 impl FileService for GrpcFileService {
+    type DownloadContentStream =
+        Pin<Box<dyn Stream<Item = Result<DownloadContentResponse, Status>> + Send>>;
+    type DownloadBundleStream =
+        Pin<Box<dyn Stream<Item = Result<DownloadBundleResponse, Status>> + Send>>;
+
     async fn get_file(
         &self,
         request: Request<GetFileRequest>,
     ) -> Result<Response<FileResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let file_id = map_entity_id(req.id)?;
@@ -32,9 +56,9 @@ impl FileService for GrpcFileService {
         let file = self
             .app_state
             .file_service
-            .get_by_id(file_id)
+            .get_by_id(file_id, caller.user_id)
             .await?
-            .ok_or_else(|| Status::not_found(format!("No user found with email: {}", file_id)))?;
+            .ok_or_else(|| Status::not_found(format!("No file found with id: {}", file_id)))?;
 
         Ok(Response::new(map_file_to_proto(file)))
     }
@@ -43,12 +67,14 @@ impl FileService for GrpcFileService {
         &self,
         request: Request<SearchFilesRequest>,
     ) -> Result<Response<FileListResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let files = self
             .app_state
             .file_service
-            .search_file(req.file_name)
+            .search_file(req.file_name, caller.user_id)
             .await?;
 
         let proto_files = files.into_iter().map(|f| map_file_to_proto(f)).collect();
@@ -60,14 +86,12 @@ impl FileService for GrpcFileService {
         &self,
         request: Request<GetDeletedFilesRequest>,
     ) -> Result<Response<FileListResponse>, Status> {
-        let req = request.into_inner();
-
-        let user_id = map_entity_id(req.user_id)?;
+        let caller = require_caller(&request)?;
 
         let files = self
             .app_state
             .file_service
-            .get_all_deleted_files(user_id)
+            .get_all_deleted_files(caller.user_id)
             .await?;
 
         let proto_files = files.into_iter().map(|f| map_file_to_proto(f)).collect();
@@ -79,16 +103,25 @@ impl FileService for GrpcFileService {
         &self,
         request: Request<InitFileRequest>,
     ) -> Result<Response<FileResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let destination = map_entity_id(req.destination)?;
 
-        let owner_id = map_entity_id(req.owner_id)?;
-
-        let command =
-            InitFileCommand::new(destination, owner_id, req.name, req.size, req.is_global);
+        let command = InitFileCommand::new(
+            destination,
+            req.name,
+            req.size,
+            req.is_global,
+            req.checksum,
+        );
 
-        let file = self.app_state.file_service.upload(command).await?;
+        let file = self
+            .app_state
+            .file_service
+            .upload(command, caller.user_id)
+            .await?;
 
         Ok(Response::new(map_file_to_proto(file)))
     }
@@ -97,6 +130,8 @@ impl FileService for GrpcFileService {
         &self,
         request: Request<Streaming<FileChunk>>,
     ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
         let mut stream = request.into_inner();
 
         let first_msg = match stream.message().await? {
@@ -120,7 +155,7 @@ impl FileService for GrpcFileService {
         let service_handle = tokio::spawn(async move {
             app_state_clone
                 .file_service
-                .upload_stream(file_id, rx)
+                .upload_stream(file_id, caller.user_id, rx)
                 .await
         });
 
@@ -155,10 +190,226 @@ impl FileService for GrpcFileService {
         }
     }
 
+    async fn download_content(
+        &self,
+        request: Request<DownloadContentRequest>,
+    ) -> Result<Response<Self::DownloadContentStream>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let file_id = map_entity_id(req.file_id)?;
+
+        if req.offset < 0 || req.length < 0 {
+            return Err(Status::invalid_argument(
+                "Offset and length cannot be negative",
+            ));
+        }
+
+        let start = req.offset as u64;
+        let end = match req.length {
+            0 => u64::MAX,
+            length => start.saturating_add(length as u64),
+        };
+
+        let (file, reader) = self
+            .app_state
+            .file_service
+            .get_file_for_streaming(file_id, caller.user_id, Some(start..end))
+            .await?;
+
+        let header = DownloadContentResponse {
+            data: Some(DownloadContentData::File(map_file_to_proto(file))),
+        };
+
+        // The reader is dropped after the first error, so a failed read ends the stream
+        let content = stream::unfold(Some(reader), |reader| async move {
+            let mut reader = reader?;
+            let mut buffer = vec![0u8; DOWNLOAD_CHUNK_SIZE];
+
+            match reader.read(&mut buffer).await {
+                Ok(0) => None,
+                Ok(read) => {
+                    buffer.truncate(read);
+
+                    let chunk = DownloadContentResponse {
+                        data: Some(DownloadContentData::Content(buffer)),
+                    };
+
+                    Some((Ok(chunk), Some(reader)))
+                }
+                Err(e) => Some((Err(Status::internal(e.to_string())), None)),
+            }
+        });
+
+        let output = stream::once(async move { Ok(header) }).chain(content);
+
+        Ok(Response::new(Box::pin(output) as Self::DownloadContentStream))
+    }
+
+    async fn download_bundle(
+        &self,
+        request: Request<DownloadBundleRequest>,
+    ) -> Result<Response<Self::DownloadBundleStream>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let format = map_bundle_format_from_proto(req.format)?;
+
+        let (name, reader) = match req.source {
+            Some(BundleSource::FolderId(folder_id)) => {
+                let folder_id = map_entity_id(Some(folder_id))?;
+
+                self.app_state
+                    .bundle_service
+                    .bundle_folder(folder_id, caller.user_id, format)
+                    .await?
+            }
+            Some(BundleSource::Files(files)) => {
+                let file_ids: Vec<Uuid> = files
+                    .file_ids
+                    .into_iter()
+                    .map(Some)
+                    .map(map_entity_id)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                self.app_state
+                    .bundle_service
+                    .bundle_files(&file_ids, caller.user_id, format)
+                    .await?
+            }
+            None => {
+                return Err(Status::invalid_argument(
+                    "Either a folder or files have to be chosen",
+                ))
+            }
+        };
+
+        let header = DownloadBundleResponse {
+            data: Some(DownloadBundleData::Name(name)),
+        };
+
+        // A bundle that failed half way is reported instead of ending like a complete one
+        let content = stream::unfold(Some(reader), |reader| async move {
+            let mut reader = reader?;
+            let mut buffer = vec![0u8; DOWNLOAD_CHUNK_SIZE];
+
+            match reader.read(&mut buffer).await {
+                Ok(0) => None,
+                Ok(read) => {
+                    buffer.truncate(read);
+
+                    let chunk = DownloadBundleResponse {
+                        data: Some(DownloadBundleData::Content(buffer)),
+                    };
+
+                    Some((Ok(chunk), Some(reader)))
+                }
+                Err(e) => Some((Err(Status::internal(e.to_string())), None)),
+            }
+        });
+
+        let output = stream::once(async move { Ok(header) }).chain(content);
+
+        Ok(Response::new(Box::pin(output) as Self::DownloadBundleStream))
+    }
+
+    async fn get_upload_session(
+        &self,
+        request: Request<GetUploadSessionRequest>,
+    ) -> Result<Response<UploadSessionResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let file_id = map_entity_id(req.file_id)?;
+
+        let session = self
+            .app_state
+            .file_service
+            .get_upload_session(file_id, caller.user_id)
+            .await?;
+
+        Ok(Response::new(map_upload_session_to_proto(session)))
+    }
+
+    async fn upload_chunk(
+        &self,
+        request: Request<Streaming<UploadChunkRequest>>,
+    ) -> Result<Response<UploadSessionResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let mut stream = request.into_inner();
+
+        let first_msg = match stream.message().await? {
+            Some(msg) => msg,
+            None => return Err(Status::invalid_argument("Stream cannot be empty")),
+        };
+
+        let (file_id, offset) = match first_msg.data {
+            Some(UploadChunkData::Header(header)) => (map_entity_id(header.file_id)?, header.offset),
+            Some(UploadChunkData::Content(_)) => {
+                return Err(Status::invalid_argument(
+                    "First message must be the chunk header, not content",
+                ));
+            }
+            None => return Err(Status::invalid_argument("First message empty")),
+        };
+        let (tx, rx) = mpsc::channel(32);
+
+        let app_state_clone = self.app_state.clone();
+
+        let service_handle = tokio::spawn(async move {
+            app_state_clone
+                .file_service
+                .upload_chunk(file_id, caller.user_id, offset, rx)
+                .await
+        });
+
+        loop {
+            match stream.message().await {
+                Ok(Some(msg)) => match msg.data {
+                    Some(UploadChunkData::Content(bytes)) => {
+                        if tx.send(Ok(bytes)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(UploadChunkData::Header(_)) => {
+                        return Err(Status::invalid_argument(
+                            "Received a chunk header inside content stream",
+                        ));
+                    }
+                    None => continue,
+                },
+                Ok(None) => break,
+                // The bytes received so far are still committed, the client resumes from there
+                Err(status) => {
+                    let _ = tx
+                        .send(Err(DataError::UploadInterrupter(status.to_string())))
+                        .await;
+                    break;
+                }
+            }
+        }
+
+        drop(tx);
+
+        match service_handle.await {
+            Ok(service_result) => {
+                let session = service_result.map_err(|e| Status::from(e))?;
+                Ok(Response::new(map_upload_session_to_proto(session)))
+            }
+            Err(_) => Err(Status::internal("Upload task panicked")),
+        }
+    }
+
     async fn rename_file(
         &self,
         request: Request<RenameFileRequest>,
     ) -> Result<Response<FileResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let file_id = map_entity_id(req.id)?;
@@ -168,7 +419,7 @@ impl FileService for GrpcFileService {
         let file = self
             .app_state
             .file_service
-            .update_file_name(command, file_id)
+            .update_file_name(command, file_id, caller.user_id)
             .await?;
 
         Ok(Response::new(map_file_to_proto(file)))
@@ -178,6 +429,8 @@ impl FileService for GrpcFileService {
         &self,
         request: Request<UndeleteFileRequest>,
     ) -> Result<Response<FileResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let file_id = map_entity_id(req.id)?;
@@ -185,7 +438,7 @@ impl FileService for GrpcFileService {
         let file = self
             .app_state
             .file_service
-            .update_deleted_file(file_id)
+            .update_deleted_file(file_id, caller.user_id)
             .await?;
 
         Ok(Response::new(map_file_to_proto(file)))
@@ -195,6 +448,8 @@ impl FileService for GrpcFileService {
         &self,
         request: Request<DeleteChosenFilesRequest>,
     ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let file_ids: Vec<Uuid> = req
@@ -205,7 +460,7 @@ impl FileService for GrpcFileService {
 
         self.app_state
             .file_service
-            .delete_chosen_files(&file_ids)
+            .delete_chosen_files(&file_ids, caller.user_id)
             .await?;
 
         Ok(Response::new(()))
@@ -215,11 +470,16 @@ impl FileService for GrpcFileService {
         &self,
         request: Request<DeleteFileRequest>,
     ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let file_id = map_entity_id(req.id)?;
 
-        self.app_state.file_service.delete(file_id).await?;
+        self.app_state
+            .file_service
+            .delete(file_id, caller.user_id)
+            .await?;
 
         Ok(Response::new(()))
     }
@@ -228,6 +488,8 @@ impl FileService for GrpcFileService {
         &self,
         request: Request<MoveFileRequest>,
     ) -> Result<Response<FileResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let file_id = map_entity_id(req.file_id)?;
@@ -238,7 +500,11 @@ impl FileService for GrpcFileService {
 
         let command = MoveFileCommand::new(folder_id, file_id);
 
-        let file = self.app_state.file_service.move_file(command).await?;
+        let file = self
+            .app_state
+            .file_service
+            .move_file(command, caller.user_id)
+            .await?;
 
         Ok(Response::new(map_file_to_proto(file)))
     }
@@ -247,6 +513,8 @@ impl FileService for GrpcFileService {
         &self,
         request: Request<CopyFileRequest>,
     ) -> Result<Response<FileResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let file_id = map_entity_id(req.file_id)?;
@@ -255,15 +523,21 @@ impl FileService for GrpcFileService {
 
         let command = CopyFileCommand::new(file_id, target_folder_id);
 
-        let file = self.app_state.file_service.copy_file(command).await?;
+        let file = self
+            .app_state
+            .file_service
+            .copy_file(command, caller.user_id)
+            .await?;
 
         Ok(Response::new(map_file_to_proto(file)))
     }
 
     async fn update_file_content(
         &self,
-        request: Request<Streaming<FileChunk>>,
-    ) -> Result<Response<()>, Status> {
+        request: Request<Streaming<UpdateFileContentRequest>>,
+    ) -> Result<Response<FileResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let mut stream = request.into_inner();
 
         let first_msg = match stream.message().await? {
@@ -271,11 +545,20 @@ impl FileService for GrpcFileService {
             None => return Err(Status::invalid_argument("Stream cannot be empty")),
         };
 
-        let file_id = match first_msg.data {
-            Some(FileChunkData::FileId(id_msg)) => map_entity_id(Some(id_msg))?,
-            Some(FileChunkData::Content(_)) => {
+        let (file_id, command) = match first_msg.data {
+            Some(UpdateContentData::Header(header)) => (
+                map_entity_id(header.file_id)?,
+                UpdateFileContentCommand::new(
+                    header
+                        .expected_updated_at
+                        .map(map_timestamp_from_proto)
+                        .transpose()?,
+                    header.expected_checksum,
+                ),
+            ),
+            Some(UpdateContentData::Content(_)) => {
                 return Err(Status::invalid_argument(
-                    "First message must be File ID, not content",
+                    "First message must be the content header, not content",
                 ));
             }
             None => return Err(Status::invalid_argument("First message empty")),
@@ -287,26 +570,32 @@ impl FileService for GrpcFileService {
         let service_handle = tokio::spawn(async move {
             app_state_clone
                 .file_service
-                .update_stream(file_id, rx)
+                .update_stream(file_id, caller.user_id, command, rx)
                 .await
         });
 
-        while let Ok(Some(msg)) = stream.message().await {
-            match msg.data {
-                Some(FileChunkData::Content(bytes)) => {
-                    if tx.send(Ok(bytes)).await.is_err() {
-                        break;
+        loop {
+            match stream.message().await {
+                Ok(Some(msg)) => match msg.data {
+                    Some(UpdateContentData::Content(bytes)) => {
+                        if tx.send(Ok(bytes)).await.is_err() {
+                            break;
+                        }
                     }
-                }
-
-                Some(FileChunkData::FileId(_)) => {
-                    return Err(Status::invalid_argument(
-                        "Received File ID inside content stream",
-                    ));
-                }
-
-                None => {
-                    continue;
+                    Some(UpdateContentData::Header(_)) => {
+                        return Err(Status::invalid_argument(
+                            "Received a content header inside content stream",
+                        ));
+                    }
+                    None => continue,
+                },
+                Ok(None) => break,
+                // Unlike chunked uploads nothing is kept, the file keeps its current content
+                Err(status) => {
+                    let _ = tx
+                        .send(Err(DataError::UploadInterrupter(status.to_string())))
+                        .await;
+                    break;
                 }
             }
         }
@@ -315,22 +604,89 @@ impl FileService for GrpcFileService {
 
         match service_handle.await {
             Ok(service_result) => {
-                service_result.map_err(|e| Status::from(e))?;
-                Ok(Response::new(()))
+                let file = service_result.map_err(|e| Status::from(e))?;
+                Ok(Response::new(map_file_to_proto(file)))
             }
-            Err(_) => Err(Status::internal("Upload task panicked")),
+            Err(_) => Err(Status::internal("Update task panicked")),
+        }
+    }
+
+    async fn get_preview(
+        &self,
+        request: Request<GetPreviewRequest>,
+    ) -> Result<Response<PreviewResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let file_id = map_entity_id(req.file_id)?;
+
+        let preview = self
+            .app_state
+            .file_service
+            .get_preview(file_id, caller.user_id)
+            .await?;
+
+        // The client's copy is still current, so the bytes are not sent again
+        if req.if_none_match.as_deref() == Some(preview.etag.as_str()) {
+            return Ok(Response::new(PreviewResponse {
+                content: Vec::new(),
+                content_type: preview.content_type,
+                etag: preview.etag,
+                not_modified: true,
+            }));
         }
+
+        Ok(Response::new(PreviewResponse {
+            content: preview.content,
+            content_type: preview.content_type,
+            etag: preview.etag,
+            not_modified: false,
+        }))
+    }
+
+    async fn regenerate_previews(
+        &self,
+        request: Request<RegeneratePreviewsRequest>,
+    ) -> Result<Response<RegeneratePreviewsResponse>, Status> {
+        require_admin(&request)?;
+
+        let req = request.into_inner();
+
+        let file_ids = req
+            .file_ids
+            .into_iter()
+            .map(Some)
+            .map(map_entity_id)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let queued = self
+            .app_state
+            .preview_service
+            .regenerate(RegeneratePreviewsCommand::new(file_ids, req.only_failed))
+            .await?;
+
+        Ok(Response::new(RegeneratePreviewsResponse {
+            queued: queued as i64,
+        }))
     }
 
     async fn archive_file(
         &self,
         request: Request<ArchiveFileRequest>,
     ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let file_id = map_entity_id(req.file_id)?;
 
-        self.app_state.file_service.archive_file(file_id).await?;
+        let codec = map_archive_codec_from_proto(req.codec)?;
+
+        self.app_state
+            .file_service
+            .archive_file(file_id, caller.user_id, codec)
+            .await?;
 
         Ok(Response::new(()))
     }
@@ -339,38 +695,252 @@ impl FileService for GrpcFileService {
         &self,
         request: Request<UnarchiveFileRequest>,
     ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let file_id = map_entity_id(req.file_id)?;
 
-        self.app_state.file_service.unarchive_file(file_id).await?;
+        self.app_state
+            .file_service
+            .unarchive_file(file_id, caller.user_id)
+            .await?;
 
         Ok(Response::new(()))
     }
 
+    async fn list_archive_entries(
+        &self,
+        request: Request<ListArchiveEntriesRequest>,
+    ) -> Result<Response<ArchiveEntryListResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let file_id = map_entity_id(req.file_id)?;
+
+        let entries = self
+            .app_state
+            .archive_content_service
+            .list_entries(file_id, caller.user_id)
+            .await?;
+
+        let proto_entries = entries
+            .into_iter()
+            .map(|e| map_archive_entry_to_proto(e))
+            .collect();
+
+        Ok(Response::new(ArchiveEntryListResponse {
+            entries: proto_entries,
+        }))
+    }
+
+    async fn extract_archive(
+        &self,
+        request: Request<ExtractArchiveRequest>,
+    ) -> Result<Response<ExtractArchiveResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let file_id = map_entity_id(req.file_id)?;
+        let target_folder_id = map_entity_id(req.target_folder_id)?;
+
+        let summary = self
+            .app_state
+            .archive_content_service
+            .extract(
+                file_id,
+                caller.user_id,
+                ExtractArchiveCommand::new(target_folder_id, req.entries),
+            )
+            .await?;
+
+        Ok(Response::new(map_extraction_summary_to_proto(summary)))
+    }
+
     async fn remove_all_deleted_files(
         &self,
         request: Request<RemoveAllDeletedFilesRequest>,
     ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
+        self.app_state
+            .file_service
+            .cleanup_deleted_files(caller.user_id)
+            .await?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn remove_delete_file(&self, request: Request<RemoveDeletedFileRequest>) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
-        let user_id = map_entity_id(req.user_id)?;
+        let file_id = map_entity_id(req.file_id)?;
 
         self.app_state
             .file_service
-            .cleanup_deleted_files(user_id)
+            .remove_deleted_file(file_id, caller.user_id)
             .await?;
 
         Ok(Response::new(()))
     }
 
-    async fn remove_delete_file(&self, request: Request<RemoveDeletedFileRequest>) -> Result<Response<()>, Status> {
+    async fn list_file_versions(
+        &self,
+        request: Request<ListFileVersionsRequest>,
+    ) -> Result<Response<FileVersionListResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let file_id = map_entity_id(req.file_id)?;
+
+        let versions = self
+            .app_state
+            .file_service
+            .list_file_versions(file_id, caller.user_id)
+            .await?;
+
+        let proto_versions = versions
+            .into_iter()
+            .map(|v| map_file_version_to_proto(v))
+            .collect();
+
+        Ok(Response::new(FileVersionListResponse {
+            versions: proto_versions,
+        }))
+    }
+
+    async fn restore_file_version(
+        &self,
+        request: Request<RestoreFileVersionRequest>,
+    ) -> Result<Response<FileResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let file_id = map_entity_id(req.file_id)?;
+        let version_id = map_entity_id(req.version_id)?;
+
+        let file = self
+            .app_state
+            .file_service
+            .restore_file_version(file_id, version_id, caller.user_id)
+            .await?;
+
+        Ok(Response::new(map_file_to_proto(file)))
+    }
+
+    async fn delete_file_version(
+        &self,
+        request: Request<DeleteFileVersionRequest>,
+    ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let file_id = map_entity_id(req.file_id)?;
+        let version_id = map_entity_id(req.version_id)?;
+
+        self.app_state
+            .file_service
+            .delete_file_version(file_id, version_id, caller.user_id)
+            .await?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn get_compression_policy(
+        &self,
+        request: Request<GetCompressionPolicyRequest>,
+    ) -> Result<Response<CompressionPolicyResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let user_id = if req.global {
+            None
+        } else {
+            Some(caller.user_id)
+        };
+
+        let policy = self
+            .app_state
+            .compression_policy_service
+            .get_policy(user_id)
+            .await?
+            .ok_or_else(|| Status::not_found("No compression policy applies"))?;
+
+        Ok(Response::new(map_compression_policy_to_proto(policy)))
+    }
+
+    async fn set_compression_policy(
+        &self,
+        request: Request<SetCompressionPolicyRequest>,
+    ) -> Result<Response<CompressionPolicyResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let user_id = map_policy_owner(&caller, req.global)?;
+
+        let excluded_types = req
+            .excluded_types
+            .into_iter()
+            .map(map_file_type_from_proto)
+            .collect::<Result<Vec<_>, Status>>()?;
+
+        let command = SetCompressionPolicyCommand::new(
+            req.min_idle_days,
+            req.min_size,
+            map_archive_codec_from_proto(req.codec)?,
+            excluded_types,
+            req.is_enabled,
+        );
+
+        let policy = self
+            .app_state
+            .compression_policy_service
+            .set_policy(user_id, command)
+            .await?;
+
+        Ok(Response::new(map_compression_policy_to_proto(policy)))
+    }
+
+    async fn delete_compression_policy(
+        &self,
+        request: Request<DeleteCompressionPolicyRequest>,
+    ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
 
-        self.app_state.file_service.remove_deleted_file(file_id).await?;
+        let user_id = map_policy_owner(&caller, req.global)?;
+
+        self.app_state
+            .compression_policy_service
+            .delete_policy(user_id)
+            .await?;
 
         Ok(Response::new(()))
     }
 }
+
+// The global policy covers every user, so only admins may change it
+#[allow(clippy::result_large_err)]
+fn map_policy_owner(caller: &CallerIdentity, global: bool) -> Result<Option<Uuid>, Status> {
+    if !global {
+        return Ok(Some(caller.user_id));
+    }
+
+    if !caller.is_admin() {
+        return Err(Status::permission_denied(
+            "Only admins can change the global compression policy",
+        ));
+    }
+
+    Ok(None)
+}
@@ -4,6 +4,7 @@ use crate::helpers::proto_mappers::{
 };
 use crate::AppState;
 use derive_new::new;
+use homelab_core::auth::grpc_interceptor::require_caller;
 use homelab_proto::nas::file_label_service_server::FileLabelService;
 use homelab_proto::nas::{
     CreateFileLabelRequest, FileLabelResponse, FileListResponse, GetFilesForLabelRequest,
@@ -23,6 +24,8 @@ impl FileLabelService for GrpcFileLabelService {
         &self,
         request: Request<CreateFileLabelRequest>,
     ) -> Result<Response<FileLabelResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let file_id = map_entity_id(req.file_id)?;
@@ -33,7 +36,7 @@ impl FileLabelService for GrpcFileLabelService {
         let fl = self
             .app_state
             .file_label_service
-            .create_file_label(command)
+            .create_file_label(command, caller.user_id)
             .await?;
 
         Ok(Response::new(map_file_label_to_proto(fl)))
@@ -43,10 +46,11 @@ impl FileLabelService for GrpcFileLabelService {
         &self,
         request: Request<GetLabelsForFileRequest>,
     ) -> Result<Response<LabelListResponse>, Status> {
+        let owner_id = require_caller(&request)?.user_id;
+
         let req = request.into_inner();
 
         let file_id = map_entity_id(req.file_id)?;
-        let owner_id = map_entity_id(req.owner_id)?;
 
         let labels = self
             .app_state
@@ -65,10 +69,11 @@ impl FileLabelService for GrpcFileLabelService {
         &self,
         request: Request<GetFilesForLabelRequest>,
     ) -> Result<Response<FileListResponse>, Status> {
+        let owner_id = require_caller(&request)?.user_id;
+
         let req = request.into_inner();
 
         let label_id = map_entity_id(req.label_id)?;
-        let owner_id = map_entity_id(req.owner_id)?;
 
         let files = self
             .app_state
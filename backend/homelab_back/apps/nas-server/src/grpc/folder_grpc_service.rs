@@ -7,6 +7,7 @@ use homelab_proto::nas::folder_service_server::FolderService;
 use homelab_proto::nas::{CreateFolderRequest, DeleteAllFolderRequest, DeleteFolderRequest, FileListResponse, FolderResponse, FolderResponseList, GetAllSubfoldersRequest, GetFilesForFolderRequest, GetFolderRequest, GetRootFolderRequest, MoveFolderRequest, RenameFolderRequest, SearchFolderRequest};
 use std::sync::Arc;
 use derive_new::new;
+use homelab_core::auth::grpc_interceptor::require_caller;
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
 use crate::data::create_folder_command::CreateFolderCommand;
@@ -19,9 +20,7 @@ pub struct GrpcFolderService {
 #[async_trait]
 impl FolderService for GrpcFolderService {
     async fn get_root_folder(&self, request: Request<GetRootFolderRequest>) -> Result<Response<FolderResponse>, Status> {
-        let req = request.into_inner();
-
-        let user_id = map_entity_id(req.user_id)?;
+        let user_id = require_caller(&request)?.user_id;
 
         let folder = self
             .app_state
@@ -33,10 +32,27 @@ impl FolderService for GrpcFolderService {
         Ok(Response::new(map_folder_to_proto(folder)))
     }
 
+    async fn get_shared_root_folder(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<FolderResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let folder = self
+            .app_state
+            .folder_service
+            .get_shared_root(caller.user_id)
+            .await?;
+
+        Ok(Response::new(map_folder_to_proto(folder)))
+    }
+
     async fn get_folder(
         &self,
         request: Request<GetFolderRequest>,
     ) -> Result<Response<FolderResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let folder_id = map_entity_id(req.id)?;
@@ -44,7 +60,7 @@ impl FolderService for GrpcFolderService {
         let folder = self
             .app_state
             .folder_service
-            .get_by_id(folder_id)
+            .get_by_id(folder_id, caller.user_id)
             .await?
             .ok_or_else(|| Status::not_found(format!("No folder found with id: {}", folder_id)))?;
 
@@ -55,6 +71,8 @@ impl FolderService for GrpcFolderService {
         &self,
         request: Request<GetAllSubfoldersRequest>,
     ) -> Result<Response<FolderResponseList>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let folder_id = map_entity_id(req.id)?;
@@ -62,7 +80,7 @@ impl FolderService for GrpcFolderService {
         let folders = self
             .app_state
             .folder_service
-            .get_children_by_id(folder_id)
+            .get_children_by_id(folder_id, caller.user_id)
             .await?;
 
         let proto_folders = folders
@@ -79,11 +97,16 @@ impl FolderService for GrpcFolderService {
         &self,
         request: Request<DeleteFolderRequest>,
     ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let folder_id = map_entity_id(req.id)?;
 
-        self.app_state.folder_service.delete(folder_id).await?;
+        self.app_state
+            .folder_service
+            .delete(folder_id, caller.user_id)
+            .await?;
 
         Ok(Response::new(()))
     }
@@ -92,6 +115,8 @@ impl FolderService for GrpcFolderService {
         &self,
         request: Request<GetFilesForFolderRequest>,
     ) -> Result<Response<FileListResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let folder_id = map_entity_id(req.id)?;
@@ -99,7 +124,7 @@ impl FolderService for GrpcFolderService {
         let files = self
             .app_state
             .folder_service
-            .get_by_folder(folder_id)
+            .get_by_folder(folder_id, caller.user_id)
             .await?;
 
         let proto_files = files.into_iter().map(|f| map_file_to_proto(f)).collect();
@@ -111,6 +136,8 @@ impl FolderService for GrpcFolderService {
         &self,
         request: Request<RenameFolderRequest>,
     ) -> Result<Response<FolderResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let folder_id = map_entity_id(req.id)?;
@@ -120,7 +147,7 @@ impl FolderService for GrpcFolderService {
         let folder = self
             .app_state
             .folder_service
-            .update_folder_name(command, folder_id)
+            .update_folder_name(command, folder_id, caller.user_id)
             .await?;
 
         Ok(Response::new(map_folder_to_proto(folder)))
@@ -130,12 +157,14 @@ impl FolderService for GrpcFolderService {
         &self,
         request: Request<SearchFolderRequest>,
     ) -> Result<Response<FolderResponseList>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let folders = self
             .app_state
             .folder_service
-            .search_folder(req.query)
+            .search_folder(req.query, caller.user_id)
             .await?;
 
         let proto_folders = folders
@@ -152,6 +181,8 @@ impl FolderService for GrpcFolderService {
         &self,
         request: Request<DeleteAllFolderRequest>,
     ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let folder_ids: Vec<Uuid> = req
@@ -162,7 +193,7 @@ impl FolderService for GrpcFolderService {
 
         self.app_state
             .folder_service
-            .delete_chosen_folders(&folder_ids)
+            .delete_chosen_folders(&folder_ids, caller.user_id)
             .await?;
 
         Ok(Response::new(()))
@@ -172,6 +203,8 @@ impl FolderService for GrpcFolderService {
         &self,
         request: Request<MoveFolderRequest>,
     ) -> Result<Response<FolderResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let target_folder = map_entity_id(req.target_folder)?;
@@ -180,22 +213,26 @@ impl FolderService for GrpcFolderService {
 
         let command = MoveFolderCommand::new(target_folder, folder_id);
 
-        let folder = self.app_state.folder_service.move_folder(command).await?;
+        let folder = self
+            .app_state
+            .folder_service
+            .move_folder(command, caller.user_id)
+            .await?;
 
         Ok(Response::new(map_folder_to_proto(folder)))
     }
 
     async fn create_folder(&self, request: Request<CreateFolderRequest>) -> Result<Response<FolderResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let parent_folder_id = map_entity_id(req.parent_folder_id)?;
 
-        let owner_id = map_entity_id(req.owner_id)?;
-
-        let command = CreateFolderCommand::new(parent_folder_id, req.name, owner_id);
+        let command = CreateFolderCommand::new(parent_folder_id, req.name);
 
         let folder = self.app_state.folder_service
-            .create(command).await?;
+            .create(command, caller.user_id).await?;
 
         Ok(Response::new(map_folder_to_proto(folder)))
     }
@@ -3,6 +3,7 @@ use crate::data::create_label_command::CreateLabelCommand;
 use crate::helpers::proto_mappers::{map_entity_id, map_label_to_proto};
 use crate::AppState;
 use derive_new::new;
+use homelab_core::auth::grpc_interceptor::require_caller;
 use homelab_proto::nas::label_service_server::LabelService;
 use homelab_proto::nas::{
     ChangeLabelRequest, CreateLabelRequest, DeleteLabelRequest, LabelListResponse, LabelResponse,
@@ -17,8 +18,13 @@ pub struct GrpcLabelService {
 
 #[tonic::async_trait]
 impl LabelService for GrpcLabelService {
-    async fn get_labels(&self, _: Request<()>) -> Result<Response<LabelListResponse>, Status> {
-        let labels = self.app_state.label_service.get_all().await?;
+    async fn get_labels(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<LabelListResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let labels = self.app_state.label_service.get_all(caller.user_id).await?;
 
         let proto_labels = labels.into_iter().map(|l| map_label_to_proto(l)).collect();
 
@@ -31,13 +37,17 @@ impl LabelService for GrpcLabelService {
         &self,
         request: Request<CreateLabelRequest>,
     ) -> Result<Response<LabelResponse>, Status> {
-        let req = request.into_inner();
+        let caller = require_caller(&request)?;
 
-        let owner_id = map_entity_id(req.owner_id)?;
+        let req = request.into_inner();
 
-        let command = CreateLabelCommand::new(req.name, req.color, owner_id);
+        let command = CreateLabelCommand::new(req.name, req.color);
 
-        let label = self.app_state.label_service.create_label(command).await?;
+        let label = self
+            .app_state
+            .label_service
+            .create_label(command, caller.user_id)
+            .await?;
 
         Ok(Response::new(map_label_to_proto(label)))
     }
@@ -46,11 +56,16 @@ impl LabelService for GrpcLabelService {
         &self,
         request: Request<DeleteLabelRequest>,
     ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let label_id = map_entity_id(req.id)?;
 
-        self.app_state.label_service.delete_label(label_id).await?;
+        self.app_state
+            .label_service
+            .delete_label(label_id, caller.user_id)
+            .await?;
 
         Ok(Response::new(()))
     }
@@ -59,13 +74,19 @@ impl LabelService for GrpcLabelService {
         &self,
         request: Request<ChangeLabelRequest>,
     ) -> Result<Response<LabelResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let label_id = map_entity_id(req.id)?;
 
         let command = ChangeLabelCommand::new(label_id, req.name, req.color);
 
-        let label = self.app_state.label_service.change_label(command).await?;
+        let label = self
+            .app_state
+            .label_service
+            .change_label(command, caller.user_id)
+            .await?;
 
         Ok(Response::new(map_label_to_proto(label)))
     }
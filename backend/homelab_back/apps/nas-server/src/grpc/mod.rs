@@ -3,4 +3,5 @@ pub mod file_label_grpc_service;
 pub mod folder_grpc_service;
 pub mod global_file_grpc_service;
 pub mod grpc_label_service;
+pub mod shared_file_grpc_service;
 pub mod storage_profile_grpc_service;
@@ -0,0 +1,113 @@
+use crate::data::create_shared_file_command::CreateSharedFileCommand;
+use crate::data::create_shared_folder_command::CreateSharedFolderCommand;
+use crate::helpers::proto_mappers::{
+    map_access_type_from_proto, map_entity_id, map_shared_file_to_proto, map_shared_folder_to_proto,
+};
+use crate::AppState;
+use derive_new::new;
+use homelab_core::auth::grpc_interceptor::require_caller;
+use homelab_proto::nas::shared_file_service_server::SharedFileService;
+use homelab_proto::nas::{
+    ShareFileRequest, ShareFolderRequest, SharedFileListResponse, SharedFileResponse,
+    SharedFolderListResponse, SharedFolderResponse,
+};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+#[derive(new)]
+pub struct GrpcSharedFileService {
+    pub app_state: Arc<AppState>,
+}
+
+#[tonic::async_trait]
+impl SharedFileService for GrpcSharedFileService {
+    async fn share_file(
+        &self,
+        request: Request<ShareFileRequest>,
+    ) -> Result<Response<SharedFileResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let file_id = map_entity_id(req.file_id)?;
+        let user_id = map_entity_id(req.user_id)?;
+        let access_type = map_access_type_from_proto(req.access_type)?;
+
+        let command = CreateSharedFileCommand::new(user_id, file_id, access_type);
+
+        let shared_file = self
+            .app_state
+            .shared_file_service
+            .create_shared_file(command, caller.user_id)
+            .await?;
+
+        Ok(Response::new(map_shared_file_to_proto(shared_file)))
+    }
+
+    async fn get_shared_files(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<SharedFileListResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let shared_files = self
+            .app_state
+            .shared_file_service
+            .get_all_shared_files_per_user(caller.user_id)
+            .await?;
+
+        let proto_shared_files = shared_files
+            .into_iter()
+            .map(map_shared_file_to_proto)
+            .collect();
+
+        Ok(Response::new(SharedFileListResponse {
+            shared_files: proto_shared_files,
+        }))
+    }
+
+    async fn share_folder(
+        &self,
+        request: Request<ShareFolderRequest>,
+    ) -> Result<Response<SharedFolderResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let folder_id = map_entity_id(req.folder_id)?;
+        let user_id = map_entity_id(req.user_id)?;
+        let access_type = map_access_type_from_proto(req.access_type)?;
+
+        let command = CreateSharedFolderCommand::new(user_id, folder_id, access_type);
+
+        let shared_folder = self
+            .app_state
+            .shared_file_service
+            .create_shared_folder(command, caller.user_id)
+            .await?;
+
+        Ok(Response::new(map_shared_folder_to_proto(shared_folder)))
+    }
+
+    async fn get_shared_folders(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<SharedFolderListResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let shared_folders = self
+            .app_state
+            .shared_file_service
+            .get_all_shared_folders_per_user(caller.user_id)
+            .await?;
+
+        let proto_shared_folders = shared_folders
+            .into_iter()
+            .map(map_shared_folder_to_proto)
+            .collect();
+
+        Ok(Response::new(SharedFolderListResponse {
+            shared_folders: proto_shared_folders,
+        }))
+    }
+}
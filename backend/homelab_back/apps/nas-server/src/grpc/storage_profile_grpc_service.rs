@@ -1,8 +1,9 @@
 use std::sync::Arc;
 use async_trait::async_trait;
 use derive_new::new;
+use homelab_core::auth::grpc_interceptor::{require_admin, require_caller};
 use tonic::{Request, Response, Status};
-use homelab_proto::nas::{GetStorageProfileByIdRequest, StorageProfileResponse};
+use homelab_proto::nas::{GetStorageProfileByIdRequest, RecalculateAllUsageRequest, RecalculateAllUsageResponse, RecalculateUsageRequest, StorageProfileResponse};
 use homelab_proto::nas::storage_profile_service_server::StorageProfileService;
 use crate::AppState;
 use crate::helpers::proto_mappers::{map_entity_id, map_storage_profile_to_proto};
@@ -16,10 +17,16 @@ pub struct GrpcStorageProfileService {
 #[async_trait]
 impl StorageProfileService for GrpcStorageProfileService {
     async fn get_by_id(&self, request: Request<GetStorageProfileByIdRequest>) -> Result<Response<StorageProfileResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let id = map_entity_id(req.id)?;
 
+        if !caller.can_access_user(id) {
+            return Err(Status::permission_denied("Not allowed to access this storage profile"));
+        }
+
         let sp = self.app_state
             .storage_profile_service
             .get_by_id(id)
@@ -28,4 +35,39 @@ impl StorageProfileService for GrpcStorageProfileService {
 
         Ok(Response::new(map_storage_profile_to_proto(sp)))
     }
+
+    async fn recalculate_usage(&self, request: Request<RecalculateUsageRequest>) -> Result<Response<StorageProfileResponse>, Status> {
+        let caller = require_caller(&request)?;
+
+        let req = request.into_inner();
+
+        let id = map_entity_id(req.id)?;
+
+        if !caller.can_access_user(id) {
+            return Err(Status::permission_denied("Not allowed to access this storage profile"));
+        }
+
+        let sp = self.app_state
+            .storage_profile_service
+            .recalculate_usage(id, req.from_disk)
+            .await?;
+
+        Ok(Response::new(map_storage_profile_to_proto(sp)))
+    }
+
+    async fn recalculate_all_usage(&self, request: Request<RecalculateAllUsageRequest>) -> Result<Response<RecalculateAllUsageResponse>, Status> {
+        require_admin(&request)?;
+
+        let req = request.into_inner();
+
+        let summary = self.app_state
+            .storage_profile_service
+            .recalculate_all_usage(req.from_disk)
+            .await?;
+
+        Ok(Response::new(RecalculateAllUsageResponse {
+            checked: summary.checked as i32,
+            corrected: summary.corrected as i32,
+        }))
+    }
 }
\ No newline at end of file
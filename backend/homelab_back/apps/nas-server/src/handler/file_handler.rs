@@ -1,42 +1,103 @@
 use crate::data::delete_chosen_files_command::DeleteChosenFilesCommand;
+use crate::data::download_bundle_command::DownloadBundleCommand;
 use crate::data::init_file_command::InitFileCommand;
 use crate::data::search_query::SearchQuery;
 use crate::data::update_file_name_command::UpdateFileNameCommand;
+use crate::data::upload_chunk_query::UploadChunkQuery;
+use crate::helpers::data_error::DataError;
+use crate::helpers::download::{
+    bundle_response, download_response, not_modified_response, partial_download_response,
+    plan_download, preview_response, range_not_satisfiable_response, DownloadPlan,
+};
 use crate::helpers::error_mapping::map_data_err_to_http;
 use crate::AppState;
-use actix_files::NamedFile;
-use actix_web::web::{Data, Json, Path, Query, ServiceConfig};
-use actix_web::{delete, error, get, patch, post, HttpResponse, Responder};
+use actix_web::web::{Data, Json, Path, Payload, Query, ServiceConfig};
+use actix_web::{delete, get, patch, post, put, HttpRequest, HttpResponse, Responder};
+use futures::StreamExt;
+use homelab_core::auth::caller::CallerIdentity;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
 #[get("/files/{id}/download")]
 async fn download_file(
+    req: HttpRequest,
     file_id: Path<Uuid>,
     app_state: Data<AppState>,
-) -> actix_web::Result<NamedFile> {
+    caller: CallerIdentity,
+) -> HttpResponse {
     let id = file_id.into_inner();
 
-    let path = match app_state.file_service.get_file_for_streaming(id).await {
-        Ok(path) => path,
+    let file = match app_state.file_service.get_by_id(id, caller.user_id).await {
+        Ok(Some(file)) => file,
+        Ok(None) => return HttpResponse::NotFound().body("File not found or access denied"),
         Err(e) => {
             tracing::error!("Failed to download a file: {:?}", e);
-            return Err(error::ErrorNotFound("File not found or access denied"));
+            return HttpResponse::NotFound().body("File not found or access denied");
         }
     };
 
-    let named_file = NamedFile::open(path).map_err(|e| {
-        eprintln!("File exists in DB but not on disk: {:?}", e);
-        error::ErrorNotFound("File content is missing")
-    })?;
+    // Conditions are settled on the row first, so a cached or unsatisfiable request never opens the content
+    let range = match plan_download(&file, &req) {
+        DownloadPlan::NotModified => return not_modified_response(&file),
+        DownloadPlan::Unsatisfiable => return range_not_satisfiable_response(&file),
+        DownloadPlan::Full => None,
+        DownloadPlan::Partial(range) => Some(range),
+    };
+
+    match app_state
+        .file_service
+        .get_file_for_streaming(id, caller.user_id, range.clone())
+        .await
+    {
+        Ok((file, reader)) => match range {
+            // The content may have shrunk in between, the range is cut to what is actually sent
+            Some(range) => {
+                let end = range.end.min(file.size as u64);
+
+                if end <= range.start {
+                    return range_not_satisfiable_response(&file);
+                }
+
+                partial_download_response(&file, reader, range.start..end)
+            }
+            None => download_response(&file, reader),
+        },
+        Err(e) => {
+            tracing::error!("Failed to download a file: {:?}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
 
-    Ok(named_file)
+#[get("/files/{id}/preview")]
+async fn get_preview(
+    req: HttpRequest,
+    file_id: Path<Uuid>,
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+) -> HttpResponse {
+    match app_state
+        .file_service
+        .get_preview(file_id.into_inner(), caller.user_id)
+        .await
+    {
+        Ok(preview) => preview_response(preview, &req),
+        Err(e) => {
+            tracing::error!("Failed to get a file preview: {:?}", e);
+            map_data_err_to_http(e)
+        }
+    }
 }
 
 #[get("/files/{id}")]
-pub async fn get_file(app_state: Data<AppState>, file_id: Path<Uuid>) -> impl Responder {
+pub async fn get_file(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    file_id: Path<Uuid>,
+) -> impl Responder {
     let id = file_id.into_inner();
 
-    match app_state.file_service.get_by_id(id).await {
+    match app_state.file_service.get_by_id(id, caller.user_id).await {
         Ok(Some(file)) => HttpResponse::Ok().json(file),
         Ok(None) => HttpResponse::NotFound()
             .body(format!("Was not able to find file with a given id: {}", id)),
@@ -48,10 +109,18 @@ pub async fn get_file(app_state: Data<AppState>, file_id: Path<Uuid>) -> impl Re
 }
 
 #[get("/files/search")]
-pub async fn search_file(app_state: Data<AppState>, query: Query<SearchQuery>) -> impl Responder {
+pub async fn search_file(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    query: Query<SearchQuery>,
+) -> impl Responder {
     let search_term = query.into_inner().q;
 
-    match app_state.file_service.search_file(search_term).await {
+    match app_state
+        .file_service
+        .search_file(search_term, caller.user_id)
+        .await
+    {
         Ok(f) => HttpResponse::Ok().json(f),
         Err(e) => {
             tracing::error!("Failed to search for a file: {}", e);
@@ -61,8 +130,16 @@ pub async fn search_file(app_state: Data<AppState>, query: Query<SearchQuery>) -
 }
 
 #[post("/files")]
-pub async fn init_file(app_state: Data<AppState>, req: Json<InitFileCommand>) -> impl Responder {
-    match app_state.file_service.upload(req.into_inner()).await {
+pub async fn init_file(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    req: Json<InitFileCommand>,
+) -> impl Responder {
+    match app_state
+        .file_service
+        .upload(req.into_inner(), caller.user_id)
+        .await
+    {
         Ok(file) => HttpResponse::Created().json(file.id),
         Err(e) => {
             tracing::error!("Failed while creating a file: {}", e);
@@ -71,9 +148,73 @@ pub async fn init_file(app_state: Data<AppState>, req: Json<InitFileCommand>) ->
     }
 }
 
+#[get("/files/{id}/upload")]
+pub async fn get_upload_session(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    id: Path<Uuid>,
+) -> impl Responder {
+    match app_state
+        .file_service
+        .get_upload_session(id.into_inner(), caller.user_id)
+        .await
+    {
+        Ok(session) => HttpResponse::Ok().json(session),
+        Err(e) => {
+            tracing::error!("Failed to fetch upload session: {}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
+#[put("/files/{id}/upload")]
+pub async fn upload_chunk(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    id: Path<Uuid>,
+    query: Query<UploadChunkQuery>,
+    mut payload: Payload,
+) -> impl Responder {
+    let (tx, rx) = mpsc::channel(32);
+
+    let app_state_clone = app_state.clone();
+    let file_id = id.into_inner();
+    let offset = query.offset;
+
+    let service_handle = tokio::spawn(async move {
+        app_state_clone
+            .file_service
+            .upload_chunk(file_id, caller.user_id, offset, rx)
+            .await
+    });
+
+    while let Some(chunk) = payload.next().await {
+        let item = chunk
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| DataError::UploadInterrupter(e.to_string()));
+        let interrupted = item.is_err();
+
+        if tx.send(item).await.is_err() || interrupted {
+            break;
+        }
+    }
+
+    drop(tx);
+
+    match service_handle.await {
+        Ok(Ok(session)) => HttpResponse::Ok().json(session),
+        Ok(Err(e)) => {
+            tracing::error!("Failed to upload a chunk: {}", e);
+            map_data_err_to_http(e)
+        }
+        Err(_) => HttpResponse::InternalServerError().body("Upload task panicked"),
+    }
+}
+
 #[patch("/files/{fileId}")]
 pub async fn rename_file(
     app_state: Data<AppState>,
+    caller: CallerIdentity,
     file_id: Path<Uuid>,
     req: Json<UpdateFileNameCommand>,
 ) -> impl Responder {
@@ -81,7 +222,7 @@ pub async fn rename_file(
 
     match app_state
         .file_service
-        .update_file_name(command, file_id.into_inner())
+        .update_file_name(command, file_id.into_inner(), caller.user_id)
         .await
     {
         Ok(file) => HttpResponse::Ok().json(file),
@@ -93,10 +234,14 @@ pub async fn rename_file(
 }
 
 #[patch("/files/{id}/undelete")]
-pub async fn undelete_file(app_state: Data<AppState>, id: Path<Uuid>) -> impl Responder {
+pub async fn undelete_file(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    id: Path<Uuid>,
+) -> impl Responder {
     match app_state
         .file_service
-        .update_deleted_file(id.into_inner())
+        .update_deleted_file(id.into_inner(), caller.user_id)
         .await
     {
         Ok(f) => HttpResponse::Ok().json(f),
@@ -107,16 +252,38 @@ pub async fn undelete_file(app_state: Data<AppState>, id: Path<Uuid>) -> impl Re
     }
 }
 
+#[post("/files/download")]
+pub async fn download_chosen_files(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    req: Json<DownloadBundleCommand>,
+) -> impl Responder {
+    let command: DownloadBundleCommand = req.into_inner();
+
+    match app_state
+        .bundle_service
+        .bundle_files(&command.files_ids, caller.user_id, command.format)
+        .await
+    {
+        Ok((name, reader)) => bundle_response(&name, command.format, reader),
+        Err(e) => {
+            tracing::error!("Failed to bundle chosen files: {}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
 #[delete("/files/all")]
 pub async fn delete_chosen_files(
     app_state: Data<AppState>,
+    caller: CallerIdentity,
     req: Json<DeleteChosenFilesCommand>,
 ) -> impl Responder {
     let command: DeleteChosenFilesCommand = req.into_inner();
 
     match app_state
         .file_service
-        .delete_chosen_files(&command.files_ids)
+        .delete_chosen_files(&command.files_ids, caller.user_id)
         .await
     {
         Ok(_) => HttpResponse::NoContent().finish(),
@@ -128,8 +295,16 @@ pub async fn delete_chosen_files(
 }
 
 #[delete("/files/{id}")]
-pub async fn delete_file(app_state: Data<AppState>, file_id: Path<Uuid>) -> impl Responder {
-    match app_state.file_service.delete(file_id.into_inner()).await {
+pub async fn delete_file(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    file_id: Path<Uuid>,
+) -> impl Responder {
+    match app_state
+        .file_service
+        .delete(file_id.into_inner(), caller.user_id)
+        .await
+    {
         Ok(_) => HttpResponse::NoContent().finish(),
         Err(e) => {
             tracing::error!("Failed to delete a file: {:?}", e);
@@ -139,9 +314,14 @@ pub async fn delete_file(app_state: Data<AppState>, file_id: Path<Uuid>) -> impl
 }
 
 pub fn config(c: &mut ServiceConfig) {
+    c.service(download_file);
+    c.service(get_preview);
+    c.service(download_chosen_files);
     c.service(get_file);
     c.service(delete_file);
     c.service(init_file);
+    c.service(get_upload_session);
+    c.service(upload_chunk);
     c.service(rename_file);
     c.service(search_file);
 }
@@ -1,16 +1,19 @@
 use crate::data::delete_chosen_folders_command::DeleteChosenFoldersCommand;
+use crate::data::download_bundle_query::DownloadBundleQuery;
 use crate::data::filter_files_by_filetype_command::FilterFilesByFileTypeCommand;
 use crate::data::search_query::SearchQuery;
 use crate::data::update_folder_name_command::UpdateFolderNameCommand;
+use crate::helpers::download::bundle_response;
 use crate::helpers::error_mapping::map_data_err_to_http;
 use crate::AppState;
 use actix_web::web::{Data, Json, Path, Query};
 use actix_web::{delete, get, patch, web, HttpResponse, Responder};
+use homelab_core::auth::caller::CallerIdentity;
 use uuid::Uuid;
 
-#[get("/folders/{userId}/root")]
-pub async fn get_root_folder(app_state: Data<AppState>, id: Path<Uuid>) -> impl Responder {
-    let user_id = id.into_inner();
+#[get("/folders/root")]
+pub async fn get_root_folder(app_state: Data<AppState>, caller: CallerIdentity) -> impl Responder {
+    let user_id = caller.user_id;
 
     match app_state.folder_service.get_root(user_id).await {
         Ok(Some(folder)) => HttpResponse::Ok().json(folder),
@@ -25,11 +28,37 @@ pub async fn get_root_folder(app_state: Data<AppState>, id: Path<Uuid>) -> impl
     }
 }
 
+#[get("/folders/shared")]
+pub async fn get_shared_root_folder(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+) -> impl Responder {
+    match app_state
+        .folder_service
+        .get_shared_root(caller.user_id)
+        .await
+    {
+        Ok(folder) => HttpResponse::Ok().json(folder),
+        Err(e) => {
+            tracing::error!("Failed to fetch shared root folder: {:?}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
 #[get("/folders/{folderId}")]
-pub async fn get_folder_by_id(app_state: Data<AppState>, id: Path<Uuid>) -> impl Responder {
+pub async fn get_folder_by_id(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    id: Path<Uuid>,
+) -> impl Responder {
     let folder_id = id.into_inner();
 
-    match app_state.folder_service.get_by_id(folder_id).await {
+    match app_state
+        .folder_service
+        .get_by_id(folder_id, caller.user_id)
+        .await
+    {
         Ok(Some(folder)) => HttpResponse::Ok().json(folder),
         Ok(None) => HttpResponse::NotFound().body(format!(
             "Could not find record of folder with an id of {}",
@@ -45,11 +74,12 @@ pub async fn get_folder_by_id(app_state: Data<AppState>, id: Path<Uuid>) -> impl
 #[get("/folders/{folderId}/subfolders")]
 pub async fn get_all_subfolders(
     app_state: Data<AppState>,
+    caller: CallerIdentity,
     folder_id: Path<Uuid>,
 ) -> impl Responder {
     match app_state
         .folder_service
-        .get_children_by_id(folder_id.into_inner())
+        .get_children_by_id(folder_id.into_inner(), caller.user_id)
         .await
     {
         Ok(folders) => {
@@ -67,10 +97,14 @@ pub async fn get_all_subfolders(
 }
 
 #[delete("/folders/{id}")]
-pub async fn delete_folder(app_state: Data<AppState>, folder_id: Path<Uuid>) -> impl Responder {
+pub async fn delete_folder(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    folder_id: Path<Uuid>,
+) -> impl Responder {
     match app_state
         .folder_service
-        .delete(folder_id.into_inner())
+        .delete(folder_id.into_inner(), caller.user_id)
         .await
     {
         Ok(_) => HttpResponse::NoContent().finish(),
@@ -82,10 +116,18 @@ pub async fn delete_folder(app_state: Data<AppState>, folder_id: Path<Uuid>) ->
 }
 
 #[get("folders/{folderId}/file")]
-pub async fn fetch_files_for_folder(app_state: Data<AppState>, id: Path<Uuid>) -> impl Responder {
+pub async fn fetch_files_for_folder(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    id: Path<Uuid>,
+) -> impl Responder {
     let folder_id = id.into_inner();
 
-    match app_state.folder_service.get_by_folder(folder_id).await {
+    match app_state
+        .folder_service
+        .get_by_folder(folder_id, caller.user_id)
+        .await
+    {
         Ok(files) => {
             if files.is_empty() {
                 HttpResponse::NotFound().body(format!(
@@ -106,6 +148,7 @@ pub async fn fetch_files_for_folder(app_state: Data<AppState>, id: Path<Uuid>) -
 #[patch("/folders/{id}/name")]
 pub async fn rename_folder(
     app_state: Data<AppState>,
+    caller: CallerIdentity,
     folder_id: Path<Uuid>,
     req: Json<UpdateFolderNameCommand>,
 ) -> impl Responder {
@@ -113,7 +156,7 @@ pub async fn rename_folder(
 
     match app_state
         .folder_service
-        .update_folder_name(command, folder_id.into_inner())
+        .update_folder_name(command, folder_id.into_inner(), caller.user_id)
         .await
     {
         Ok(f) => HttpResponse::Ok().json(f),
@@ -125,10 +168,18 @@ pub async fn rename_folder(
 }
 
 #[get("/folders/search")]
-pub async fn search_folder(app_state: Data<AppState>, query: Query<SearchQuery>) -> impl Responder {
+pub async fn search_folder(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    query: Query<SearchQuery>,
+) -> impl Responder {
     let search_term = query.into_inner().q;
 
-    match app_state.folder_service.search_folder(search_term).await {
+    match app_state
+        .folder_service
+        .search_folder(search_term, caller.user_id)
+        .await
+    {
         Ok(f) => {
             if f.is_empty() {
                 HttpResponse::Ok().body("No folders for the given search query")
@@ -146,13 +197,14 @@ pub async fn search_folder(app_state: Data<AppState>, query: Query<SearchQuery>)
 #[delete("/folders/all")]
 pub async fn delete_chosen_folders(
     app_state: Data<AppState>,
+    caller: CallerIdentity,
     req: Json<DeleteChosenFoldersCommand>,
 ) -> impl Responder {
     let command: DeleteChosenFoldersCommand = req.into_inner();
 
     match app_state
         .folder_service
-        .delete_chosen_folders(&command.folder_ids)
+        .delete_chosen_folders(&command.folder_ids, caller.user_id)
         .await
     {
         Ok(_) => HttpResponse::NoContent().finish(),
@@ -166,6 +218,7 @@ pub async fn delete_chosen_folders(
 #[get("/folders/{id}/files/filter")]
 pub async fn filter_files_in_folder(
     app_state: Data<AppState>,
+    caller: CallerIdentity,
     req: Json<FilterFilesByFileTypeCommand>,
     folder_id: Path<Uuid>,
 ) -> impl Responder {
@@ -173,7 +226,7 @@ pub async fn filter_files_in_folder(
 
     match app_state
         .folder_service
-        .filter_files_by_folder(&command.file_types, folder_id.into_inner())
+        .filter_files_by_folder(&command.file_types, folder_id.into_inner(), caller.user_id)
         .await
     {
         Ok(f) => {
@@ -191,9 +244,33 @@ pub async fn filter_files_in_folder(
     }
 }
 
+#[get("/folders/{folderId}/download")]
+pub async fn download_folder(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    id: Path<Uuid>,
+    query: Query<DownloadBundleQuery>,
+) -> impl Responder {
+    let format = query.into_inner().format;
+
+    match app_state
+        .bundle_service
+        .bundle_folder(id.into_inner(), caller.user_id, format)
+        .await
+    {
+        Ok((name, reader)) => bundle_response(&name, format, reader),
+        Err(e) => {
+            tracing::error!("Failed to bundle folder: {:?}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(get_root_folder);
+    cfg.service(get_shared_root_folder);
     cfg.service(get_folder_by_id);
+    cfg.service(download_folder);
     cfg.service(get_all_subfolders);
     cfg.service(delete_folder);
     cfg.service(fetch_files_for_folder);
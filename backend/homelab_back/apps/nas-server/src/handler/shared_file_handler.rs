@@ -1,13 +1,24 @@
+use crate::data::create_share_link_command::CreateShareLinkCommand;
 use crate::data::create_shared_file_command::CreateSharedFileCommand;
+use crate::data::create_shared_folder_command::CreateSharedFolderCommand;
+use crate::data::share_link_query::ShareLinkQuery;
+use crate::helpers::data_error::DataError;
+use crate::helpers::download::download_response;
 use crate::helpers::error_mapping::map_data_err_to_http;
+use crate::storage::storage_backend::ObjectReader;
 use crate::AppState;
-use actix_web::web::{Data, Json, Path, ServiceConfig};
-use actix_web::{get, post, HttpResponse, Responder};
+use actix_web::web::{Data, Json, Path, Query, ServiceConfig};
+use actix_web::{delete, get, post, HttpRequest, HttpResponse, Responder};
+use homelab_core::auth::caller::CallerIdentity;
+use homelab_core::file::File;
 use uuid::Uuid;
 
+const SHARE_PASSWORD_HEADER: &str = "X-Share-Password";
+
 #[post("/shared")]
 pub async fn create_shared_file_record(
     app_state: Data<AppState>,
+    caller: CallerIdentity,
     req: Json<CreateSharedFileCommand>,
 ) -> impl Responder {
     let command = req.into_inner();
@@ -15,7 +26,7 @@ pub async fn create_shared_file_record(
 
     match app_state
         .shared_file_service
-        .create_shared_file(command)
+        .create_shared_file(command, caller.user_id)
         .await
     {
         Ok(sh) => HttpResponse::Created().json(sh),
@@ -27,10 +38,10 @@ pub async fn create_shared_file_record(
 }
 
 #[get("/shared")]
-pub async fn get_all(app_state: Data<AppState>, user_id: Path<Uuid>) -> impl Responder {
+pub async fn get_all(app_state: Data<AppState>, caller: CallerIdentity) -> impl Responder {
     match app_state
         .shared_file_service
-        .get_all_shared_files_per_user(user_id.into_inner())
+        .get_all_shared_files_per_user(caller.user_id)
         .await
     {
         Ok(sf) => {
@@ -47,7 +58,182 @@ pub async fn get_all(app_state: Data<AppState>, user_id: Path<Uuid>) -> impl Res
     }
 }
 
+#[post("/shared/folders")]
+pub async fn create_shared_folder_record(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    req: Json<CreateSharedFolderCommand>,
+) -> impl Responder {
+    match app_state
+        .shared_file_service
+        .create_shared_folder(req.into_inner(), caller.user_id)
+        .await
+    {
+        Ok(sf) => HttpResponse::Created().json(sf),
+        Err(e) => {
+            tracing::error!("Failed to share folder: {}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
+#[get("/shared/folders")]
+pub async fn get_all_shared_folders(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+) -> impl Responder {
+    match app_state
+        .shared_file_service
+        .get_all_shared_folders_per_user(caller.user_id)
+        .await
+    {
+        Ok(sf) => HttpResponse::Ok().json(sf),
+        Err(e) => {
+            tracing::error!("Failed while retrieving shared folders: {}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
+#[post("/shared/links")]
+pub async fn create_share_link(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    req: Json<CreateShareLinkCommand>,
+) -> impl Responder {
+    match app_state
+        .share_link_service
+        .create_share_link(req.into_inner(), caller.user_id)
+        .await
+    {
+        Ok(link) => HttpResponse::Created().json(link),
+        Err(e) => {
+            tracing::error!("Failed to create a share link: {}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
+#[get("/shared/links")]
+pub async fn get_share_links(app_state: Data<AppState>, caller: CallerIdentity) -> impl Responder {
+    match app_state
+        .share_link_service
+        .get_share_links(caller.user_id)
+        .await
+    {
+        Ok(links) => HttpResponse::Ok().json(links),
+        Err(e) => {
+            tracing::error!("Failed to fetch share links: {}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
+#[delete("/shared/links/{id}")]
+pub async fn delete_share_link(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    id: Path<Uuid>,
+) -> impl Responder {
+    match app_state
+        .share_link_service
+        .delete_share_link(id.into_inner(), caller.user_id)
+        .await
+    {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            tracing::error!("Failed to delete a share link: {}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
+#[get("/share/{token}")]
+pub async fn get_share_link_content(
+    app_state: Data<AppState>,
+    http_req: HttpRequest,
+    token: Path<String>,
+    query: Query<ShareLinkQuery>,
+) -> impl Responder {
+    let password = share_password(&http_req);
+
+    match app_state
+        .share_link_service
+        .get_content(&token, password.as_deref(), query.into_inner().folder_id)
+        .await
+    {
+        Ok(content) => HttpResponse::Ok().json(content),
+        Err(e) => {
+            tracing::error!("Failed to open a share link: {}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
+#[get("/share/{token}/download")]
+pub async fn download_shared_file(
+    app_state: Data<AppState>,
+    http_req: HttpRequest,
+    token: Path<String>,
+) -> HttpResponse {
+    let password = share_password(&http_req);
+
+    let result = app_state
+        .share_link_service
+        .get_file_for_download(&token, password.as_deref(), None)
+        .await;
+
+    stream_shared_file(result)
+}
+
+#[get("/share/{token}/files/{fileId}/download")]
+pub async fn download_shared_folder_file(
+    app_state: Data<AppState>,
+    http_req: HttpRequest,
+    path: Path<(String, Uuid)>,
+) -> HttpResponse {
+    let (token, file_id) = path.into_inner();
+    let password = share_password(&http_req);
+
+    let result = app_state
+        .share_link_service
+        .get_file_for_download(&token, password.as_deref(), Some(file_id))
+        .await;
+
+    stream_shared_file(result)
+}
+
+fn share_password(http_req: &HttpRequest) -> Option<String> {
+    http_req
+        .headers()
+        .get(SHARE_PASSWORD_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+fn stream_shared_file(result: Result<(File, ObjectReader), DataError>) -> HttpResponse {
+    match result {
+        Ok((file, reader)) => download_response(&file, reader),
+        Err(e) => {
+            tracing::error!("Failed to download a shared file: {}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
 pub fn config(c: &mut ServiceConfig) {
     c.service(create_shared_file_record);
     c.service(get_all);
+    c.service(create_shared_folder_record);
+    c.service(get_all_shared_folders);
+    c.service(create_share_link);
+    c.service(get_share_links);
+    c.service(delete_share_link);
+}
+
+// Routes that are reachable without a token, access is controlled by the share link itself
+pub fn public_config(c: &mut ServiceConfig) {
+    c.service(get_share_link_content);
+    c.service(download_shared_file);
+    c.service(download_shared_folder_file);
 }
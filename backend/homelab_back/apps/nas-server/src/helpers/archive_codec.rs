@@ -0,0 +1,29 @@
+use crate::storage::storage_backend::ObjectReader;
+use async_compression::tokio::bufread::{GzipDecoder, XzDecoder, ZstdDecoder};
+use async_compression::tokio::write::{GzipEncoder, XzEncoder, ZstdEncoder};
+use homelab_core::file::ArchiveCodec;
+use tokio::io::{AsyncWrite, BufReader};
+
+pub type ContentWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+// Compresses everything written to it, the stream is only complete once it is shut down
+pub fn encoder<W>(codec: ArchiveCodec, writer: W) -> ContentWriter
+where
+    W: AsyncWrite + Send + Unpin + 'static,
+{
+    match codec {
+        ArchiveCodec::Gzip => Box::new(GzipEncoder::new(writer)),
+        ArchiveCodec::Zstd => Box::new(ZstdEncoder::new(writer)),
+        ArchiveCodec::Xz => Box::new(XzEncoder::new(writer)),
+    }
+}
+
+pub fn decoder(codec: ArchiveCodec, reader: ObjectReader) -> ObjectReader {
+    let reader = BufReader::new(reader);
+
+    match codec {
+        ArchiveCodec::Gzip => Box::new(GzipDecoder::new(reader)),
+        ArchiveCodec::Zstd => Box::new(ZstdDecoder::new(reader)),
+        ArchiveCodec::Xz => Box::new(XzDecoder::new(reader)),
+    }
+}
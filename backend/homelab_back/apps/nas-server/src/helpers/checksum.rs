@@ -0,0 +1,53 @@
+use crate::helpers::data_error::DataError;
+use homelab_core::constants::MB;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tokio::fs;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const SHA256_HEX_LEN: usize = 64;
+
+pub fn encode_digest(hasher: Sha256) -> String {
+    hex::encode(hasher.finalize())
+}
+
+/// Normalises a client supplied SHA-256 so it can be compared with the digests we compute
+pub fn parse_checksum(checksum: &str) -> Result<String, DataError> {
+    let checksum = checksum.trim().to_ascii_lowercase();
+
+    if checksum.len() != SHA256_HEX_LEN || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(DataError::ValidationError(
+            "Checksum must be a hex encoded SHA-256 digest".to_string(),
+        ));
+    }
+
+    Ok(checksum)
+}
+
+pub async fn checksum_file(path: &Path) -> Result<String, DataError> {
+    let file = fs::File::open(path)
+        .await
+        .map_err(|e| DataError::IOError(e.to_string()))?;
+
+    checksum_reader(file).await
+}
+
+pub async fn checksum_reader<R: AsyncRead + Unpin>(mut reader: R) -> Result<String, DataError> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; MB as usize];
+
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        if read == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(encode_digest(hasher))
+}
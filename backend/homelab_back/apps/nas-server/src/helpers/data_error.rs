@@ -28,4 +28,16 @@ pub enum DataError {
     FileIsAlreadyArchivedError,
     #[error("This file is not archived, so you cannot not unarchive it")]
     FileIsNotArchivedError,
+    #[error("You do not have permission to access {0}")]
+    PermissionDeniedError(String),
+    #[error("This share link has expired or reached its download limit")]
+    ShareLinkUnavailableError,
+    #[error("A valid password is required to open this share link")]
+    InvalidSharePasswordError,
+    #[error("Upload offset {0} does not match the committed offset {1}")]
+    UploadOffsetMismatchError(i64, i64),
+    #[error("Uploaded content does not match the declared checksum")]
+    ChecksumMismatchError,
+    #[error("The file was changed by someone else in the meantime")]
+    ContentConflictError,
 }
@@ -0,0 +1,411 @@
+use crate::data::bundle_format::BundleFormat;
+use crate::data::preview::Preview;
+use crate::storage::storage_backend::ObjectReader;
+use actix_web::http::header::{
+    CacheControl, CacheDirective, ContentDisposition, ContentRange, ContentRangeSpec,
+    DispositionParam, DispositionType, ETag, EntityTag, HttpDate, IfNoneMatch, IfRange,
+    LastModified, Range, ACCEPT_RANGES,
+};
+use actix_web::http::StatusCode;
+use actix_web::web::Bytes;
+use actix_web::{mime, HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder};
+use futures::stream;
+use homelab_core::file::{File, FileType};
+use std::ops::Range as ByteRange;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tokio::io::AsyncReadExt;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+// Previews live at the file's address but follow its content, so clients keep them briefly and revalidate
+const PREVIEW_MAX_AGE: u32 = 60 * 60;
+
+// What a download request asks for once its conditional headers are taken into account
+pub enum DownloadPlan {
+    NotModified,
+    Full,
+    Partial(ByteRange<u64>),
+    Unsatisfiable,
+}
+
+pub fn plan_download(file: &File, req: &HttpRequest) -> DownloadPlan {
+    let etag = entity_tag(file);
+
+    if let Some(if_none_match) = req.get_header::<IfNoneMatch>() {
+        let matches = match if_none_match {
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Items(tags) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        };
+
+        if matches {
+            return DownloadPlan::NotModified;
+        }
+    }
+
+    // Archived content is unpacked while it is sent, so its length is unknown up front
+    if file.is_archived() {
+        return DownloadPlan::Full;
+    }
+
+    let specs = match req.get_header::<Range>() {
+        Some(Range::Bytes(specs)) => specs,
+        _ => return DownloadPlan::Full,
+    };
+
+    // A range of an older copy would be stitched onto the wrong content, so the whole file is sent then
+    let is_current = match req.get_header::<IfRange>() {
+        Some(IfRange::EntityTag(tag)) => tag.strong_eq(&etag),
+        Some(IfRange::Date(date)) => !etag.weak && date == last_modified(file),
+        None => true,
+    };
+
+    // Multiple ranges would need a multipart body, sending everything is allowed instead
+    if !is_current || specs.len() != 1 {
+        return DownloadPlan::Full;
+    }
+
+    match specs[0].to_satisfiable_range(file.size as u64) {
+        Some((first, last)) => DownloadPlan::Partial(first..last + 1),
+        None => DownloadPlan::Unsatisfiable,
+    }
+}
+
+// Stored content is named after its blob, so the type and the download name come from the file itself
+pub fn download_response(file: &File, reader: ObjectReader) -> HttpResponse {
+    // Archived content is handed out unpacked, as it was before archiving
+    if file.is_archived() {
+        return content_headers(HttpResponse::Ok(), file)
+            .insert_header((ACCEPT_RANGES, "none"))
+            .streaming(read_chunks(reader));
+    }
+
+    content_headers(HttpResponse::Ok(), file)
+        .insert_header((ACCEPT_RANGES, "bytes"))
+        .no_chunking(file.size as u64)
+        .streaming(read_chunks(reader))
+}
+
+pub fn partial_download_response(
+    file: &File,
+    reader: ObjectReader,
+    range: ByteRange<u64>,
+) -> HttpResponse {
+    content_headers(HttpResponse::PartialContent(), file)
+        .insert_header((ACCEPT_RANGES, "bytes"))
+        .insert_header(ContentRange(ContentRangeSpec::Bytes {
+            range: Some((range.start, range.end - 1)),
+            instance_length: Some(file.size as u64),
+        }))
+        .no_chunking(range.end - range.start)
+        .streaming(read_chunks(reader))
+}
+
+// Bundles are built while they are sent, so their length is never known up front
+pub fn bundle_response(name: &str, format: BundleFormat, reader: ObjectReader) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(name.to_string())],
+        })
+        .insert_header((ACCEPT_RANGES, "none"))
+        .streaming(read_chunks(reader))
+}
+
+pub fn not_modified_response(file: &File) -> HttpResponse {
+    HttpResponse::NotModified()
+        .insert_header(ETag(entity_tag(file)))
+        .insert_header(LastModified(last_modified(file)))
+        .finish()
+}
+
+pub fn preview_response(preview: Preview, req: &HttpRequest) -> HttpResponse {
+    let etag = EntityTag::new_strong(preview.etag);
+
+    let cache_control = CacheControl(vec![
+        CacheDirective::Private,
+        CacheDirective::MaxAge(PREVIEW_MAX_AGE),
+    ]);
+
+    let matches = match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+
+    if matches {
+        return HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .insert_header(cache_control)
+            .finish();
+    }
+
+    HttpResponse::Ok()
+        .content_type(preview.content_type)
+        .insert_header(ETag(etag))
+        .insert_header(cache_control)
+        .body(preview.content)
+}
+
+pub fn range_not_satisfiable_response(file: &File) -> HttpResponse {
+    HttpResponse::build(StatusCode::RANGE_NOT_SATISFIABLE)
+        .insert_header(ContentRange(ContentRangeSpec::Bytes {
+            range: None,
+            instance_length: Some(file.size as u64),
+        }))
+        .finish()
+}
+
+// The checksum identifies the content exactly, files without one fall back to a weak tag of their last change
+pub fn entity_tag(file: &File) -> EntityTag {
+    match &file.checksum {
+        Some(checksum) => EntityTag::new_strong(checksum.to_lowercase()),
+        None => EntityTag::new_weak(format!(
+            "{}-{}",
+            file.updated_at.unix_timestamp_nanos(),
+            file.size
+        )),
+    }
+}
+
+// HTTP dates have whole seconds only, so the sub-second part is dropped to let If-Range dates match
+fn last_modified(file: &File) -> HttpDate {
+    let seconds = file.updated_at.unix_timestamp().max(0) as u64;
+
+    HttpDate::from(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+fn content_headers(mut builder: HttpResponseBuilder, file: &File) -> HttpResponseBuilder {
+    let content_type = content_type(&file.file_type, &file.name);
+
+    let disposition = match content_type.type_() {
+        mime::IMAGE | mime::TEXT | mime::AUDIO | mime::VIDEO => DispositionType::Inline,
+        _ if content_type == mime::APPLICATION_PDF => DispositionType::Inline,
+        _ => DispositionType::Attachment,
+    };
+
+    builder
+        .content_type(content_type)
+        .insert_header(ContentDisposition {
+            disposition,
+            parameters: vec![DispositionParam::Filename(file.name.clone())],
+        })
+        .insert_header(ETag(entity_tag(file)))
+        .insert_header(LastModified(last_modified(file)));
+
+    builder
+}
+
+// The file type decides what kind of content is sent, the extension only narrows it down
+fn content_type(file_type: &FileType, name: &str) -> mime::Mime {
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+
+    let guessed = actix_files::file_extension_to_mime(extension);
+
+    match file_type {
+        FileType::Pdf => mime::APPLICATION_PDF,
+        // Markup is never rendered, it would run with the caller's session
+        FileType::Text if guessed.type_() == mime::TEXT && guessed.subtype() != mime::HTML => {
+            guessed
+        }
+        FileType::Text => mime::TEXT_PLAIN_UTF_8,
+        FileType::Image if guessed.type_() == mime::IMAGE && guessed.subtype() != mime::SVG => {
+            guessed
+        }
+        FileType::Video if guessed.type_() == mime::VIDEO => guessed,
+        FileType::Audio if guessed.type_() == mime::AUDIO => guessed,
+        FileType::Zip => guessed,
+        _ => mime::APPLICATION_OCTET_STREAM,
+    }
+}
+
+fn read_chunks(reader: ObjectReader) -> impl futures::Stream<Item = Result<Bytes, std::io::Error>> {
+    stream::unfold(reader, |mut reader| async move {
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+
+        match reader.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(read) => {
+                buffer.truncate(read);
+                Some((Ok::<_, std::io::Error>(Bytes::from(buffer)), reader))
+            }
+            Err(e) => Some((Err(e), reader)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use homelab_core::file::ArchiveCodec;
+    use sqlx::types::time::OffsetDateTime;
+    use uuid::Uuid;
+
+    const CHECKSUM: &str = "ABCDEF0123";
+
+    fn file(checksum: Option<&str>) -> File {
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let mut file = File::new(
+            Uuid::new_v4(),
+            "movie.mp4".to_string(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            false,
+            1000,
+            now,
+            now,
+        );
+
+        file.checksum = checksum.map(|c| c.to_string());
+        file
+    }
+
+    fn plan(file: &File, headers: &[(&str, &str)]) -> DownloadPlan {
+        let req = headers
+            .iter()
+            .fold(TestRequest::default(), |req, header| {
+                req.insert_header(*header)
+            })
+            .to_http_request();
+
+        plan_download(file, &req)
+    }
+
+    fn partial(plan: DownloadPlan) -> ByteRange<u64> {
+        match plan {
+            DownloadPlan::Partial(range) => range,
+            _ => panic!("expected a partial download"),
+        }
+    }
+
+    #[test]
+    fn without_range_the_whole_file_is_sent() {
+        assert!(matches!(plan(&file(None), &[]), DownloadPlan::Full));
+    }
+
+    #[test]
+    fn single_range_is_served_with_an_exclusive_end() {
+        let range = partial(plan(&file(None), &[("Range", "bytes=0-99")]));
+
+        assert_eq!(range, 0..100);
+    }
+
+    #[test]
+    fn open_and_suffix_ranges_end_at_the_last_byte() {
+        assert_eq!(
+            partial(plan(&file(None), &[("Range", "bytes=900-")])),
+            900..1000
+        );
+        assert_eq!(
+            partial(plan(&file(None), &[("Range", "bytes=-100")])),
+            900..1000
+        );
+        assert_eq!(
+            partial(plan(&file(None), &[("Range", "bytes=-5000")])),
+            0..1000
+        );
+    }
+
+    #[test]
+    fn range_past_the_end_is_cut_to_the_file() {
+        let range = partial(plan(&file(None), &[("Range", "bytes=900-5000")]));
+
+        assert_eq!(range, 900..1000);
+    }
+
+    #[test]
+    fn range_starting_past_the_end_is_unsatisfiable() {
+        let plan = plan(&file(None), &[("Range", "bytes=1000-1100")]);
+
+        assert!(matches!(plan, DownloadPlan::Unsatisfiable));
+    }
+
+    #[test]
+    fn multiple_ranges_fall_back_to_the_whole_file() {
+        let plan = plan(&file(None), &[("Range", "bytes=0-9,20-29")]);
+
+        assert!(matches!(plan, DownloadPlan::Full));
+    }
+
+    #[test]
+    fn malformed_range_is_ignored() {
+        let plan = plan(&file(None), &[("Range", "lines=0-9")]);
+
+        assert!(matches!(plan, DownloadPlan::Full));
+    }
+
+    #[test]
+    fn matching_if_none_match_is_not_modified() {
+        let file = file(Some(CHECKSUM));
+
+        assert!(matches!(
+            plan(&file, &[("If-None-Match", "\"abcdef0123\"")]),
+            DownloadPlan::NotModified
+        ));
+        assert!(matches!(
+            plan(&file, &[("If-None-Match", "W/\"abcdef0123\"")]),
+            DownloadPlan::NotModified
+        ));
+        assert!(matches!(
+            plan(&file, &[("If-None-Match", "*")]),
+            DownloadPlan::NotModified
+        ));
+        assert!(matches!(
+            plan(&file, &[("If-None-Match", "\"other\"")]),
+            DownloadPlan::Full
+        ));
+    }
+
+    #[test]
+    fn if_range_needs_the_current_strong_tag() {
+        let file = file(Some(CHECKSUM));
+
+        let current = plan(
+            &file,
+            &[("Range", "bytes=0-9"), ("If-Range", "\"abcdef0123\"")],
+        );
+        let stale = plan(&file, &[("Range", "bytes=0-9"), ("If-Range", "\"other\"")]);
+
+        assert_eq!(partial(current), 0..10);
+        assert!(matches!(stale, DownloadPlan::Full));
+    }
+
+    #[test]
+    fn if_range_date_is_only_trusted_with_a_strong_tag() {
+        let date = "Tue, 14 Nov 2023 22:13:20 GMT";
+
+        let strong = plan(
+            &file(Some(CHECKSUM)),
+            &[("Range", "bytes=0-9"), ("If-Range", date)],
+        );
+        let weak = plan(&file(None), &[("Range", "bytes=0-9"), ("If-Range", date)]);
+
+        assert_eq!(partial(strong), 0..10);
+        assert!(matches!(weak, DownloadPlan::Full));
+    }
+
+    #[test]
+    fn archived_content_is_sent_whole_under_its_content_tag() {
+        let mut file = file(Some(CHECKSUM));
+        file.set_as_archived(ArchiveCodec::Zstd, 400);
+
+        let plan = plan(&file, &[("Range", "bytes=0-9")]);
+
+        assert!(matches!(plan, DownloadPlan::Full));
+        assert_eq!(entity_tag(&file).tag(), "abcdef0123");
+    }
+
+    #[test]
+    fn entity_tag_is_strong_only_with_a_checksum() {
+        let strong = entity_tag(&file(Some(CHECKSUM)));
+        let weak = entity_tag(&file(None));
+
+        assert!(!strong.weak);
+        assert_eq!(strong.tag(), "abcdef0123");
+        assert!(weak.weak);
+    }
+}
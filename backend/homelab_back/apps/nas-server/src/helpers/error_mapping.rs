@@ -6,6 +6,12 @@ pub fn map_data_err_to_http(e: DataError) -> HttpResponse {
         DataError::EntityNotFoundException(msg) => HttpResponse::NotFound().body(msg),
         DataError::ValidationError(msg) => HttpResponse::BadRequest().body(msg),
         DataError::EntityCreationError(msg) => HttpResponse::BadRequest().body(msg),
+        DataError::PermissionDeniedError(_) => HttpResponse::Forbidden().body(e.to_string()),
+        DataError::ShareLinkUnavailableError => HttpResponse::Gone().body(e.to_string()),
+        DataError::InvalidSharePasswordError => HttpResponse::Unauthorized().body(e.to_string()),
+        DataError::UploadOffsetMismatchError(_, _) => HttpResponse::Conflict().body(e.to_string()),
+        DataError::ChecksumMismatchError => HttpResponse::UnprocessableEntity().body(e.to_string()),
+        DataError::ContentConflictError => HttpResponse::Conflict().body(e.to_string()),
         DataError::DatabaseError(err) => HttpResponse::InternalServerError().body(err.to_string()),
         _ => {
             tracing::error!("Internal server error: {:?}", e);
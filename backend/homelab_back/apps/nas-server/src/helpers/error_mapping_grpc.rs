@@ -25,6 +25,14 @@ impl From<DataError> for Status {
             DataError::FileIsNotArchivedError => {
                 Status::invalid_argument("You cannot unarchive file that is not an archive")
             }
+            DataError::PermissionDeniedError(_) => Status::permission_denied(e.to_string()),
+            DataError::ShareLinkUnavailableError => Status::failed_precondition(e.to_string()),
+            DataError::InvalidSharePasswordError => Status::unauthenticated(e.to_string()),
+            DataError::UploadOffsetMismatchError(_, _) => {
+                Status::failed_precondition(e.to_string())
+            }
+            DataError::ChecksumMismatchError => Status::data_loss(e.to_string()),
+            DataError::ContentConflictError => Status::aborted(e.to_string()),
             DataError::UnknownError(msg) => Status::internal(msg),
             _ => Status::internal("Internal server error"),
         }
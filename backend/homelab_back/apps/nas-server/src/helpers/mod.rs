@@ -1,4 +1,7 @@
+pub mod archive_codec;
+pub mod checksum;
 pub mod data_error;
+pub mod download;
 pub mod error_mapping;
 pub mod error_mapping_grpc;
 pub mod proto_mappers;
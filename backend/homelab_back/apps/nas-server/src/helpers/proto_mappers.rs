@@ -1,10 +1,19 @@
-use homelab_core::file::{File, FileType as DomainFileType, UploadStatus as DomainUploadStatus};
+use crate::data::archive_entry::ArchiveEntry;
+use crate::data::bundle_format::BundleFormat;
+use crate::data::extraction_summary::ExtractionSummary;
+use homelab_core::compression_policy::CompressionPolicy;
+use homelab_core::file::{ArchiveCodec, File, FileType as DomainFileType, PreviewStatus, UploadStatus as DomainUploadStatus};
 use homelab_core::file_label::FileLabel;
+use homelab_core::file_version::FileVersion;
 use homelab_core::folder::Folder;
 use homelab_core::global_file::GlobalFile;
 use homelab_core::label::Label;
+use homelab_core::shared_file::{SharedFile, SharedFileAccessType};
+use homelab_core::shared_folder::SharedFolder;
+use homelab_core::upload_session::UploadSession;
 use homelab_proto::common::EntityId;
-use homelab_proto::nas::{FileLabelResponse, FileResponse, FileType as ProtoFileType, FolderResponse, GlobalFileResponse, LabelResponse, StorageProfileResponse, UploadStatus as ProtoUploadStatus};
+use homelab_proto::nas::{ArchiveCodec as ProtoArchiveCodec, ArchiveEntryResponse, BundleFormat as ProtoBundleFormat, CompressionPolicyResponse, ExtractArchiveResponse, FileLabelResponse, FileResponse, FileType as ProtoFileType, FileVersionResponse, FolderResponse, GlobalFileResponse, LabelResponse, SharedFileAccessType as ProtoSharedFileAccessType, SharedFileResponse, SharedFolderResponse, StorageProfileResponse, UploadSessionResponse, UploadStatus as ProtoUploadStatus};
+use sqlx::types::time::OffsetDateTime;
 use tonic::Status;
 use uuid::Uuid;
 use homelab_core::storage_profile::StorageProfile;
@@ -15,24 +24,19 @@ pub fn map_storage_profile_to_proto(sp: StorageProfile) -> StorageProfileRespons
         allowed_storage: sp.allowed_storage,
         taken_storage: sp.taken_storage,
         is_blocked: sp.is_blocked,
+        reserved_storage: sp.reserved_storage,
     }
 }
 
 pub fn map_file_to_proto(f: File) -> FileResponse {
+    let logical_size = f.logical_size();
+
     FileResponse {
         id: Option::from(map_id_to_proto(f.id)),
         name: f.name,
         owner_id: Option::from(map_id_to_proto(f.owner_id)),
         parent_folder_id: Option::from(map_id_to_proto(f.parent_folder_id)),
-        file_type: match f.file_type {
-            DomainFileType::Image => ProtoFileType::Image,
-            DomainFileType::Text => ProtoFileType::Text,
-            DomainFileType::Video => ProtoFileType::Video,
-            DomainFileType::Audio => ProtoFileType::Audio,
-            DomainFileType::Pdf => ProtoFileType::Pdf,
-            DomainFileType::Zip => ProtoFileType::Zip,
-            DomainFileType::Unknown => ProtoFileType::Unknown,
-        } as i32,
+        file_type: map_file_type_to_proto(&f.file_type) as i32,
         is_deleted: f.is_deleted,
         ttl: f.ttl.map(|t| prost_types::Timestamp {
             seconds: t.unix_timestamp(),
@@ -51,7 +55,11 @@ pub fn map_file_to_proto(f: File) -> FileResponse {
         updated_at: Some(prost_types::Timestamp {
             seconds: f.updated_at.unix_timestamp(),
             nanos: f.updated_at.nanosecond() as i32,
-        })
+        }),
+        checksum: f.checksum,
+        archive_codec: f.archive_codec.map(|codec| map_archive_codec_to_proto(codec) as i32),
+        logical_size,
+        has_preview: f.preview_status == PreviewStatus::Ready,
     }
 }
 
@@ -91,12 +99,173 @@ pub fn map_file_label_to_proto(fl: FileLabel) -> FileLabelResponse {
     }
 }
 
+pub fn map_shared_file_to_proto(sf: SharedFile) -> SharedFileResponse {
+    SharedFileResponse {
+        id: Option::from(map_id_to_proto(sf.id)),
+        file_id: Option::from(map_id_to_proto(sf.file_id)),
+        user_id: Option::from(map_id_to_proto(sf.user_id)),
+        owner_id: Option::from(map_id_to_proto(sf.owner_id)),
+        access_type: map_access_type_to_proto(&sf.access_type),
+    }
+}
+
+pub fn map_shared_folder_to_proto(sf: SharedFolder) -> SharedFolderResponse {
+    SharedFolderResponse {
+        id: Option::from(map_id_to_proto(sf.id)),
+        folder_id: Option::from(map_id_to_proto(sf.folder_id)),
+        user_id: Option::from(map_id_to_proto(sf.user_id)),
+        owner_id: Option::from(map_id_to_proto(sf.owner_id)),
+        access_type: map_access_type_to_proto(&sf.access_type),
+    }
+}
+
+pub fn map_file_version_to_proto(v: FileVersion) -> FileVersionResponse {
+    FileVersionResponse {
+        id: Option::from(map_id_to_proto(v.id)),
+        file_id: Option::from(map_id_to_proto(v.file_id)),
+        size: v.size,
+        checksum: v.checksum,
+        created_at: Some(prost_types::Timestamp {
+            seconds: v.created_at.unix_timestamp(),
+            nanos: v.created_at.nanosecond() as i32,
+        }),
+    }
+}
+
+pub fn map_upload_session_to_proto(session: UploadSession) -> UploadSessionResponse {
+    UploadSessionResponse {
+        file_id: Option::from(map_id_to_proto(session.file_id)),
+        committed_offset: session.committed_offset,
+        size: session.total_size,
+        is_complete: session.is_complete(),
+    }
+}
+
+pub fn map_archive_entry_to_proto(e: ArchiveEntry) -> ArchiveEntryResponse {
+    ArchiveEntryResponse {
+        path: e.path,
+        size: e.size as i64,
+        modified_at: e.modified_at.map(|t| prost_types::Timestamp {
+            seconds: t.unix_timestamp(),
+            nanos: t.nanosecond() as i32,
+        }),
+        is_dir: e.is_dir,
+    }
+}
+
+pub fn map_extraction_summary_to_proto(s: ExtractionSummary) -> ExtractArchiveResponse {
+    ExtractArchiveResponse {
+        folders_created: s.folders_created as i64,
+        files_created: s.files_created as i64,
+        extracted_bytes: s.extracted_bytes as i64,
+    }
+}
+
+pub fn map_compression_policy_to_proto(p: CompressionPolicy) -> CompressionPolicyResponse {
+    CompressionPolicyResponse {
+        id: Option::from(map_id_to_proto(p.id)),
+        user_id: p.user_id.map(map_id_to_proto),
+        min_idle_days: p.min_idle_days,
+        min_size: p.min_size,
+        codec: map_archive_codec_to_proto(p.codec) as i32,
+        excluded_types: p
+            .excluded_types
+            .iter()
+            .map(|t| map_file_type_to_proto(t) as i32)
+            .collect(),
+        is_enabled: p.is_enabled,
+        updated_at: Some(prost_types::Timestamp {
+            seconds: p.updated_at.unix_timestamp(),
+            nanos: p.updated_at.nanosecond() as i32,
+        }),
+    }
+}
+
+fn map_file_type_to_proto(file_type: &DomainFileType) -> ProtoFileType {
+    match file_type {
+        DomainFileType::Image => ProtoFileType::Image,
+        DomainFileType::Text => ProtoFileType::Text,
+        DomainFileType::Video => ProtoFileType::Video,
+        DomainFileType::Audio => ProtoFileType::Audio,
+        DomainFileType::Pdf => ProtoFileType::Pdf,
+        DomainFileType::Zip => ProtoFileType::Zip,
+        DomainFileType::Unknown => ProtoFileType::Unknown,
+    }
+}
+
+#[allow(clippy::result_large_err)]
+pub fn map_file_type_from_proto(file_type: i32) -> Result<DomainFileType, Status> {
+    match ProtoFileType::try_from(file_type) {
+        Ok(ProtoFileType::Image) => Ok(DomainFileType::Image),
+        Ok(ProtoFileType::Text) => Ok(DomainFileType::Text),
+        Ok(ProtoFileType::Video) => Ok(DomainFileType::Video),
+        Ok(ProtoFileType::Audio) => Ok(DomainFileType::Audio),
+        Ok(ProtoFileType::Pdf) => Ok(DomainFileType::Pdf),
+        Ok(ProtoFileType::Zip) => Ok(DomainFileType::Zip),
+        Ok(ProtoFileType::Unknown) => Ok(DomainFileType::Unknown),
+        Err(_) => Err(Status::invalid_argument("Invalid file type")),
+    }
+}
+
+fn map_archive_codec_to_proto(codec: ArchiveCodec) -> ProtoArchiveCodec {
+    match codec {
+        ArchiveCodec::Gzip => ProtoArchiveCodec::Gzip,
+        ArchiveCodec::Zstd => ProtoArchiveCodec::Zstd,
+        ArchiveCodec::Xz => ProtoArchiveCodec::Xz,
+    }
+}
+
+#[allow(clippy::result_large_err)]
+pub fn map_archive_codec_from_proto(codec: i32) -> Result<ArchiveCodec, Status> {
+    match ProtoArchiveCodec::try_from(codec) {
+        Ok(ProtoArchiveCodec::Gzip) => Ok(ArchiveCodec::Gzip),
+        Ok(ProtoArchiveCodec::Zstd) => Ok(ArchiveCodec::Zstd),
+        Ok(ProtoArchiveCodec::Xz) => Ok(ArchiveCodec::Xz),
+        Err(_) => Err(Status::invalid_argument("Invalid archive codec")),
+    }
+}
+
+fn map_access_type_to_proto(access_type: &SharedFileAccessType) -> i32 {
+    let proto_access_type = match access_type {
+        SharedFileAccessType::ReadOnly => ProtoSharedFileAccessType::ReadOnly,
+        SharedFileAccessType::Edit => ProtoSharedFileAccessType::Edit,
+    };
+
+    proto_access_type as i32
+}
+
+#[allow(clippy::result_large_err)]
+pub fn map_access_type_from_proto(access_type: i32) -> Result<SharedFileAccessType, Status> {
+    match ProtoSharedFileAccessType::try_from(access_type) {
+        Ok(ProtoSharedFileAccessType::ReadOnly) => Ok(SharedFileAccessType::ReadOnly),
+        Ok(ProtoSharedFileAccessType::Edit) => Ok(SharedFileAccessType::Edit),
+        Err(_) => Err(Status::invalid_argument("Invalid access type")),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+pub fn map_bundle_format_from_proto(format: i32) -> Result<BundleFormat, Status> {
+    match ProtoBundleFormat::try_from(format) {
+        Ok(ProtoBundleFormat::Zip) => Ok(BundleFormat::Zip),
+        Ok(ProtoBundleFormat::TarGz) => Ok(BundleFormat::TarGz),
+        Err(_) => Err(Status::invalid_argument("Invalid bundle format")),
+    }
+}
+
 pub fn map_id_to_proto(id: Uuid) -> EntityId {
     EntityId {
         value: id.to_string(),
     }
 }
 
+#[allow(clippy::result_large_err)]
+pub fn map_timestamp_from_proto(ts: prost_types::Timestamp) -> Result<OffsetDateTime, Status> {
+    let nanos = ts.seconds as i128 * 1_000_000_000 + ts.nanos as i128;
+
+    OffsetDateTime::from_unix_timestamp_nanos(nanos)
+        .map_err(|_| Status::invalid_argument("Invalid timestamp"))
+}
+
 pub fn map_entity_id(id: Option<EntityId>) -> Result<Uuid, Status> {
     let entity_id = id.ok_or_else(|| Status::invalid_argument("Missing ID"))?;
 
@@ -0,0 +1,50 @@
+use crate::service::compression_policy_service::CompressionPolicyService;
+use std::sync::Arc;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+const DEFAULT_COMPRESSION_BATCH_SIZE: i64 = 200;
+
+pub async fn init_compression_policy_job(
+    compression_policy_service: Arc<dyn CompressionPolicyService>,
+) -> JobScheduler {
+    let sched = JobScheduler::new()
+        .await
+        .expect("Failed to create Compression Policy JobScheduler");
+
+    // Files handled per policy and night, the coldest ones go first
+    let batch_size = std::env::var("COLD_STORAGE_BATCH_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_COMPRESSION_BATCH_SIZE);
+
+    let service_clone = compression_policy_service.clone();
+
+    let compression_job = Job::new_async("0 0 2 * * *", move |_uuid, _l| {
+        let service = service_clone.clone();
+        Box::pin(async move {
+            tracing::info!("🕒 [Cron] Starting cold storage compression...");
+
+            match service.apply_policies(batch_size).await {
+                Ok(summary) => tracing::info!(
+                    "✅ [Cron] Cold storage compressed {} files, skipped {}, {} failed.",
+                    summary.compressed,
+                    summary.skipped,
+                    summary.failed
+                ),
+                Err(e) => tracing::error!("❌ [Cron] Cold storage compression failed: {:?}", e),
+            }
+        })
+    })
+    .expect("Failed to create compression policy job");
+
+    sched
+        .add(compression_job)
+        .await
+        .expect("Failed to add compression policy job");
+
+    sched.start().await.expect("Failed to start scheduler");
+
+    tracing::info!("⏰ Compression Policy Scheduler started successfully");
+
+    sched
+}
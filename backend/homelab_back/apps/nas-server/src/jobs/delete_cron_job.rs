@@ -1,12 +1,23 @@
 use crate::service::file_service::FileService;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio_cron_scheduler::{Job, JobScheduler};
 
+const DEFAULT_UPLOAD_RESERVATION_TTL_HOURS: u64 = 24;
+
 pub async fn init_delete_job(file_service: Arc<dyn FileService>) -> JobScheduler {
     let sched = JobScheduler::new()
         .await
         .expect("Failed to create Delete JobScheduler");
 
+    // Pending uploads keep their quota reserved until they finish or go quiet for this long
+    let reservation_ttl_hours = std::env::var("UPLOAD_RESERVATION_TTL_HOURS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_UPLOAD_RESERVATION_TTL_HOURS);
+
+    let reservation_ttl = Duration::from_secs(reservation_ttl_hours * 3600);
+
     let service_clone = file_service.clone();
 
     let cleanup_job = Job::new_async("0 0 3 * * *", move |_uuid, _l| {
@@ -18,6 +29,11 @@ pub async fn init_delete_job(file_service: Arc<dyn FileService>) -> JobScheduler
                 Ok(_) => tracing::info!("✅ [Cron] Daily cleanup completed successfully."),
                 Err(e) => tracing::error!("❌ [Cron] Daily cleanup failed: {:?}", e),
             }
+
+            match service.expire_stale_uploads(reservation_ttl).await {
+                Ok(_) => tracing::info!("✅ [Cron] Stale upload reservations released."),
+                Err(e) => tracing::error!("❌ [Cron] Releasing stale uploads failed: {:?}", e),
+            }
         })
     })
     .expect("Failed to create cleanup job");
@@ -0,0 +1,51 @@
+use crate::data::version_retention_policy::VersionRetentionPolicy;
+use crate::service::file_service::FileService;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+pub async fn init_file_version_retention_job(file_service: Arc<dyn FileService>) -> JobScheduler {
+    let sched = JobScheduler::new()
+        .await
+        .expect("Failed to create File Version Retention JobScheduler");
+
+    let keep_count = std::env::var("FILE_VERSION_KEEP_COUNT")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(10);
+
+    let keep_days = std::env::var("FILE_VERSION_KEEP_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(30);
+
+    let policy =
+        VersionRetentionPolicy::new(keep_count, Duration::from_secs(keep_days * 24 * 60 * 60));
+
+    let service_clone = file_service.clone();
+
+    let retention_job = Job::new_async("0 30 3 * * *", move |_uuid, _l| {
+        let service = service_clone.clone();
+        let policy = policy.clone();
+        Box::pin(async move {
+            tracing::info!("🕒 [Cron] Starting file version pruning...");
+
+            match service.prune_file_versions(policy).await {
+                Ok(pruned) => tracing::info!("✅ [Cron] Pruned {} file versions.", pruned),
+                Err(e) => tracing::error!("❌ [Cron] File version pruning failed: {:?}", e),
+            }
+        })
+    })
+    .expect("Failed to create file version retention job");
+
+    sched
+        .add(retention_job)
+        .await
+        .expect("Failed to add file version retention job");
+
+    sched.start().await.expect("Failed to start scheduler");
+
+    tracing::info!("⏰ File Version Retention Scheduler started successfully");
+
+    sched
+}
@@ -0,0 +1,50 @@
+use crate::service::integrity_service::IntegrityService;
+use std::sync::Arc;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+const DEFAULT_SCRUB_BATCH_SIZE: i64 = 500;
+
+pub async fn init_integrity_scrub_job(
+    integrity_service: Arc<dyn IntegrityService>,
+) -> JobScheduler {
+    let sched = JobScheduler::new()
+        .await
+        .expect("Failed to create Integrity JobScheduler");
+
+    // Every run re-hashes the least recently checked files, so the whole tree is covered over time
+    let batch_size = std::env::var("INTEGRITY_SCRUB_BATCH_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_SCRUB_BATCH_SIZE);
+
+    let service_clone = integrity_service.clone();
+
+    let scrub_job = Job::new_async("0 0 4 * * *", move |_uuid, _l| {
+        let service = service_clone.clone();
+        Box::pin(async move {
+            tracing::info!("🕒 [Cron] Starting integrity scrub...");
+
+            match service.scrub(batch_size).await {
+                Ok(summary) => tracing::info!(
+                    "✅ [Cron] Integrity scrub checked {} files, {} missing, {} corrupted.",
+                    summary.checked,
+                    summary.missing,
+                    summary.corrupted
+                ),
+                Err(e) => tracing::error!("❌ [Cron] Integrity scrub failed: {:?}", e),
+            }
+        })
+    })
+    .expect("Failed to create integrity scrub job");
+
+    sched
+        .add(scrub_job)
+        .await
+        .expect("Failed to add integrity scrub job");
+
+    sched.start().await.expect("Failed to start scheduler");
+
+    tracing::info!("⏰ Integrity Scheduler started successfully");
+
+    sched
+}
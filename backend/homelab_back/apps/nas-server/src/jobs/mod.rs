@@ -1 +1,7 @@
+pub mod compression_policy_job;
 pub mod delete_cron_job;
+pub mod file_version_retention_job;
+pub mod integrity_scrub_job;
+pub mod preview_job;
+pub mod reconciliation_job;
+pub mod storage_recalculation_job;
\ No newline at end of file
@@ -0,0 +1,17 @@
+use crate::service::preview_service::PreviewService;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+// Every worker runs one ffmpeg or pdftoppm at a time, so this bounds how many run at once
+const DEFAULT_PREVIEW_CONCURRENCY: usize = 2;
+
+pub fn init_preview_workers(preview_service: Arc<dyn PreviewService>) -> JoinHandle<()> {
+    let concurrency = std::env::var("PREVIEW_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_PREVIEW_CONCURRENCY);
+
+    tracing::info!("🖼️ Starting {} preview workers", concurrency);
+
+    tokio::spawn(async move { preview_service.run_workers(concurrency).await })
+}
@@ -0,0 +1,63 @@
+use crate::data::reconciliation_options::ReconciliationOptions;
+use crate::service::reconciliation_service::ReconciliationService;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+pub const DEFAULT_STALE_AFTER_HOURS: u64 = 24;
+
+pub async fn init_reconciliation_job(
+    reconciliation_service: Arc<dyn ReconciliationService>,
+) -> JobScheduler {
+    let sched = JobScheduler::new()
+        .await
+        .expect("Failed to create Reconciliation JobScheduler");
+
+    // Only reports by default, repairs have to be switched on explicitly
+    let repair = std::env::var("RECONCILE_REPAIR")
+        .ok()
+        .and_then(|v| v.parse::<bool>().ok())
+        .unwrap_or(false);
+
+    let stale_after_hours = std::env::var("RECONCILE_STALE_AFTER_HOURS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_STALE_AFTER_HOURS);
+
+    let options = ReconciliationOptions::new(repair, Duration::from_secs(stale_after_hours * 3600));
+
+    let service_clone = reconciliation_service.clone();
+
+    let reconcile_job = Job::new_async("0 30 4 * * *", move |_uuid, _l| {
+        let service = service_clone.clone();
+        let options = options.clone();
+        Box::pin(async move {
+            tracing::info!("🕒 [Cron] Starting storage reconciliation...");
+
+            match service.reconcile(options).await {
+                Ok(report) if report.is_clean() => {
+                    tracing::info!("✅ [Cron] Storage reconciliation found no drift.")
+                }
+                Ok(report) => tracing::warn!(
+                    "⚠️ [Cron] Storage reconciliation found {} drifts (repaired: {}): {:?}",
+                    report.drift_count(),
+                    report.repaired,
+                    report
+                ),
+                Err(e) => tracing::error!("❌ [Cron] Storage reconciliation failed: {:?}", e),
+            }
+        })
+    })
+    .expect("Failed to create reconciliation job");
+
+    sched
+        .add(reconcile_job)
+        .await
+        .expect("Failed to add reconciliation job");
+
+    sched.start().await.expect("Failed to start scheduler");
+
+    tracing::info!("⏰ Reconciliation Scheduler started successfully");
+
+    sched
+}
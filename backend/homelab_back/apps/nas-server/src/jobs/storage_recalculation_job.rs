@@ -0,0 +1,51 @@
+use crate::service::storage_profile_service::StorageProfileService;
+use std::sync::Arc;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+pub async fn init_storage_recalculation_job(
+    storage_profile_service: Arc<dyn StorageProfileService>,
+) -> JobScheduler {
+    let sched = JobScheduler::new()
+        .await
+        .expect("Failed to create Storage Recalculation JobScheduler");
+
+    // Summing up the rows is cheap, stat-ing every blob on disk has to be switched on explicitly
+    let from_disk = std::env::var("STORAGE_RECALC_FROM_DISK")
+        .ok()
+        .and_then(|v| v.parse::<bool>().ok())
+        .unwrap_or(false);
+
+    let service_clone = storage_profile_service.clone();
+
+    let recalculation_job = Job::new_async("0 0 5 * * *", move |_uuid, _l| {
+        let service = service_clone.clone();
+        Box::pin(async move {
+            tracing::info!("🕒 [Cron] Starting storage usage recalculation...");
+
+            match service.recalculate_all_usage(from_disk).await {
+                Ok(summary) if summary.corrected == 0 => tracing::info!(
+                    "✅ [Cron] Storage usage of {} users is in sync.",
+                    summary.checked
+                ),
+                Ok(summary) => tracing::warn!(
+                    "⚠️ [Cron] Corrected storage usage of {} out of {} users.",
+                    summary.corrected,
+                    summary.checked
+                ),
+                Err(e) => tracing::error!("❌ [Cron] Storage usage recalculation failed: {:?}", e),
+            }
+        })
+    })
+    .expect("Failed to create storage recalculation job");
+
+    sched
+        .add(recalculation_job)
+        .await
+        .expect("Failed to add storage recalculation job");
+
+    sched.start().await.expect("Failed to start scheduler");
+
+    tracing::info!("⏰ Storage Recalculation Scheduler started successfully");
+
+    sched
+}
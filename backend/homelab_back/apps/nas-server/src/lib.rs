@@ -0,0 +1,40 @@
+pub mod data;
+pub mod db;
+pub mod events;
+pub mod grpc;
+pub mod handler;
+pub mod helpers;
+pub mod jobs;
+pub mod service;
+pub mod storage;
+
+use crate::db::file_repository::FileRepository;
+use crate::service::archive_content_service::ArchiveContentService;
+use crate::service::bundle_service::BundleService;
+use crate::service::compression_policy_service::CompressionPolicyService;
+use crate::service::file_label_service::FileLabelService;
+use crate::service::file_service::FileService;
+use crate::service::folder_service::FolderService;
+use crate::service::global_file_service::GlobalFileService;
+use crate::service::label_service::LabelService;
+use crate::service::preview_service::PreviewService;
+use crate::service::share_link_service::ShareLinkService;
+use crate::service::shared_file_service::SharedFileService;
+use crate::service::storage_profile_service::StorageProfileService;
+use std::sync::Arc;
+
+pub struct AppState {
+    pub file_service: Arc<dyn FileService>,
+    pub folder_service: Arc<dyn FolderService>,
+    pub shared_file_service: Arc<dyn SharedFileService>,
+    pub file_repo: Arc<dyn FileRepository>,
+    pub global_file_service: Arc<dyn GlobalFileService>,
+    pub label_service: Arc<dyn LabelService>,
+    pub file_label_service: Arc<dyn FileLabelService>,
+    pub storage_profile_service: Arc<dyn StorageProfileService>,
+    pub share_link_service: Arc<dyn ShareLinkService>,
+    pub bundle_service: Arc<dyn BundleService>,
+    pub archive_content_service: Arc<dyn ArchiveContentService>,
+    pub compression_policy_service: Arc<dyn CompressionPolicyService>,
+    pub preview_service: Arc<dyn PreviewService>,
+}
@@ -0,0 +1,184 @@
+use crate::db::folder_repository::FolderRepository;
+use crate::db::global_file_repository::GlobalFileRepository;
+use crate::db::shared_file_repository::SharedFileRepository;
+use crate::db::shared_folder_repository::SharedFolderRepository;
+use crate::helpers::data_error::DataError;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::file::File;
+use homelab_core::folder::Folder;
+use homelab_core::label::Label;
+use homelab_core::shared_file::SharedFileAccessType;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// What a caller is allowed to do with a file or folder. Levels are ordered,
+/// so a higher level always includes everything the lower ones allow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FileAccessLevel {
+    None,
+    Read,
+    Edit,
+    Owner,
+}
+
+impl From<&SharedFileAccessType> for FileAccessLevel {
+    fn from(access_type: &SharedFileAccessType) -> Self {
+        match access_type {
+            SharedFileAccessType::ReadOnly => FileAccessLevel::Read,
+            SharedFileAccessType::Edit => FileAccessLevel::Edit,
+        }
+    }
+}
+
+#[async_trait]
+pub trait AccessControlService: Send + Sync {
+    async fn file_access_level(
+        &self,
+        caller_id: Uuid,
+        file: &File,
+    ) -> Result<FileAccessLevel, DataError>;
+    async fn authorize_file(
+        &self,
+        caller_id: Uuid,
+        file: &File,
+        required: FileAccessLevel,
+    ) -> Result<(), DataError>;
+    async fn folder_access_level(
+        &self,
+        caller_id: Uuid,
+        folder: &Folder,
+    ) -> Result<FileAccessLevel, DataError>;
+    async fn authorize_folder(
+        &self,
+        caller_id: Uuid,
+        folder: &Folder,
+        required: FileAccessLevel,
+    ) -> Result<(), DataError>;
+    fn authorize_label(&self, caller_id: Uuid, label: &Label) -> Result<(), DataError>;
+}
+
+#[derive(new)]
+pub struct AccessControlServiceImpl {
+    shared_file_repo: Arc<dyn SharedFileRepository>,
+    global_file_repo: Arc<dyn GlobalFileRepository>,
+    folder_repo: Arc<dyn FolderRepository>,
+    shared_folder_repo: Arc<dyn SharedFolderRepository>,
+}
+
+impl AccessControlServiceImpl {
+    /// Strongest grant the caller holds on the folder or any of its ancestors
+    async fn inherited_level(
+        &self,
+        caller_id: Uuid,
+        folder_id: Uuid,
+    ) -> Result<FileAccessLevel, DataError> {
+        let ancestors = self.folder_repo.get_ancestor_ids(folder_id).await?;
+
+        let level = self
+            .shared_folder_repo
+            .get_strongest_for_folders(caller_id, &ancestors)
+            .await?
+            .map(|shared| FileAccessLevel::from(&shared.access_type))
+            .unwrap_or(FileAccessLevel::None);
+
+        Ok(level)
+    }
+}
+
+#[async_trait]
+impl AccessControlService for AccessControlServiceImpl {
+    async fn file_access_level(
+        &self,
+        caller_id: Uuid,
+        file: &File,
+    ) -> Result<FileAccessLevel, DataError> {
+        if file.owner_id == caller_id {
+            return Ok(FileAccessLevel::Owner);
+        }
+
+        let direct = self
+            .shared_file_repo
+            .get_for_user_and_file(caller_id, file.id)
+            .await?
+            .map(|shared| FileAccessLevel::from(&shared.access_type))
+            .unwrap_or(FileAccessLevel::None);
+
+        // A grant on any enclosing folder also covers files added to it later
+        let inherited = self
+            .inherited_level(caller_id, file.parent_folder_id)
+            .await?;
+
+        let level = direct.max(inherited);
+
+        if level > FileAccessLevel::None {
+            return Ok(level);
+        }
+
+        // Global files are visible to every user, but only the owner can change them
+        if self.global_file_repo.is_global(file.id).await? {
+            return Ok(FileAccessLevel::Read);
+        }
+
+        Ok(FileAccessLevel::None)
+    }
+
+    async fn authorize_file(
+        &self,
+        caller_id: Uuid,
+        file: &File,
+        required: FileAccessLevel,
+    ) -> Result<(), DataError> {
+        let level = self.file_access_level(caller_id, file).await?;
+
+        if level < required {
+            return Err(DataError::PermissionDeniedError(format!(
+                "file {}",
+                file.id
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn folder_access_level(
+        &self,
+        caller_id: Uuid,
+        folder: &Folder,
+    ) -> Result<FileAccessLevel, DataError> {
+        if folder.owner_id == caller_id {
+            return Ok(FileAccessLevel::Owner);
+        }
+
+        self.inherited_level(caller_id, folder.id).await
+    }
+
+    async fn authorize_folder(
+        &self,
+        caller_id: Uuid,
+        folder: &Folder,
+        required: FileAccessLevel,
+    ) -> Result<(), DataError> {
+        let level = self.folder_access_level(caller_id, folder).await?;
+
+        if level < required {
+            return Err(DataError::PermissionDeniedError(format!(
+                "folder {}",
+                folder.id
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn authorize_label(&self, caller_id: Uuid, label: &Label) -> Result<(), DataError> {
+        if label.owner_id != caller_id {
+            return Err(DataError::PermissionDeniedError(format!(
+                "label {}",
+                label.id
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,786 @@
+use crate::data::archive_entry::ArchiveEntry;
+use crate::data::extract_archive_command::ExtractArchiveCommand;
+use crate::data::extraction_summary::ExtractionSummary;
+use crate::data::init_file_command::InitFileCommand;
+use crate::db::folder_repository::FolderRepository;
+use crate::db::storage_profile_repository::StorageProfileRepository;
+use crate::helpers::data_error::DataError;
+use crate::service::access_control_service::{AccessControlService, FileAccessLevel};
+use crate::service::file_service::FileService;
+use crate::storage::storage_backend::ObjectReader;
+use async_compression::tokio::bufread::GzipDecoder;
+use async_trait::async_trait;
+use async_zip::tokio::read::seek::ZipFileReader;
+use async_zip::ZipDateTime;
+use derive_new::new;
+use futures::StreamExt;
+use homelab_core::constants::GB;
+use homelab_core::file::File;
+use homelab_core::folder::Folder;
+use sqlx::types::time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+use std::collections::{HashMap, HashSet};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use time::Month;
+use tokio::fs;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::{self, Sender};
+use tokio_tar::Archive;
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+use uuid::Uuid;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+const MAX_ENTRIES: usize = 10_000;
+const MAX_EXTRACTED_SIZE: u64 = 50 * GB as u64;
+// Ordinary content rarely packs better than this, archives claiming more are treated as bombs
+const MAX_COMPRESSION_RATIO: u64 = 100;
+
+#[async_trait]
+pub trait ArchiveContentService: Send + Sync {
+    async fn list_entries(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<Vec<ArchiveEntry>, DataError>;
+    async fn extract(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+        command: ExtractArchiveCommand,
+    ) -> Result<ExtractionSummary, DataError>;
+}
+
+#[derive(new)]
+pub struct ArchiveContentServiceImpl {
+    file_service: Arc<dyn FileService>,
+    folder_repo: Arc<dyn FolderRepository>,
+    storage_profile_repo: Arc<dyn StorageProfileRepository>,
+    access_control: Arc<dyn AccessControlService>,
+    storage_path: PathBuf,
+}
+
+#[derive(Clone, Copy)]
+enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    fn from_name(name: &str) -> Result<Self, DataError> {
+        let name = name.to_lowercase();
+
+        if name.ends_with(".zip") {
+            Ok(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar") {
+            Ok(ArchiveFormat::Tar)
+        } else {
+            Err(DataError::ValidationError(
+                "Only zip, tar and tar.gz archives can be opened".to_string(),
+            ))
+        }
+    }
+}
+
+// An entry that passed every check, index is its position in the listing of the archive
+struct PlannedEntry {
+    index: usize,
+    components: Vec<String>,
+    size: u64,
+    is_dir: bool,
+}
+
+#[async_trait]
+impl ArchiveContentService for ArchiveContentServiceImpl {
+    async fn list_entries(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<Vec<ArchiveEntry>, DataError> {
+        let (file, reader) = self
+            .file_service
+            .get_file_for_streaming(file_id, caller_id, None)
+            .await?;
+
+        match ArchiveFormat::from_name(&file.name)? {
+            ArchiveFormat::Zip => {
+                let path = self.copy_to_temp(reader).await?;
+                let entries = match open_zip(&path).await {
+                    Ok(zip) => zip_entries(&zip),
+                    Err(e) => Err(e),
+                };
+
+                let _ = fs::remove_file(&path).await;
+                entries
+            }
+            format => tar_entries(open_tar(reader, format)).await,
+        }
+    }
+
+    async fn extract(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+        command: ExtractArchiveCommand,
+    ) -> Result<ExtractionSummary, DataError> {
+        let (file, reader) = self
+            .file_service
+            .get_file_for_streaming(file_id, caller_id, None)
+            .await?;
+
+        let format = ArchiveFormat::from_name(&file.name)?;
+
+        let target = self
+            .folder_repo
+            .get_by_id(command.target_folder_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Folder".to_string()))?;
+
+        self.access_control
+            .authorize_folder(caller_id, &target, FileAccessLevel::Owner)
+            .await?;
+
+        match format {
+            ArchiveFormat::Zip => {
+                let path = self.copy_to_temp(reader).await?;
+                let summary = self
+                    .extract_zip(&path, &file, &target, caller_id, &command.entries)
+                    .await;
+
+                let _ = fs::remove_file(&path).await;
+                summary
+            }
+            format => {
+                // A tar stream can only be read front to back, so it is checked on a first pass
+                // and extracted on a second one
+                let entries = tar_entries(open_tar(reader, format)).await?;
+                let plan = self
+                    .plan_extraction(&file, &target, caller_id, entries, &command.entries)
+                    .await?;
+
+                let (_, reader) = self
+                    .file_service
+                    .get_file_for_streaming(file.id, caller_id, None)
+                    .await?;
+
+                self.extract_tar(open_tar(reader, format), plan, &target, caller_id)
+                    .await
+            }
+        }
+    }
+}
+
+impl ArchiveContentServiceImpl {
+    // Nothing is extracted before the server is up, so anything left here came from a crash
+    pub async fn clear_extract_dir(&self) {
+        match fs::remove_dir_all(self.extract_dir()).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => tracing::error!("Failed to clear the extraction directory: {:?}", e),
+        }
+    }
+
+    fn extract_dir(&self) -> PathBuf {
+        self.storage_path.join("extract")
+    }
+
+    // Zip keeps its directory at the end, so it is read from a local copy instead of a stream.
+    // With encryption at rest that copy is plaintext, it is removed as soon as reading ends
+    // and a copy left behind by a crash is cleared on the next start
+    async fn copy_to_temp(&self, mut reader: ObjectReader) -> Result<PathBuf, DataError> {
+        let dir = self.extract_dir();
+
+        fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        let path = dir.join(format!("{}.zip", Uuid::new_v4()));
+
+        let mut temp = fs::File::create(&path)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        let copied = match tokio::io::copy(&mut reader, &mut temp).await {
+            Ok(_) => temp.flush().await,
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = copied {
+            let _ = fs::remove_file(&path).await;
+            return Err(DataError::IOError(e.to_string()));
+        }
+
+        Ok(path)
+    }
+
+    // Every guard runs before anything is written, so a rejected archive leaves no trace.
+    // Content that turns out broken while it is read can still stop an extraction halfway
+    async fn plan_extraction(
+        &self,
+        archive: &File,
+        target: &Folder,
+        caller_id: Uuid,
+        entries: Vec<ArchiveEntry>,
+        requested: &[String],
+    ) -> Result<Vec<PlannedEntry>, DataError> {
+        let requested: Vec<String> = requested
+            .iter()
+            .map(|path| path.replace('\\', "/").trim_matches('/').to_string())
+            .collect();
+
+        let mut matched = vec![false; requested.len()];
+        let mut planned = Vec::new();
+        let mut total_size: u64 = 0;
+
+        for (index, entry) in entries.into_iter().enumerate() {
+            if !requested.is_empty() {
+                let path = entry.path.trim_end_matches('/');
+                let mut is_chosen = false;
+
+                for (i, chosen) in requested.iter().enumerate() {
+                    if path == chosen.as_str() || path.starts_with(&format!("{}/", chosen)) {
+                        matched[i] = true;
+                        is_chosen = true;
+                    }
+                }
+
+                if !is_chosen {
+                    continue;
+                }
+            }
+
+            let components = entry_components(&entry.path)?;
+
+            if components.is_empty() {
+                continue;
+            }
+
+            if !entry.is_dir {
+                total_size = total_size.saturating_add(entry.size);
+            }
+
+            planned.push(PlannedEntry {
+                index,
+                components,
+                size: if entry.is_dir { 0 } else { entry.size },
+                is_dir: entry.is_dir,
+            });
+        }
+
+        if let Some(i) = matched.iter().position(|m| !m) {
+            return Err(DataError::ValidationError(format!(
+                "Archive has no entry {}",
+                requested[i]
+            )));
+        }
+
+        if planned.len() > MAX_ENTRIES {
+            return Err(DataError::ValidationError(format!(
+                "Archive holds more than {} entries",
+                MAX_ENTRIES
+            )));
+        }
+
+        let packed_size = archive.logical_size().max(1) as u64;
+
+        if total_size > MAX_EXTRACTED_SIZE
+            || total_size > packed_size.saturating_mul(MAX_COMPRESSION_RATIO)
+        {
+            return Err(DataError::ValidationError(
+                "Archive unpacks to far more than it could reasonably hold".to_string(),
+            ));
+        }
+
+        self.check_collisions(target, &planned).await?;
+
+        let sp = self
+            .storage_profile_repo
+            .get_by_id(caller_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("User".to_string()))?;
+
+        if !sp.validate_storage_size(total_size as i64) {
+            return Err(DataError::NoFreeStorageError);
+        }
+
+        Ok(planned)
+    }
+
+    // Uploads refuse a name that is taken, so clashes are looked for here instead of halfway through.
+    // Folders merge into existing ones, files have to be new in their folder and in the archive
+    async fn check_collisions(
+        &self,
+        target: &Folder,
+        planned: &[PlannedEntry],
+    ) -> Result<(), DataError> {
+        // Paths come back starting with the target's own name, entries are relative to it
+        let folder_ids: HashMap<String, Uuid> = self
+            .folder_repo
+            .get_folder_paths(target.id)
+            .await?
+            .into_iter()
+            .map(|(id, path)| {
+                let relative = path
+                    .split_once('/')
+                    .map(|(_, rest)| rest.to_string())
+                    .unwrap_or_default();
+
+                (relative, id)
+            })
+            .collect();
+
+        let ids: Vec<Uuid> = folder_ids.values().cloned().collect();
+
+        let taken: HashSet<(Uuid, String)> = self
+            .folder_repo
+            .get_by_folder_ids(&ids)
+            .await?
+            .into_iter()
+            .map(|f| (f.parent_folder_id, f.name))
+            .collect();
+
+        let mut seen = HashSet::new();
+
+        for entry in planned.iter().filter(|e| !e.is_dir) {
+            let path = entry.components.join("/");
+
+            if !seen.insert(path.clone()) {
+                return Err(DataError::ValidationError(format!(
+                    "Archive holds {} more than once",
+                    path
+                )));
+            }
+
+            let Some((name, parents)) = entry.components.split_last() else {
+                continue;
+            };
+
+            if let Some(folder_id) = folder_ids.get(&parents.join("/")) {
+                if taken.contains(&(*folder_id, name.clone())) {
+                    return Err(DataError::FileAlreadyExistsError);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn extract_zip(
+        &self,
+        path: &Path,
+        archive: &File,
+        target: &Folder,
+        caller_id: Uuid,
+        requested: &[String],
+    ) -> Result<ExtractionSummary, DataError> {
+        let mut zip = open_zip(path).await?;
+
+        let entries = zip_entries(&zip)?;
+        let plan = self
+            .plan_extraction(archive, target, caller_id, entries, requested)
+            .await?;
+
+        let mut summary = ExtractionSummary::default();
+        let mut folders = HashMap::new();
+
+        for entry in plan {
+            let folder_id = self
+                .ensure_folders(target.id, &entry, caller_id, &mut folders, &mut summary)
+                .await?;
+
+            if entry.is_dir {
+                continue;
+            }
+
+            let content = zip
+                .reader_with_entry(entry.index)
+                .await
+                .map_err(|e| DataError::IOError(e.to_string()))?;
+
+            self.extract_file(folder_id, &entry, caller_id, content.compat())
+                .await?;
+
+            summary.files_created += 1;
+            summary.extracted_bytes += entry.size;
+        }
+
+        Ok(summary)
+    }
+
+    async fn extract_tar(
+        &self,
+        reader: ObjectReader,
+        plan: Vec<PlannedEntry>,
+        target: &Folder,
+        caller_id: Uuid,
+    ) -> Result<ExtractionSummary, DataError> {
+        let mut plan: HashMap<usize, PlannedEntry> =
+            plan.into_iter().map(|entry| (entry.index, entry)).collect();
+
+        let mut summary = ExtractionSummary::default();
+        let mut folders = HashMap::new();
+
+        let mut archive = Archive::new(reader);
+        let mut tar_entries = archive
+            .entries()
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        let mut index = 0;
+
+        while let Some(tar_entry) = tar_entries.next().await {
+            if plan.is_empty() {
+                break;
+            }
+
+            let tar_entry = tar_entry.map_err(|e| DataError::IOError(e.to_string()))?;
+
+            // The index has to be counted exactly like the listing counted it
+            if tar_entry_info(&tar_entry)?.is_none() {
+                continue;
+            }
+
+            let current = index;
+            index += 1;
+
+            let Some(entry) = plan.remove(&current) else {
+                continue;
+            };
+
+            let folder_id = self
+                .ensure_folders(target.id, &entry, caller_id, &mut folders, &mut summary)
+                .await?;
+
+            if entry.is_dir {
+                continue;
+            }
+
+            self.extract_file(folder_id, &entry, caller_id, tar_entry)
+                .await?;
+
+            summary.files_created += 1;
+            summary.extracted_bytes += entry.size;
+        }
+
+        Ok(summary)
+    }
+
+    // Returns the folder the entry belongs in, or the folder it is itself. Folders that already
+    // exist under the target are reused, so extracting into a filled folder merges into it
+    async fn ensure_folders(
+        &self,
+        target_id: Uuid,
+        entry: &PlannedEntry,
+        caller_id: Uuid,
+        folders: &mut HashMap<String, Uuid>,
+        summary: &mut ExtractionSummary,
+    ) -> Result<Uuid, DataError> {
+        let depth = if entry.is_dir {
+            entry.components.len()
+        } else {
+            entry.components.len() - 1
+        };
+
+        let mut parent_id = target_id;
+
+        for i in 0..depth {
+            let key = entry.components[..=i].join("/");
+
+            if let Some(id) = folders.get(&key) {
+                parent_id = *id;
+                continue;
+            }
+
+            let name = &entry.components[i];
+
+            let existing = self
+                .folder_repo
+                .get_children_by_id(parent_id)
+                .await?
+                .into_iter()
+                .find(|f| &f.name == name);
+
+            let folder_id = match existing {
+                Some(folder) => folder.id,
+                None => {
+                    let folder =
+                        Folder::new(Uuid::new_v4(), Some(parent_id), name.clone(), caller_id);
+                    summary.folders_created += 1;
+
+                    self.folder_repo.create(folder).await?.id
+                }
+            };
+
+            folders.insert(key, folder_id);
+            parent_id = folder_id;
+        }
+
+        Ok(parent_id)
+    }
+
+    // Goes through the regular upload, so quota reservation, dedupe and previews all apply.
+    // A file that fails halfway stays pending and is cleaned up with other stale uploads
+    async fn extract_file<R>(
+        &self,
+        folder_id: Uuid,
+        entry: &PlannedEntry,
+        caller_id: Uuid,
+        content: R,
+    ) -> Result<(), DataError>
+    where
+        R: AsyncRead + Send,
+    {
+        let name = entry.components[entry.components.len() - 1].clone();
+
+        let file = self
+            .file_service
+            .upload(
+                InitFileCommand::new(folder_id, name, entry.size as i64, false, None),
+                caller_id,
+            )
+            .await?;
+
+        let (tx, rx) = mpsc::channel(4);
+
+        let (_, uploaded) = tokio::join!(
+            send_content(content, entry.size, tx),
+            self.file_service.upload_stream(file.id, caller_id, rx)
+        );
+
+        uploaded
+    }
+}
+
+// Entries may hold more than they claim, so reading stops one byte past the claimed size
+async fn send_content<R>(content: R, size: u64, tx: Sender<Result<Vec<u8>, DataError>>)
+where
+    R: AsyncRead + Send,
+{
+    let mut content = Box::pin(content.take(size + 1));
+    let mut sent: u64 = 0;
+
+    loop {
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+
+        let read = match content.read(&mut buffer).await {
+            Ok(read) => read,
+            Err(e) => {
+                let _ = tx.send(Err(DataError::IOError(e.to_string()))).await;
+                return;
+            }
+        };
+
+        if read == 0 {
+            return;
+        }
+
+        sent += read as u64;
+
+        if sent > size {
+            let _ = tx
+                .send(Err(DataError::ValidationError(
+                    "Archive entry holds more than it declares".to_string(),
+                )))
+                .await;
+            return;
+        }
+
+        buffer.truncate(read);
+
+        // The upload stopped listening, it reports its own error
+        if tx.send(Ok(buffer)).await.is_err() {
+            return;
+        }
+    }
+}
+
+// Splits an entry path into folder names, refusing anything that could land outside the target
+fn entry_components(path: &str) -> Result<Vec<String>, DataError> {
+    let invalid =
+        || DataError::ValidationError(format!("Archive entry {} has an unsafe path", path));
+
+    if path.starts_with('/') || path.as_bytes().get(1) == Some(&b':') {
+        return Err(invalid());
+    }
+
+    let mut components = Vec::new();
+
+    for component in path.split('/') {
+        match component {
+            "" | "." => continue,
+            ".." => return Err(invalid()),
+            name => components.push(name.to_string()),
+        }
+    }
+
+    Ok(components)
+}
+
+async fn open_zip(path: &Path) -> Result<ZipFileReader<BufReader<fs::File>>, DataError> {
+    let file = fs::File::open(path)
+        .await
+        .map_err(|e| DataError::IOError(e.to_string()))?;
+
+    ZipFileReader::with_tokio(BufReader::new(file))
+        .await
+        .map_err(|e| DataError::ValidationError(format!("Archive cannot be read: {}", e)))
+}
+
+fn zip_entries(zip: &ZipFileReader<BufReader<fs::File>>) -> Result<Vec<ArchiveEntry>, DataError> {
+    let entries = zip.file().entries();
+
+    if entries.len() > MAX_ENTRIES {
+        return Err(DataError::ValidationError(format!(
+            "Archive holds more than {} entries",
+            MAX_ENTRIES
+        )));
+    }
+
+    entries
+        .iter()
+        .map(|entry| {
+            let path = entry
+                .filename()
+                .as_str()
+                .map_err(|_| {
+                    DataError::ValidationError("Archive entry name is not valid UTF-8".to_string())
+                })?
+                .replace('\\', "/");
+
+            let is_dir = entry
+                .dir()
+                .map_err(|e| DataError::ValidationError(e.to_string()))?;
+
+            Ok(ArchiveEntry::new(
+                path,
+                if is_dir { 0 } else { entry.uncompressed_size() },
+                zip_modified_at(entry.last_modification_date()),
+                is_dir,
+            ))
+        })
+        .collect()
+}
+
+// Zip stores local time without a zone, it is taken as UTC
+fn zip_modified_at(date: &ZipDateTime) -> Option<OffsetDateTime> {
+    let month = Month::try_from(date.month() as u8).ok()?;
+    let day = Date::from_calendar_date(date.year(), month, date.day() as u8).ok()?;
+    let time = Time::from_hms(date.hour() as u8, date.minute() as u8, date.second() as u8).ok()?;
+
+    Some(PrimitiveDateTime::new(day, time).assume_utc())
+}
+
+fn open_tar(reader: ObjectReader, format: ArchiveFormat) -> ObjectReader {
+    match format {
+        ArchiveFormat::TarGz => Box::new(GzipDecoder::new(BufReader::new(reader))),
+        _ => reader,
+    }
+}
+
+async fn tar_entries(reader: ObjectReader) -> Result<Vec<ArchiveEntry>, DataError> {
+    let mut archive = Archive::new(reader);
+    let mut tar_entries = archive
+        .entries()
+        .map_err(|e| DataError::IOError(e.to_string()))?;
+
+    let mut entries = Vec::new();
+
+    while let Some(tar_entry) = tar_entries.next().await {
+        let tar_entry = tar_entry
+            .map_err(|e| DataError::ValidationError(format!("Archive cannot be read: {}", e)))?;
+
+        if let Some(entry) = tar_entry_info(&tar_entry)? {
+            if entries.len() == MAX_ENTRIES {
+                return Err(DataError::ValidationError(format!(
+                    "Archive holds more than {} entries",
+                    MAX_ENTRIES
+                )));
+            }
+
+            entries.push(entry);
+        }
+    }
+
+    Ok(entries)
+}
+
+// Links could point anywhere on disk, only plain files and directories are taken over
+fn tar_entry_info(
+    tar_entry: &tokio_tar::Entry<Archive<ObjectReader>>,
+) -> Result<Option<ArchiveEntry>, DataError> {
+    let header = tar_entry.header();
+    let entry_type = header.entry_type();
+
+    if !entry_type.is_file() && !entry_type.is_dir() {
+        return Ok(None);
+    }
+
+    let path = tar_entry
+        .path()
+        .map_err(|e| DataError::ValidationError(e.to_string()))?
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    let size = if entry_type.is_dir() {
+        0
+    } else {
+        header
+            .size()
+            .map_err(|e| DataError::ValidationError(e.to_string()))?
+    };
+
+    let modified_at = header
+        .mtime()
+        .ok()
+        .and_then(|mtime| OffsetDateTime::from_unix_timestamp(mtime as i64).ok());
+
+    Ok(Some(ArchiveEntry::new(
+        path,
+        size,
+        modified_at,
+        entry_type.is_dir(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_components_splits_nested_paths() {
+        let components = entry_components("docs/2024/report.pdf").unwrap();
+
+        assert_eq!(components, vec!["docs", "2024", "report.pdf"]);
+    }
+
+    #[test]
+    fn entry_components_skips_empty_and_current_dir_parts() {
+        let components = entry_components("./docs//notes/").unwrap();
+
+        assert_eq!(components, vec!["docs", "notes"]);
+        assert!(entry_components("./").unwrap().is_empty());
+    }
+
+    #[test]
+    fn entry_components_rejects_parent_dir_parts() {
+        assert!(entry_components("../outside.txt").is_err());
+        assert!(entry_components("docs/../../outside.txt").is_err());
+        assert!(entry_components("docs/..").is_err());
+    }
+
+    #[test]
+    fn entry_components_rejects_absolute_paths() {
+        assert!(entry_components("/etc/passwd").is_err());
+        assert!(entry_components("C:/Windows/win.ini").is_err());
+        assert!(entry_components("c:evil.txt").is_err());
+    }
+
+    #[test]
+    fn entry_components_keeps_dots_inside_names() {
+        let components = entry_components("..hidden/file..txt").unwrap();
+
+        assert_eq!(components, vec!["..hidden", "file..txt"]);
+    }
+}
@@ -0,0 +1,393 @@
+use crate::data::bundle_format::BundleFormat;
+use crate::db::file_repository::FileRepository;
+use crate::db::folder_repository::FolderRepository;
+use crate::helpers::archive_codec;
+use crate::helpers::data_error::DataError;
+use crate::service::access_control_service::{AccessControlService, FileAccessLevel};
+use crate::storage::storage_backend::{ObjectReader, StorageBackend};
+use async_compression::tokio::write::GzipEncoder;
+use async_trait::async_trait;
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use derive_new::new;
+use futures::AsyncWriteExt as _;
+use homelab_core::file::{File, UploadStatus};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, DuplexStream, ReadBuf};
+use tokio::task::JoinHandle;
+use tokio_tar::{EntryType, Header};
+use uuid::Uuid;
+
+// How much of the bundle is built ahead of what the client has read
+const PIPE_CAPACITY: usize = 256 * 1024;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[async_trait]
+pub trait BundleService: Send + Sync {
+    async fn bundle_folder(
+        &self,
+        folder_id: Uuid,
+        caller_id: Uuid,
+        format: BundleFormat,
+    ) -> Result<(String, ObjectReader), DataError>;
+    async fn bundle_files(
+        &self,
+        file_ids: &[Uuid],
+        caller_id: Uuid,
+        format: BundleFormat,
+    ) -> Result<(String, ObjectReader), DataError>;
+}
+
+#[derive(new)]
+pub struct BundleServiceImpl {
+    file_repo: Arc<dyn FileRepository>,
+    folder_repo: Arc<dyn FolderRepository>,
+    access_control: Arc<dyn AccessControlService>,
+    storage: Arc<dyn StorageBackend>,
+}
+
+// One item of the bundle, folders are kept too so that empty ones survive
+enum BundleEntry {
+    Folder(String),
+    File(String, File),
+}
+
+#[async_trait]
+impl BundleService for BundleServiceImpl {
+    async fn bundle_folder(
+        &self,
+        folder_id: Uuid,
+        caller_id: Uuid,
+        format: BundleFormat,
+    ) -> Result<(String, ObjectReader), DataError> {
+        let folder = self
+            .folder_repo
+            .get_by_id(folder_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Folder".to_string()))?;
+
+        // Access to a folder covers everything below it
+        self.access_control
+            .authorize_folder(caller_id, &folder, FileAccessLevel::Read)
+            .await?;
+
+        let folder_paths = self.folder_repo.get_folder_paths(folder.id).await?;
+
+        let folder_ids: Vec<Uuid> = folder_paths.iter().map(|(id, _)| *id).collect();
+        let paths: HashMap<Uuid, &str> = folder_paths
+            .iter()
+            .map(|(id, path)| (*id, path.as_str()))
+            .collect();
+
+        let mut entries: Vec<BundleEntry> = folder_paths
+            .iter()
+            .map(|(_, path)| BundleEntry::Folder(path.clone()))
+            .collect();
+
+        let mut files = self.folder_repo.get_by_folder_ids(&folder_ids).await?;
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for file in files {
+            if file.upload_status != UploadStatus::Completed {
+                continue;
+            }
+
+            let Some(folder_path) = paths.get(&file.parent_folder_id) else {
+                continue;
+            };
+
+            entries.push(BundleEntry::File(
+                format!("{}/{}", folder_path, file.name),
+                file,
+            ));
+        }
+
+        self.mark_accessed(&entries).await?;
+
+        let name = format!("{}.{}", folder.name, format.extension());
+
+        Ok((name, self.spawn_writer(entries, format)))
+    }
+
+    async fn bundle_files(
+        &self,
+        file_ids: &[Uuid],
+        caller_id: Uuid,
+        format: BundleFormat,
+    ) -> Result<(String, ObjectReader), DataError> {
+        if file_ids.is_empty() {
+            return Err(DataError::ValidationError(
+                "At least one file has to be chosen".to_string(),
+            ));
+        }
+
+        let files = self.file_repo.get_all_by_ids(file_ids).await?;
+
+        if files.len() != file_ids.iter().collect::<HashSet<_>>().len() {
+            return Err(DataError::EntityNotFoundException("File".to_string()));
+        }
+
+        let mut used_names = HashSet::new();
+        let mut entries = Vec::with_capacity(files.len());
+
+        for file in files {
+            self.access_control
+                .authorize_file(caller_id, &file, FileAccessLevel::Read)
+                .await?;
+
+            if file.upload_status != UploadStatus::Completed {
+                return Err(DataError::ValidationError(format!(
+                    "File {} has not finished uploading",
+                    file.name
+                )));
+            }
+
+            // Chosen files may come from different folders, so their names can collide
+            let name = unique_name(&file.name, &mut used_names);
+            entries.push(BundleEntry::File(name, file));
+        }
+
+        self.mark_accessed(&entries).await?;
+
+        let name = format!("files.{}", format.extension());
+
+        Ok((name, self.spawn_writer(entries, format)))
+    }
+}
+
+impl BundleServiceImpl {
+    async fn mark_accessed(&self, entries: &[BundleEntry]) -> Result<(), DataError> {
+        let file_ids: Vec<Uuid> = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                BundleEntry::File(_, file) => Some(file.id),
+                BundleEntry::Folder(_) => None,
+            })
+            .collect();
+
+        self.file_repo.mark_accessed(&file_ids).await
+    }
+
+    // The bundle is built while it is read, nothing is staged on disk
+    fn spawn_writer(&self, entries: Vec<BundleEntry>, format: BundleFormat) -> ObjectReader {
+        let (reader, writer) = tokio::io::duplex(PIPE_CAPACITY);
+        let storage = self.storage.clone();
+
+        let task = tokio::spawn(async move {
+            match format {
+                BundleFormat::Zip => write_zip(storage, entries, writer).await,
+                BundleFormat::TarGz => write_tar_gz(storage, entries, writer).await,
+            }
+        });
+
+        Box::new(BundleReader {
+            inner: reader,
+            task: Some(task),
+        })
+    }
+}
+
+async fn write_zip(
+    storage: Arc<dyn StorageBackend>,
+    entries: Vec<BundleEntry>,
+    writer: DuplexStream,
+) -> Result<(), DataError> {
+    let mut zip = ZipFileWriter::with_tokio(writer);
+
+    for entry in entries {
+        match entry {
+            BundleEntry::Folder(path) => {
+                let builder =
+                    ZipEntryBuilder::new(format!("{}/", path).into(), Compression::Stored);
+
+                zip.write_entry_whole(builder, &[])
+                    .await
+                    .map_err(|e| DataError::IOError(e.to_string()))?;
+            }
+            BundleEntry::File(path, file) => {
+                let content = storage.get_range(&file.storage_key(), None).await?;
+
+                let mut content = match file.archive_codec {
+                    Some(codec) => archive_codec::decoder(codec, content),
+                    None => content,
+                };
+
+                // Sizes are unknown to the writer up front, so entries are written with data descriptors
+                let builder = ZipEntryBuilder::new(path.into(), Compression::Stored);
+                let mut entry_writer = zip
+                    .write_entry_stream(builder)
+                    .await
+                    .map_err(|e| DataError::IOError(e.to_string()))?;
+
+                let mut buffer = vec![0u8; CHUNK_SIZE];
+
+                loop {
+                    let read = content
+                        .read(&mut buffer)
+                        .await
+                        .map_err(|e| DataError::IOError(e.to_string()))?;
+
+                    if read == 0 {
+                        break;
+                    }
+
+                    entry_writer
+                        .write_all(&buffer[..read])
+                        .await
+                        .map_err(|e| DataError::IOError(e.to_string()))?;
+                }
+
+                entry_writer
+                    .close()
+                    .await
+                    .map_err(|e| DataError::IOError(e.to_string()))?;
+            }
+        }
+    }
+
+    let mut writer = zip
+        .close()
+        .await
+        .map_err(|e| DataError::IOError(e.to_string()))?
+        .into_inner();
+
+    writer
+        .shutdown()
+        .await
+        .map_err(|e| DataError::IOError(e.to_string()))
+}
+
+async fn write_tar_gz(
+    storage: Arc<dyn StorageBackend>,
+    entries: Vec<BundleEntry>,
+    writer: DuplexStream,
+) -> Result<(), DataError> {
+    let mut tar = tokio_tar::Builder::new(GzipEncoder::new(writer));
+
+    for entry in entries {
+        let mut header = Header::new_gnu();
+
+        match entry {
+            BundleEntry::Folder(path) => {
+                header.set_entry_type(EntryType::Directory);
+                header.set_mode(0o755);
+                header.set_size(0);
+
+                tar.append_data(&mut header, path, tokio::io::empty())
+                    .await
+                    .map_err(|e| DataError::IOError(e.to_string()))?;
+            }
+            BundleEntry::File(path, file) => {
+                let content = storage.get_range(&file.storage_key(), None).await?;
+
+                // The header needs the size up front, files archived before it was kept go in as they are stored
+                let (path, content, size) = match (file.archive_codec, file.original_size) {
+                    (Some(codec), Some(original_size)) => (
+                        path,
+                        archive_codec::decoder(codec, content),
+                        original_size as u64,
+                    ),
+                    (Some(codec), None) => (
+                        format!("{}.{}", path, codec.extension()),
+                        content,
+                        file.size as u64,
+                    ),
+                    (None, _) => (path, content, file.size as u64),
+                };
+
+                header.set_entry_type(EntryType::Regular);
+                header.set_mode(0o644);
+                header.set_size(size);
+                header.set_mtime(file.updated_at.unix_timestamp().max(0) as u64);
+
+                // The header promises the size, content of another length would corrupt the rest
+                tar.append_data(&mut header, path, content.take(size))
+                    .await
+                    .map_err(|e| DataError::IOError(e.to_string()))?;
+            }
+        }
+    }
+
+    let mut encoder = tar
+        .into_inner()
+        .await
+        .map_err(|e| DataError::IOError(e.to_string()))?;
+
+    encoder
+        .shutdown()
+        .await
+        .map_err(|e| DataError::IOError(e.to_string()))
+}
+
+fn unique_name(name: &str, used_names: &mut HashSet<String>) -> String {
+    if used_names.insert(name.to_string()) {
+        return name.to_string();
+    }
+
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+
+    let mut counter = 1;
+
+    loop {
+        let candidate = format!("{} ({}){}", stem, counter, extension);
+
+        if used_names.insert(candidate.clone()) {
+            return candidate;
+        }
+
+        counter += 1;
+    }
+}
+
+// Hands out what the writer produces and reports its failure once the pipe runs dry,
+// so a broken bundle never looks like a complete one
+struct BundleReader {
+    inner: DuplexStream,
+    task: Option<JoinHandle<Result<(), DataError>>>,
+}
+
+impl AsyncRead for BundleReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+
+        if buf.filled().len() > filled {
+            return Poll::Ready(Ok(()));
+        }
+
+        let Some(task) = self.task.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+
+        let result = ready!(Pin::new(task).poll(cx));
+        self.task = None;
+
+        match result {
+            Ok(Ok(())) => Poll::Ready(Ok(())),
+            Ok(Err(e)) => Poll::Ready(Err(io::Error::other(e.to_string()))),
+            Err(e) => Poll::Ready(Err(io::Error::other(e))),
+        }
+    }
+}
+
+impl Drop for BundleReader {
+    // Nobody reads the rest anymore, so there is no point in building it
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::data::compression_summary::CompressionSummary;
+use crate::data::set_compression_policy_command::SetCompressionPolicyCommand;
+use crate::db::compression_policy_repository::CompressionPolicyRepository;
+use crate::db::file_repository::FileRepository;
+use crate::helpers::data_error::DataError;
+use crate::service::file_service::FileService;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::compression_policy::CompressionPolicy;
+use homelab_core::file::File;
+use sqlx::types::time::OffsetDateTime;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait CompressionPolicyService: Send + Sync {
+    // Without a user this is the global policy, with one it is whichever policy applies to them
+    async fn get_policy(
+        &self,
+        user_id: Option<Uuid>,
+    ) -> Result<Option<CompressionPolicy>, DataError>;
+    async fn set_policy(
+        &self,
+        user_id: Option<Uuid>,
+        command: SetCompressionPolicyCommand,
+    ) -> Result<CompressionPolicy, DataError>;
+    async fn delete_policy(&self, user_id: Option<Uuid>) -> Result<(), DataError>;
+    async fn apply_policies(&self, batch_size: i64) -> Result<CompressionSummary, DataError>;
+}
+
+#[derive(new)]
+pub struct CompressionPolicyServiceImpl {
+    policy_repo: Arc<dyn CompressionPolicyRepository>,
+    file_repo: Arc<dyn FileRepository>,
+    file_service: Arc<dyn FileService>,
+}
+
+#[async_trait]
+impl CompressionPolicyService for CompressionPolicyServiceImpl {
+    async fn get_policy(
+        &self,
+        user_id: Option<Uuid>,
+    ) -> Result<Option<CompressionPolicy>, DataError> {
+        match user_id {
+            Some(user_id) => self.policy_repo.get_effective(user_id).await,
+            None => self.policy_repo.get_by_user_id(None).await,
+        }
+    }
+
+    async fn set_policy(
+        &self,
+        user_id: Option<Uuid>,
+        command: SetCompressionPolicyCommand,
+    ) -> Result<CompressionPolicy, DataError> {
+        if command.min_idle_days < 1 {
+            return Err(DataError::ValidationError(
+                "Files have to be idle for at least one day".to_string(),
+            ));
+        }
+
+        if command.min_size < 0 {
+            return Err(DataError::ValidationError(
+                "Minimum size cannot be negative".to_string(),
+            ));
+        }
+
+        let mut policy = CompressionPolicy::new(
+            user_id,
+            command.min_idle_days,
+            command.min_size,
+            command.codec,
+            command.excluded_types,
+            command.is_enabled,
+        );
+
+        match self.policy_repo.get_by_user_id(user_id).await? {
+            Some(existing) => {
+                policy.id = existing.id;
+                self.policy_repo.update(policy).await
+            }
+            None => self.policy_repo.save(policy).await,
+        }
+    }
+
+    async fn delete_policy(&self, user_id: Option<Uuid>) -> Result<(), DataError> {
+        if !self.policy_repo.delete_by_user_id(user_id).await? {
+            return Err(DataError::EntityNotFoundException(
+                "Compression policy".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn apply_policies(&self, batch_size: i64) -> Result<CompressionSummary, DataError> {
+        let policies = self.policy_repo.get_all_enabled().await?;
+        let now = OffsetDateTime::now_utc();
+
+        let mut summary = CompressionSummary::default();
+
+        for policy in policies {
+            let files = self
+                .file_repo
+                .get_compression_candidates(&policy, policy.idle_since(now), batch_size)
+                .await?;
+
+            for file in files {
+                match self.compress(&policy, &file).await {
+                    Ok(()) => summary.compressed += 1,
+                    Err(DataError::ValidationError(_)) => summary.skipped += 1,
+                    Err(e) => {
+                        tracing::error!("Failed to compress file {}: {:?}", file.id, e);
+                        summary.failed += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+impl CompressionPolicyServiceImpl {
+    // Runs as the owner, so the saved bytes are credited to them like with a manual archive.
+    // A file that could not be compressed is left alone until its content changes
+    async fn compress(&self, policy: &CompressionPolicy, file: &File) -> Result<(), DataError> {
+        let result = self
+            .file_service
+            .archive_file(file.id, file.owner_id, policy.codec)
+            .await;
+
+        if result.is_err() {
+            self.file_repo.mark_compression_attempted(file.id).await?;
+        }
+
+        result
+    }
+}
@@ -4,6 +4,7 @@ use crate::db::file_repository::FileRepository;
 use crate::db::label_repository::LabelRepository;
 use crate::db::storage_profile_repository::StorageProfileRepository;
 use crate::helpers::data_error::DataError;
+use crate::service::access_control_service::{AccessControlService, FileAccessLevel};
 use async_trait::async_trait;
 use derive_new::new;
 use homelab_core::file::File;
@@ -17,6 +18,7 @@ pub trait FileLabelService: Send + Sync {
     async fn create_file_label(
         &self,
         command: CreateFileLabelCommand,
+        caller_id: Uuid,
     ) -> Result<FileLabel, DataError>;
     async fn get_files_by_label(
         &self,
@@ -36,6 +38,7 @@ pub struct FileLabelServiceImpl {
     file_repo: Arc<dyn FileRepository>,
     file_label_repo: Arc<dyn FileLabelRepository>,
     storage_profile_repo: Arc<dyn StorageProfileRepository>,
+    access_control: Arc<dyn AccessControlService>,
 }
 
 #[async_trait]
@@ -43,8 +46,27 @@ impl FileLabelService for FileLabelServiceImpl {
     async fn create_file_label(
         &self,
         command: CreateFileLabelCommand,
+        caller_id: Uuid,
     ) -> Result<FileLabel, DataError> {
-        let fl = FileLabel::new(command.file_id, command.label_id);
+        let label = self
+            .label_repo
+            .get_by_id(command.label_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Label".to_string()))?;
+
+        self.access_control.authorize_label(caller_id, &label)?;
+
+        let file = self
+            .file_repo
+            .get_by_id(command.file_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+
+        self.access_control
+            .authorize_file(caller_id, &file, FileAccessLevel::Read)
+            .await?;
+
+        let fl = FileLabel::new(file.id, label.id);
 
         Ok(self.file_label_repo.create(fl).await?)
     }
@@ -60,6 +82,8 @@ impl FileLabelService for FileLabelServiceImpl {
             .await?
             .ok_or_else(|| DataError::EntityNotFoundException("Label".to_string()))?;
 
+        self.access_control.authorize_label(owner_id, &label)?;
+
         let sp = self
             .storage_profile_repo
             .get_by_id(owner_id)
@@ -83,6 +107,10 @@ impl FileLabelService for FileLabelServiceImpl {
             .await?
             .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
 
+        self.access_control
+            .authorize_file(owner_id, &file, FileAccessLevel::Read)
+            .await?;
+
         let sp = self
             .storage_profile_repo
             .get_by_id(owner_id)
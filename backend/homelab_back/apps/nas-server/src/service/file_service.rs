@@ -1,68 +1,151 @@
 use crate::data::copy_file_command::CopyFileCommand;
 use crate::data::init_file_command::InitFileCommand;
 use crate::data::move_file_command::MoveFileCommand;
+use crate::data::preview::Preview;
+use crate::data::update_file_content_command::UpdateFileContentCommand;
 use crate::data::update_file_name_command::UpdateFileNameCommand;
+use crate::data::version_retention_policy::VersionRetentionPolicy;
+use crate::db::blob_repository::BlobRepository;
 use crate::db::file_repository::FileRepository;
+use crate::db::file_version_repository::FileVersionRepository;
 use crate::db::folder_repository::FolderRepository;
 use crate::db::global_file_repository::GlobalFileRepository;
 use crate::db::storage_profile_repository::StorageProfileRepository;
+use crate::db::upload_session_repository::UploadSessionRepository;
 use crate::events::rabbitmq::RabbitMqPublisher;
+use crate::helpers::archive_codec;
+use crate::helpers::checksum::{checksum_file, checksum_reader, encode_digest, parse_checksum};
 use crate::helpers::data_error::DataError;
-use crate::service::preview_service::{PreviewService, PreviewServiceImpl};
-use async_compression::tokio::write::{GzipDecoder, GzipEncoder};
+use crate::service::access_control_service::{AccessControlService, FileAccessLevel};
+use crate::service::preview_service::PreviewService;
+use crate::storage::storage_backend::{ObjectReader, StorageBackend};
 use async_trait::async_trait;
 use derive_new::new;
 use futures::stream::{self, StreamExt};
+use homelab_core::blob::Blob;
 use homelab_core::constants::MB;
 use homelab_core::events::{FileUpdatedEvent, FileUploadedEvent, UserUpdatedEvent};
-use homelab_core::file::{File, FileType, UploadStatus};
+use homelab_core::file::{build_preview_key, ArchiveCodec, File, PreviewStatus, UploadStatus};
+use homelab_core::file_version::FileVersion;
 use homelab_core::folder::Folder;
 use homelab_core::global_file::GlobalFile;
 use homelab_core::storage_profile::StorageProfile;
+use homelab_core::upload_session::UploadSession;
+use sha2::{Digest, Sha256};
 use sqlx::types::time::OffsetDateTime;
 use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::fs;
-use tokio::io::{AsyncWriteExt, BufReader, BufWriter};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufWriter};
 use tokio::sync::mpsc::Receiver;
 use uuid::Uuid;
 
 #[async_trait]
 pub trait FileService: Send + Sync {
-    async fn get_by_id(&self, file_id: Uuid) -> Result<Option<File>, DataError>;
+    async fn get_by_id(&self, file_id: Uuid, caller_id: Uuid) -> Result<Option<File>, DataError>;
     async fn get_all_deleted_files(&self, user_id: Uuid) -> Result<Vec<File>, DataError>;
-    async fn search_file(&self, search_query: String) -> Result<Vec<File>, DataError>;
-    async fn upload(&self, command: InitFileCommand) -> Result<File, DataError>;
+    async fn search_file(
+        &self,
+        search_query: String,
+        caller_id: Uuid,
+    ) -> Result<Vec<File>, DataError>;
+    async fn upload(&self, command: InitFileCommand, owner_id: Uuid) -> Result<File, DataError>;
     async fn upload_stream(
         &self,
         file_id: Uuid,
+        caller_id: Uuid,
         rx: Receiver<Result<Vec<u8>, DataError>>,
     ) -> Result<(), DataError>;
+    async fn get_upload_session(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<UploadSession, DataError>;
+    async fn upload_chunk(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+        offset: i64,
+        rx: Receiver<Result<Vec<u8>, DataError>>,
+    ) -> Result<UploadSession, DataError>;
     async fn update_file_name(
         &self,
         command: UpdateFileNameCommand,
         id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<File, DataError>;
+    async fn update_deleted_file(&self, id: Uuid, caller_id: Uuid) -> Result<File, DataError>;
+    async fn delete_chosen_files(
+        &self,
+        file_ids: &[Uuid],
+        caller_id: Uuid,
+    ) -> Result<(), DataError>;
+    async fn delete(&self, file_id: Uuid, caller_id: Uuid) -> Result<(), DataError>;
+    async fn move_file(
+        &self,
+        command: MoveFileCommand,
+        caller_id: Uuid,
+    ) -> Result<File, DataError>;
+    async fn copy_file(
+        &self,
+        command: CopyFileCommand,
+        caller_id: Uuid,
     ) -> Result<File, DataError>;
-    async fn update_deleted_file(&self, id: Uuid) -> Result<File, DataError>;
-    async fn delete_chosen_files(&self, file_ids: &[Uuid]) -> Result<(), DataError>;
-    async fn delete(&self, file_id: Uuid) -> Result<(), DataError>;
-    async fn move_file(&self, command: MoveFileCommand) -> Result<File, DataError>;
-    async fn copy_file(&self, command: CopyFileCommand) -> Result<File, DataError>;
     async fn update_stream(
         &self,
         file_id: Uuid,
+        caller_id: Uuid,
+        command: UpdateFileContentCommand,
         rx: Receiver<Result<Vec<u8>, DataError>>,
+    ) -> Result<File, DataError>;
+    async fn get_file_for_streaming(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+        range: Option<Range<u64>>,
+    ) -> Result<(File, ObjectReader), DataError>;
+    async fn get_preview(&self, file_id: Uuid, caller_id: Uuid) -> Result<Preview, DataError>;
+    async fn archive_file(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+        codec: ArchiveCodec,
     ) -> Result<(), DataError>;
-    async fn get_file_for_streaming(&self, file_id: Uuid) -> Result<PathBuf, DataError>;
-    async fn archive_file(&self, file_id: Uuid) -> Result<(), DataError>;
-    async fn unarchive_file(&self, file_id: Uuid) -> Result<(), DataError>;
+    async fn unarchive_file(&self, file_id: Uuid, caller_id: Uuid) -> Result<(), DataError>;
     async fn cleanup_deleted_files(&self, user_id: Uuid) -> Result<(), DataError>;
     async fn cleanup_expired_files(&self) -> Result<(), DataError>;
-    async fn remove_deleted_file(&self, file_id: Uuid) -> Result<(), DataError>;
+    async fn expire_stale_uploads(&self, max_age: Duration) -> Result<(), DataError>;
+    async fn remove_deleted_file(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<(), DataError>;
+    async fn list_file_versions(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<Vec<FileVersion>, DataError>;
+    async fn restore_file_version(
+        &self,
+        file_id: Uuid,
+        version_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<File, DataError>;
+    async fn delete_file_version(
+        &self,
+        file_id: Uuid,
+        version_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<(), DataError>;
+    async fn prune_file_versions(&self, policy: VersionRetentionPolicy) -> Result<u64, DataError>;
 }
 
 #[derive(new)]
+#[allow(clippy::too_many_arguments)]
 pub struct FileServiceImpl {
     file_repo: Arc<dyn FileRepository>,
     folder_repo: Arc<dyn FolderRepository>,
@@ -70,31 +153,54 @@ pub struct FileServiceImpl {
     storage_path: PathBuf,
     global_file_repo: Arc<dyn GlobalFileRepository>,
     publisher: Arc<RabbitMqPublisher>,
+    access_control: Arc<dyn AccessControlService>,
+    upload_session_repo: Arc<dyn UploadSessionRepository>,
+    blob_repo: Arc<dyn BlobRepository>,
+    storage: Arc<dyn StorageBackend>,
+    file_version_repo: Arc<dyn FileVersionRepository>,
+    preview_service: Arc<dyn PreviewService>,
 }
 
 #[async_trait]
 impl FileService for FileServiceImpl {
-    async fn get_by_id(&self, file_id: Uuid) -> Result<Option<File>, DataError> {
-        self.file_repo.get_by_id(file_id).await
+    async fn get_by_id(&self, file_id: Uuid, caller_id: Uuid) -> Result<Option<File>, DataError> {
+        let file = match self.file_repo.get_by_id(file_id).await? {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+
+        self.access_control
+            .authorize_file(caller_id, &file, FileAccessLevel::Read)
+            .await?;
+
+        Ok(Some(file))
     }
 
     async fn get_all_deleted_files(&self, user_id: Uuid) -> Result<Vec<File>, DataError> {
         self.file_repo.get_all_deleted(user_id).await
     }
 
-    async fn search_file(&self, search_query: String) -> Result<Vec<File>, DataError> {
+    async fn search_file(
+        &self,
+        search_query: String,
+        caller_id: Uuid,
+    ) -> Result<Vec<File>, DataError> {
         self.file_repo
-            .search_by_name(format!("%{}%", search_query))
+            .search_by_name(format!("%{}%", search_query), caller_id)
             .await
     }
 
-    async fn upload(&self, command: InitFileCommand) -> Result<File, DataError> {
+    async fn upload(&self, command: InitFileCommand, owner_id: Uuid) -> Result<File, DataError> {
         let folder: Folder = self
             .folder_repo
             .get_by_id(command.destination)
             .await?
             .ok_or_else(|| DataError::EntityNotFoundException("Folder".to_string()))?;
 
+        self.access_control
+            .authorize_folder(owner_id, &folder, FileAccessLevel::Owner)
+            .await?;
+
         if let Some(_) = self
             .file_repo
             .get_by_folder_and_file_name(folder.id, command.name.clone())
@@ -103,14 +209,20 @@ impl FileService for FileServiceImpl {
             return Err(DataError::FileAlreadyExistsError);
         }
 
+        let checksum = command
+            .checksum
+            .as_deref()
+            .map(parse_checksum)
+            .transpose()?;
+
         let sp: StorageProfile = self
             .storage_profile_repo
-            .get_by_id(command.owner_id)
+            .get_by_id(owner_id)
             .await?
             .ok_or_else(|| DataError::EntityNotFoundException("User".to_string()))?;
 
         if sp.validate_storage_size(command.expected_size) {
-            let f = File::new(
+            let mut f = File::new(
                 Uuid::new_v4(),
                 command.name,
                 sp.user_id,
@@ -121,6 +233,13 @@ impl FileService for FileServiceImpl {
                 OffsetDateTime::now_utc(),
             );
 
+            if let Some(checksum) = checksum {
+                f.update_checksum(checksum);
+            }
+
+            // Reserves the expected size in the same transaction, so parallel uploads cannot overshoot the quota
+            let f = self.file_repo.save_with_reservation(f).await?;
+
             if command.is_global {
                 let original = f.id.clone();
                 let global_file = GlobalFile::new(Uuid::new_v4(), original);
@@ -142,20 +261,7 @@ impl FileService for FileServiceImpl {
                 eprintln!("Failed to publish event: {:?}", e);
             }
 
-            let sp_event: UserUpdatedEvent = UserUpdatedEvent::new(
-                sp.user_id.clone(),
-                None,
-                None,
-                Some(sp.allowed_storage.clone()),
-                Some(sp.taken_storage.clone()),
-                sp.is_blocked.clone(),
-            );
-
-            if let Err(e) = self.publisher.publish(&sp_event).await {
-                eprintln!("Failed to publish event: {:?}", e);
-            }
-
-            self.file_repo.save(f).await
+            Ok(f)
         } else {
             Err(DataError::NoFreeStorageError)
         }
@@ -164,13 +270,12 @@ impl FileService for FileServiceImpl {
     async fn upload_stream(
         &self,
         file_id: Uuid,
+        caller_id: Uuid,
         mut rx: Receiver<Result<Vec<u8>, DataError>>,
     ) -> Result<(), DataError> {
         let mut f = self
-            .file_repo
-            .get_by_id(file_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Owner)
+            .await?;
 
         if f.upload_status != UploadStatus::Pending {
             return Err(DataError::ValidationError(
@@ -178,10 +283,10 @@ impl FileService for FileServiceImpl {
             ));
         }
 
-        let file_path = f.build_file_path(&self.storage_path);
+        let file_path = f.build_upload_path(&self.storage_path);
 
         // Make sure that parent directories exist.
-        // Storing a bunch of files without parent directories that come build_file_path would be an unoptimized
+        // Storing a bunch of files without parent directories that come build_upload_path would be an unoptimized
         if let Some(parent) = file_path.parent() {
             tokio::fs::create_dir_all(parent)
                 .await
@@ -194,73 +299,197 @@ impl FileService for FileServiceImpl {
 
         let mut writer = BufWriter::with_capacity(MB as usize, file_handle);
         let mut total_bytes = 0i64;
+        let mut hasher = Sha256::new();
 
+        // Whatever breaks the stream leaves nothing worth keeping, the upload fails and its space is given back
         while let Some(chunk_result) = rx.recv().await {
-            let data = chunk_result?;
+            let data = match chunk_result {
+                Ok(data) => data,
+                Err(e) => {
+                    let _ = tokio::fs::remove_file(&file_path).await;
+                    self.file_repo.release_reservation(f).await?;
+                    return Err(e);
+                }
+            };
 
             total_bytes += data.len() as i64;
+            hasher.update(&data);
 
             if let Err(e) = writer.write_all(&data).await {
                 let _ = tokio::fs::remove_file(&file_path).await;
+                self.file_repo.release_reservation(f).await?;
                 return Err(DataError::IOError(e.to_string()));
             }
         }
 
         if let Err(e) = writer.flush().await {
             let _ = tokio::fs::remove_file(&file_path).await;
+            self.file_repo.release_reservation(f).await?;
             return Err(DataError::IOError(e.to_string()));
         }
 
         // If the file size is not the same as provided during initialization,
         // we need to remove file from the disk since the file was probably corrupted
         if !f.validate_size(total_bytes) {
-            f.update_status(UploadStatus::Failed);
-            self.file_repo.update(f).await?;
+            self.file_repo.release_reservation(f).await?;
             let _ = tokio::fs::remove_file(&file_path).await;
             return Err(DataError::NotMatchingByteSizeError);
         }
 
-        f.update_status(UploadStatus::Completed);
-        self.file_repo.update(f.clone()).await?;
+        self.verify_checksum(&mut f, encode_digest(hasher), &file_path)
+            .await?;
 
-        let mut sp: StorageProfile = self
-            .storage_profile_repo
-            .get_by_id(f.owner_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("User".to_string()))?;
+        self.complete_upload(f, &file_path).await
+    }
 
-        sp.increase_storage_size(total_bytes);
+    async fn get_upload_session(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<UploadSession, DataError> {
+        let f = self
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Owner)
+            .await?;
 
-        self.storage_profile_repo.save(sp).await?;
+        if f.upload_status != UploadStatus::Pending {
+            return Err(DataError::ValidationError(
+                "File is not pending".to_string(),
+            ));
+        }
 
-        let event: FileUpdatedEvent = FileUpdatedEvent::new(
-            f.id.clone(),
-            f.is_deleted.clone(),
-            f.ttl.clone(),
-            f.size.clone(),
-            f.upload_status.clone(),
-        );
+        self.get_or_create_upload_session(&f).await
+    }
 
-        if let Err(e) = self.publisher.publish(&event).await {
-            eprintln!("Failed to publish event: {:?}", e);
+    async fn upload_chunk(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+        offset: i64,
+        mut rx: Receiver<Result<Vec<u8>, DataError>>,
+    ) -> Result<UploadSession, DataError> {
+        let mut f = self
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Owner)
+            .await?;
+
+        if f.upload_status != UploadStatus::Pending {
+            return Err(DataError::ValidationError(
+                "File is not pending".to_string(),
+            ));
         }
 
-        // After the file has been uploaded we need to create a preview of this file
-        PreviewServiceImpl::spawn_generation(f, self.storage_path.clone());
+        let session = self.get_or_create_upload_session(&f).await?;
 
-        Ok(())
+        if offset != session.committed_offset {
+            return Err(DataError::UploadOffsetMismatchError(
+                offset,
+                session.committed_offset,
+            ));
+        }
+
+        let partial_path = f.build_partial_file_path(&self.storage_path);
+
+        if let Some(parent) = partial_path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| DataError::IOError(format!("Failed to create buckets: {}", e)))?;
+        }
+
+        let mut file_handle = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&partial_path)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        // Anything past the committed offset was left by an interrupted chunk and cannot be trusted
+        file_handle
+            .set_len(offset as u64)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+        file_handle
+            .seek(SeekFrom::Start(offset as u64))
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        let mut writer = BufWriter::with_capacity(MB as usize, file_handle);
+        let mut written = 0i64;
+        let mut stream_error = None;
+
+        while let Some(chunk_result) = rx.recv().await {
+            let data = match chunk_result {
+                Ok(data) => data,
+                Err(e) => {
+                    stream_error = Some(e);
+                    break;
+                }
+            };
+
+            if written + data.len() as i64 > session.remaining() {
+                return Err(DataError::ValidationError(
+                    "Chunk goes past the declared file size".to_string(),
+                ));
+            }
+
+            writer
+                .write_all(&data)
+                .await
+                .map_err(|e| DataError::IOError(e.to_string()))?;
+
+            written += data.len() as i64;
+        }
+
+        writer
+            .flush()
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+        writer
+            .get_ref()
+            .sync_data()
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        // Everything that reached the disk is committed, so a dropped connection resumes from here
+        let session = match self
+            .upload_session_repo
+            .advance_offset(f.id, offset, offset + written)
+            .await?
+        {
+            Some(session) => session,
+            None => {
+                let current = self.get_or_create_upload_session(&f).await?;
+                return Err(DataError::UploadOffsetMismatchError(
+                    offset,
+                    current.committed_offset,
+                ));
+            }
+        };
+
+        if let Some(e) = stream_error {
+            return Err(e);
+        }
+
+        if session.is_complete() {
+            // Chunks may have arrived over several connections, so the digest is taken from disk
+            let checksum = checksum_file(&partial_path).await?;
+
+            self.verify_checksum(&mut f, checksum, &partial_path).await?;
+
+            self.complete_upload(f, &partial_path).await?;
+        }
+
+        Ok(session)
     }
 
     async fn update_file_name(
         &self,
         command: UpdateFileNameCommand,
         file_id: Uuid,
+        caller_id: Uuid,
     ) -> Result<File, DataError> {
         let mut file: File = self
-            .file_repo
-            .get_by_id(file_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Edit)
+            .await?;
 
         if file.is_deleted {
             return Err(DataError::ValidationError(
@@ -273,7 +502,7 @@ impl FileService for FileServiceImpl {
         self.file_repo.update(file).await
     }
 
-    async fn update_deleted_file(&self, id: Uuid) -> Result<File, DataError> {
+    async fn update_deleted_file(&self, id: Uuid, caller_id: Uuid) -> Result<File, DataError> {
         println!("Trying to restore a file: {}", id.to_string());
         let mut file = self
             .file_repo
@@ -281,6 +510,10 @@ impl FileService for FileServiceImpl {
             .await?
             .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
 
+        self.access_control
+            .authorize_file(caller_id, &file, FileAccessLevel::Owner)
+            .await?;
+
         file.set_as_undeleted();
 
         let event: FileUpdatedEvent = FileUpdatedEvent::new(
@@ -298,9 +531,20 @@ impl FileService for FileServiceImpl {
         self.file_repo.update(file).await
     }
 
-    async fn delete_chosen_files(&self, file_ids: &[Uuid]) -> Result<(), DataError> {
+    async fn delete_chosen_files(
+        &self,
+        file_ids: &[Uuid],
+        caller_id: Uuid,
+    ) -> Result<(), DataError> {
         let files = self.file_repo.get_all_by_ids(file_ids).await?;
 
+        // Check the whole selection first so that nothing is deleted when one of the files is not permitted
+        for file in &files {
+            self.access_control
+                .authorize_file(caller_id, file, FileAccessLevel::Edit)
+                .await?;
+        }
+
         for mut file in files {
             file.set_as_deleted();
             self.file_repo.update(file).await.map_err(|e| e)?;
@@ -309,12 +553,10 @@ impl FileService for FileServiceImpl {
         Ok(())
     }
 
-    async fn delete(&self, file_id: Uuid) -> Result<(), DataError> {
+    async fn delete(&self, file_id: Uuid, caller_id: Uuid) -> Result<(), DataError> {
         let mut file = self
-            .file_repo
-            .get_by_id(file_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Edit)
+            .await?;
 
         file.set_as_deleted();
 
@@ -335,12 +577,14 @@ impl FileService for FileServiceImpl {
         Ok(())
     }
 
-    async fn move_file(&self, command: MoveFileCommand) -> Result<File, DataError> {
+    async fn move_file(
+        &self,
+        command: MoveFileCommand,
+        caller_id: Uuid,
+    ) -> Result<File, DataError> {
         let mut file = self
-            .file_repo
-            .get_by_id(command.file_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+            .get_authorized_file(command.file_id, caller_id, FileAccessLevel::Edit)
+            .await?;
 
         if file.is_deleted {
             return Err(DataError::ValidationError(
@@ -348,27 +592,47 @@ impl FileService for FileServiceImpl {
             ));
         }
 
-        file.update_parent_folder(command.folder_id);
+        let folder = self
+            .folder_repo
+            .get_by_id(command.folder_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Folder".to_string()))?;
+
+        // Shared editors can reorganise a file, but it always stays inside its owner's tree
+        if folder.owner_id != file.owner_id {
+            return Err(DataError::PermissionDeniedError(format!(
+                "folder {}",
+                folder.id
+            )));
+        }
+
+        self.access_control
+            .authorize_folder(caller_id, &folder, FileAccessLevel::Edit)
+            .await?;
+
+        file.update_parent_folder(folder.id);
 
         Ok(self.file_repo.update(file).await?)
     }
 
-    async fn copy_file(&self, command: CopyFileCommand) -> Result<File, DataError> {
+    async fn copy_file(
+        &self,
+        command: CopyFileCommand,
+        caller_id: Uuid,
+    ) -> Result<File, DataError> {
         let file = self
-            .file_repo
-            .get_by_id(command.file_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+            .get_authorized_file(command.file_id, caller_id, FileAccessLevel::Read)
+            .await?;
 
-        let mut sp = self
+        self.get_authorized_folder(command.target_folder_id, caller_id, FileAccessLevel::Owner)
+            .await?;
+
+        // The copy belongs to whoever made it, so it is charged to the caller's storage up front
+        let sp = self
             .storage_profile_repo
-            .get_by_id(file.owner_id)
+            .try_charge(caller_id, file.size)
             .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("User".to_string()))?;
-
-        if !sp.validate_storage_size(file.size) {
-            return Err(DataError::NoFreeStorageError);
-        }
+            .ok_or(DataError::NoFreeStorageError)?;
 
         let new_name = if file.parent_folder_id == command.target_folder_id {
             let path = Path::new(&file.name);
@@ -400,46 +664,34 @@ impl FileService for FileServiceImpl {
         );
 
         new_file.upload_status = UploadStatus::Completed;
+        new_file.archive_codec = file.archive_codec;
+        new_file.original_size = file.original_size;
+        new_file.preview_status = file.preview_status;
 
-        let source_path = file.build_file_path(&self.storage_path);
-        let dest_path = new_file.build_file_path(&self.storage_path);
-
-        if let Some(parent) = dest_path.parent() {
-            tokio::fs::create_dir_all(parent)
-                .await
-                .map_err(|e| DataError::IOError(format!("Failed to create buckets: {}", e)))?;
-        }
-
-        if let Err(e) = tokio::fs::copy(&source_path, &dest_path).await {
-            return Err(DataError::IOError(e.to_string()));
-        }
-
-        let sp_event: UserUpdatedEvent = UserUpdatedEvent::new(
-            sp.user_id.clone(),
-            None,
-            None,
-            Some(sp.allowed_storage.clone()),
-            Some(sp.taken_storage.clone()),
-            sp.is_blocked.clone(),
-        );
+        // The copy only takes another reference on the source's blob, no bytes are duplicated
+        let blob = match self.share_blob(file).await {
+            Ok(blob) => blob,
+            Err(err) => {
+                self.storage_profile_repo
+                    .release(sp.user_id, new_file.size)
+                    .await?;
+                return Err(err);
+            }
+        };
 
-        if let Err(e) = self.publisher.publish(&sp_event).await {
-            eprintln!("Failed to publish event: {:?}", e);
-        }
+        new_file.update_checksum(blob.checksum);
+        new_file.update_blob(blob.id);
 
         match self.file_repo.save(new_file.clone()).await {
             Ok(uploaded_file) => {
-                sp.increase_storage_size(new_file.size);
-                self.storage_profile_repo.save(sp).await?;
+                self.publish_storage_profile(sp).await;
                 Ok(uploaded_file)
             }
             Err(err) => {
-                if let Err(del_err) = tokio::fs::remove_file(&dest_path).await {
-                    return Err(DataError::IOError(format!(
-                        "Failed to delete ghost file. Needs immediate attention: {}",
-                        del_err.to_string()
-                    )));
-                }
+                self.storage_profile_repo
+                    .release(sp.user_id, new_file.size)
+                    .await?;
+                self.release_blob(blob.id).await?;
 
                 Err(err)
             }
@@ -449,27 +701,51 @@ impl FileService for FileServiceImpl {
     async fn update_stream(
         &self,
         file_id: Uuid,
+        caller_id: Uuid,
+        command: UpdateFileContentCommand,
         mut rx: Receiver<Result<Vec<u8>, DataError>>,
-    ) -> Result<(), DataError> {
+    ) -> Result<File, DataError> {
+        if !command.has_expectation() {
+            return Err(DataError::ValidationError(
+                "Either the expected updated_at or checksum is required".to_string(),
+            ));
+        }
+
         let mut f = self
-            .file_repo
-            .get_by_id(file_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Edit)
+            .await?;
 
         if f.is_deleted {
             return Err(DataError::ValidationError(
-                "Cannot rename a deleted file".to_string(),
+                "Cannot update a deleted file".to_string(),
+            ));
+        }
+
+        if f.upload_status != UploadStatus::Completed {
+            return Err(DataError::ValidationError(
+                "Cannot update a file that is still being uploaded".to_string(),
             ));
         }
 
-        let target_path = f.build_file_path(&self.storage_path);
+        // The current content would become a version that nobody knows is compressed
+        if f.is_archived() {
+            return Err(DataError::ValidationError(
+                "Cannot update an archived file, unarchive it first".to_string(),
+            ));
+        }
 
-        let parent = target_path
-            .parent()
-            .ok_or_else(|| DataError::UnknownError("Invalid file path structure".to_string()))?;
+        // Checked up front so a stale client does not send the whole content for nothing,
+        // a change while the content is streamed in is caught when the row is switched over
+        if !command.is_met_by(&f) {
+            return Err(DataError::ContentConflictError);
+        }
+
+        // The current content becomes a version, which needs a blob of its own to point at
+        if f.blob_id.is_none() {
+            f = self.adopt_into_blob(f).await?;
+        }
 
-        let temp_path = parent.join(format!("{}.tmp", f.id));
+        let temp_path = self.prepare_temp_path(&f).await?;
 
         let file_handle = fs::File::create(&temp_path)
             .await
@@ -477,10 +753,19 @@ impl FileService for FileServiceImpl {
 
         let mut writer = BufWriter::with_capacity(MB as usize, file_handle);
         let mut new_total_bytes = 0i64;
+        let mut hasher = Sha256::new();
 
         while let Some(chunk_result) = rx.recv().await {
-            let data = chunk_result?;
+            // An interrupted stream must not replace the content with what arrived so far
+            let data = match chunk_result {
+                Ok(data) => data,
+                Err(e) => {
+                    let _ = tokio::fs::remove_file(&temp_path).await;
+                    return Err(e);
+                }
+            };
             new_total_bytes += data.len() as i64;
+            hasher.update(&data);
 
             if let Err(e) = writer.write_all(&data).await {
                 let _ = tokio::fs::remove_file(&temp_path).await;
@@ -498,38 +783,69 @@ impl FileService for FileServiceImpl {
             return Err(DataError::IOError(e.to_string()));
         }
 
-        let old_size = f.size;
-        let size_diff = new_total_bytes - old_size;
-
-        if size_diff > 0 {
-            let sp = self
-                .storage_profile_repo
-                .get_by_id(f.owner_id)
-                .await?
-                .ok_or_else(|| DataError::EntityNotFoundException("User".to_string()))?;
-
-            if !sp.validate_storage_size(size_diff) {
+        // The previous content stays charged as a version, so the new content is charged in full
+        let sp = match self
+            .storage_profile_repo
+            .try_charge(f.owner_id, new_total_bytes)
+            .await?
+        {
+            Some(sp) => sp,
+            None => {
                 let _ = tokio::fs::remove_file(&temp_path).await;
                 return Err(DataError::NoFreeStorageError);
             }
+        };
 
-            let mut sp: StorageProfile = self
-                .storage_profile_repo
-                .get_by_id(f.owner_id)
-                .await?
-                .ok_or_else(|| DataError::EntityNotFoundException("User".to_string()))?;
+        // The current blob may be shared with other files, so the new content is never written over it
+        let blob = self
+            .store_blob(&temp_path, &encode_digest(hasher), new_total_bytes)
+            .await;
 
-            sp.increase_storage_size(size_diff);
+        let _ = tokio::fs::remove_file(&temp_path).await;
 
-            self.storage_profile_repo.save(sp).await?;
-        }
+        let blob = match blob {
+            Ok(blob) => blob,
+            Err(e) => {
+                self.storage_profile_repo
+                    .release(f.owner_id, new_total_bytes)
+                    .await?;
 
-        if let Err(e) = tokio::fs::rename(&temp_path, &target_path).await {
-            let _ = tokio::fs::remove_file(&temp_path).await;
-            return Err(DataError::IOError(e.to_string()));
-        }
+                return Err(e);
+            }
+        };
+
+        let owner_id = f.owner_id;
+        let previous_blob_id = f
+            .blob_id
+            .ok_or_else(|| DataError::EntityNotFoundException("Blob".to_string()))?;
+
+        // The reference on the previous blob is handed over to the version
+        let version = FileVersion::new(
+            f.id,
+            previous_blob_id,
+            f.size,
+            f.checksum.clone().unwrap_or_default(),
+        );
 
         f.update_size(new_total_bytes);
+        f.update_checksum(blob.checksum.clone());
+        f.update_blob(blob.id);
+
+        let f = match self.file_version_repo.replace_content(f, version).await {
+            Ok(f) => f,
+            Err(e) => {
+                self.release_blob(blob.id).await?;
+                self.storage_profile_repo
+                    .release(owner_id, new_total_bytes)
+                    .await?;
+
+                return Err(e);
+            }
+        };
+
+        self.refresh_preview(f.clone(), &blob).await?;
+
+        self.publish_storage_profile(sp).await;
 
         let event: FileUpdatedEvent = FileUpdatedEvent::new(
             f.id.clone(),
@@ -543,55 +859,139 @@ impl FileService for FileServiceImpl {
             eprintln!("Failed to publish event: {:?}", e);
         }
 
-        self.file_repo.update(f).await?;
-
-        Ok(())
+        Ok(f)
     }
 
-    async fn get_file_for_streaming(&self, file_id: Uuid) -> Result<PathBuf, DataError> {
+    async fn get_file_for_streaming(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+        range: Option<Range<u64>>,
+    ) -> Result<(File, ObjectReader), DataError> {
         let file = self
-            .file_repo
-            .get_by_id(file_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Read)
+            .await?;
 
-        let file_path = file.build_file_path(&self.storage_path);
+        // Cold storage compression leaves alone whatever is still being read
+        self.file_repo.mark_accessed(&[file.id]).await?;
 
-        if !file_path.exists() {
-            return Err(DataError::IOError(
-                "File metadata exists but disk file is missing".to_string(),
-            ));
+        let storage_key = file.storage_key();
+
+        // Archived content is unpacked on the way out, offsets only exist in the unpacked content
+        if let Some(codec) = file.archive_codec {
+            let reader = self.storage.get_range(&storage_key, None).await?;
+            let mut reader = archive_codec::decoder(codec, reader);
+
+            let reader: ObjectReader = match range {
+                Some(range) => {
+                    tokio::io::copy(&mut (&mut reader).take(range.start), &mut tokio::io::sink())
+                        .await
+                        .map_err(|e| DataError::IOError(e.to_string()))?;
+
+                    Box::new(reader.take(range.end.saturating_sub(range.start)))
+                }
+                None => reader,
+            };
+
+            return Ok((file, reader));
         }
 
-        Ok(file_path)
+        let object = self.storage.stat(&storage_key).await?.ok_or_else(|| {
+            DataError::IOError("File metadata exists but stored content is missing".to_string())
+        })?;
+
+        // A range reaching past the end is cut short, one starting past it cannot be served at all
+        let range = match range {
+            Some(range) if range.start > object.size => {
+                return Err(DataError::ValidationError(
+                    "Requested range starts beyond the end of the file".to_string(),
+                ));
+            }
+            Some(range) => Some(range.start..range.end.min(object.size)),
+            None => None,
+        };
+
+        let reader: ObjectReader = match range {
+            Some(range) if range.is_empty() => Box::new(tokio::io::empty()),
+            range => self.storage.get_range(&storage_key, range).await?,
+        };
+
+        // The content is named after the blob, so callers need the file for its name
+        Ok((file, reader))
     }
 
-    async fn archive_file(&self, file_id: Uuid) -> Result<(), DataError> {
+    async fn get_preview(&self, file_id: Uuid, caller_id: Uuid) -> Result<Preview, DataError> {
         let mut file = self
-            .file_repo
-            .get_by_id(file_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Read)
+            .await?;
 
-        if file.is_archived(&self.storage_path) {
-            return Err(DataError::FileIsAlreadyArchivedError);
+        let preview_key = file.preview_key();
+
+        // Files from before the status was kept may have a preview nobody recorded
+        if file.preview_status == PreviewStatus::Pending
+            && self.storage.stat(&preview_key).await?.is_some()
+        {
+            self.file_repo
+                .set_preview_status(&file, PreviewStatus::Ready)
+                .await?;
+            file.preview_status = PreviewStatus::Ready;
         }
 
-        let original_path = file.build_file_path(&self.storage_path);
+        if file.preview_status != PreviewStatus::Ready {
+            return Err(DataError::EntityNotFoundException("Preview".to_string()));
+        }
 
-        let mut compressed_path = original_path.clone();
-        compressed_path.set_extension("gz");
+        let mut content = Vec::new();
 
-        let mut source_file = fs::File::open(&original_path)
+        self.storage
+            .get_range(&preview_key, None)
+            .await?
+            .read_to_end(&mut content)
             .await
             .map_err(|e| DataError::IOError(e.to_string()))?;
 
+        // Covers are copied out of media as they are, so not every preview is a JPEG
+        let content_type = image::guess_format(&content)
+            .map(|format| format.to_mime_type())
+            .unwrap_or("image/jpeg");
+
+        // The preview belongs to the content, so it only changes along with the blob
+        let etag = match file.blob_id {
+            Some(blob_id) => format!("{}-preview", blob_id),
+            None => format!(
+                "{}-{}-preview",
+                file.id,
+                file.updated_at.unix_timestamp_nanos()
+            ),
+        };
+
+        Ok(Preview::new(content, content_type.to_string(), etag))
+    }
+
+    async fn archive_file(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+        codec: ArchiveCodec,
+    ) -> Result<(), DataError> {
+        let mut file = self
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Owner)
+            .await?;
+
+        if file.is_archived() {
+            return Err(DataError::FileIsAlreadyArchivedError);
+        }
+
+        let compressed_path = self.prepare_temp_path(&file).await?;
+
+        let mut source_file = self.storage.get_range(&file.storage_key(), None).await?;
+
         let dest_file = fs::File::create(&compressed_path)
             .await
             .map_err(|e| DataError::IOError(e.to_string()))?;
 
         let buffered_writer = BufWriter::new(dest_file);
-        let mut encoder = GzipEncoder::new(buffered_writer);
+        let mut encoder = archive_codec::encoder(codec, buffered_writer);
 
         if let Err(e) = tokio::io::copy(&mut source_file, &mut encoder).await {
             let _ = fs::remove_file(&compressed_path).await;
@@ -607,66 +1007,71 @@ impl FileService for FileServiceImpl {
             .await
             .map_err(|e| DataError::IOError(e.to_string()))?;
 
-        let archived_size = metadata.len() as i64;
-        let original_size = file.size;
+        // Content that does not shrink would take more of the owner's storage instead of less
+        if metadata.len() as i64 >= file.size {
+            let _ = fs::remove_file(&compressed_path).await;
+            return Err(DataError::ValidationError(
+                "Archiving would not make this file any smaller".to_string(),
+            ));
+        }
 
-        let mut sp = self
-            .storage_profile_repo
-            .get_by_id(file.owner_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("User".to_string()))?;
+        let blob = self.store_temp_content(&compressed_path).await?;
 
-        sp.reduce_taken_storage_size(original_size - archived_size);
+        let previous_blob_id = file.blob_id;
 
-        let sp_event = UserUpdatedEvent::new(
-            sp.user_id.clone(),
-            None,
-            None,
-            Some(sp.allowed_storage.clone()),
-            Some(sp.taken_storage.clone()),
-            sp.is_blocked.clone(),
-        );
+        // The checksum stays the one of the content as it is handed out, the blob keeps the stored one
+        file.set_as_archived(codec, blob.size);
+        file.update_blob(blob.id);
 
-        if let Err(e) = self.publisher.publish(&sp_event).await {
-            eprintln!("Failed to publish event: {:?}", e);
-        }
-
-        self.storage_profile_repo.save(sp).await?;
-
-        fs::remove_file(&original_path)
-            .await
-            .map_err(|e| DataError::IOError(e.to_string()))?;
+        let file = self
+            .replace_file_content(file, previous_blob_id, &blob)
+            .await?;
 
-        file.rename(format!("{}.gz", file.name));
-        file.size = archived_size;
-        file.update_type(FileType::Zip);
-        self.file_repo.update(file).await?;
+        // The owner is only charged for what is stored, so the saved bytes are given back
+        if let Some(sp) = self
+            .storage_profile_repo
+            .release(file.owner_id, file.logical_size() - file.size)
+            .await?
+        {
+            self.publish_storage_profile(sp).await;
+        }
 
         Ok(())
     }
 
-    async fn unarchive_file(&self, file_id: Uuid) -> Result<(), DataError> {
+    async fn unarchive_file(&self, file_id: Uuid, caller_id: Uuid) -> Result<(), DataError> {
         let mut file = self
-            .file_repo
-            .get_by_id(file_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Owner)
+            .await?;
+
+        // Files archived before codecs were recorded are gzip and get their name back
+        let (codec, legacy_name) = match file.archive_codec {
+            Some(codec) => (codec, None),
+            None if self.file_repo.is_legacy_archive(file.id).await? => (
+                ArchiveCodec::Gzip,
+                file.name.strip_suffix(".gz").map(|name| name.to_string()),
+            ),
+            None => return Err(DataError::FileIsNotArchivedError),
+        };
 
-        if !file.is_archived(&self.storage_path) {
-            return Err(DataError::FileIsNotArchivedError);
+        if let Some(name) = &legacy_name {
+            if self
+                .file_repo
+                .get_by_folder_and_file_name(file.parent_folder_id, name.clone())
+                .await?
+                .is_some()
+            {
+                return Err(DataError::FileAlreadyExistsError);
+            }
         }
 
-        let compressed_path = file.build_file_path(&self.storage_path);
+        let previous_upload_key = file.upload_key();
 
-        let mut output_path = compressed_path.clone();
-        output_path.set_extension("");
+        let output_path = self.prepare_temp_path(&file).await?;
 
-        let source_file = fs::File::open(&compressed_path)
-            .await
-            .map_err(|e| DataError::IOError(e.to_string()))?;
+        let source_file = self.storage.get_range(&file.storage_key(), None).await?;
 
-        let reader = BufReader::new(source_file);
-        let mut decoder = GzipDecoder::new(reader);
+        let mut decoder = archive_codec::decoder(codec, source_file);
 
         let dest_file = fs::File::create(&output_path)
             .await
@@ -691,49 +1096,71 @@ impl FileService for FileServiceImpl {
         let unarchived_size = metadata.len() as i64;
         let original_compressed_size = file.size;
 
-        let mut sp = self
-            .storage_profile_repo
-            .get_by_id(file.owner_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("User".to_string()))?;
-
         let size_difference = unarchived_size - original_compressed_size;
 
-        if !sp.validate_storage_size(size_difference) {
-            let _ = fs::remove_file(&output_path).await;
-            return Err(DataError::NoFreeStorageError);
-        }
+        let mut charged = None;
 
         if size_difference > 0 {
-            sp.increase_storage_size(size_difference);
+            charged = self
+                .storage_profile_repo
+                .try_charge(file.owner_id, size_difference)
+                .await?;
+
+            if charged.is_none() {
+                let _ = fs::remove_file(&output_path).await;
+                return Err(DataError::NoFreeStorageError);
+            }
         }
 
-        let sp_event = UserUpdatedEvent::new(
-            sp.user_id.clone(),
-            None,
-            None,
-            Some(sp.allowed_storage.clone()),
-            Some(sp.taken_storage.clone()),
-            sp.is_blocked.clone(),
-        );
+        let owner_id = file.owner_id;
+        let previous_blob_id = file.blob_id;
 
-        if let Err(e) = self.publisher.publish(&sp_event).await {
-            eprintln!("Failed to publish event: {:?}", e);
-        }
+        let stored = match self.store_temp_content(&output_path).await {
+            Ok(blob) => {
+                file.set_as_unarchived(unarchived_size);
+                file.update_checksum(blob.checksum.clone());
+                file.update_blob(blob.id);
 
-        self.storage_profile_repo.save(sp).await?;
+                if let Some(name) = legacy_name {
+                    file.rename(name);
+                }
 
-        fs::remove_file(&compressed_path)
-            .await
-            .map_err(|e| DataError::IOError(e.to_string()))?;
+                self.replace_file_content(file, previous_blob_id, &blob)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = stored {
+            if size_difference > 0 {
+                self.storage_profile_repo
+                    .release(owner_id, size_difference)
+                    .await?;
+            }
+
+            return Err(e);
+        }
 
-        let new_name = file.name.strip_suffix(".gz").unwrap_or(&file.name).to_string();
-        file.rename(new_name);
-        file.size = unarchived_size;
-        let file_type = FileType::from_filename(&file.name);
-        file.update_type(file_type);
+        // Content stored before blobs existed sits under a key built from the name it had
+        if previous_blob_id.is_none() {
+            let _ = self.storage.delete(&previous_upload_key).await;
+            let _ = self
+                .storage
+                .delete(&build_preview_key(&previous_upload_key))
+                .await;
+        }
+
+        let sp = if size_difference > 0 {
+            charged
+        } else {
+            self.storage_profile_repo
+                .release(owner_id, -size_difference)
+                .await?
+        };
 
-        self.file_repo.update(file).await?;
+        if let Some(sp) = sp {
+            self.publish_storage_profile(sp).await;
+        }
 
         Ok(())
     }
@@ -745,149 +1172,311 @@ impl FileService for FileServiceImpl {
             return Ok(());
         }
 
-        let mut sp = self
-            .storage_profile_repo
-            .get_by_id(user_id)
-            .await
-            .map_err(|e| DataError::EntityNotFoundException(e.to_string()))?
-            .unwrap();
+        self.remove_deleted_files(deleted_files).await
+    }
 
-        let total_size_to_reduce: i64 = deleted_files.iter().map(|f| f.size).sum();
+    async fn cleanup_expired_files(&self) -> Result<(), DataError> {
+        let expired_files = self.file_repo.get_expired_files().await?;
 
-        sp.reduce_taken_storage_size(total_size_to_reduce);
+        if expired_files.is_empty() {
+            return Ok(());
+        }
 
-        self.storage_profile_repo.save(sp.clone()).await?;
+        self.remove_deleted_files(expired_files).await
+    }
 
-        let sp_event: UserUpdatedEvent = UserUpdatedEvent::new(
-            sp.user_id.clone(),
-            None,
-            None,
-            Some(sp.allowed_storage.clone()),
-            Some(sp.taken_storage.clone()),
-            sp.is_blocked.clone(),
-        );
+    async fn expire_stale_uploads(&self, max_age: Duration) -> Result<(), DataError> {
+        let stale_uploads = self
+            .file_repo
+            .get_stale_pending_uploads(OffsetDateTime::now_utc() - max_age)
+            .await?;
+
+        for f in stale_uploads {
+            // Fails the upload and hands its reservation back, unless it was finished in the meantime
+            let f = match self.file_repo.release_reservation(f).await {
+                Ok(f) => f,
+                Err(DataError::ValidationError(_)) => continue,
+                Err(e) => return Err(e),
+            };
+
+            self.upload_session_repo.delete_by_file_id(f.id).await?;
+            let _ = fs::remove_file(f.build_partial_file_path(&self.storage_path)).await;
+            let _ = fs::remove_file(f.build_upload_path(&self.storage_path)).await;
+
+            let event: FileUpdatedEvent = FileUpdatedEvent::new(
+                f.id.clone(),
+                f.is_deleted.clone(),
+                f.ttl.clone(),
+                f.size.clone(),
+                f.upload_status.clone(),
+            );
 
-        if let Err(e) = self.publisher.publish(&sp_event).await {
-            eprintln!("Failed to publish event: {:?}", e);
+            if let Err(e) = self.publisher.publish(&event).await {
+                eprintln!("Failed to publish event: {:?}", e);
+            }
         }
 
-        self.remove_deleted_files(deleted_files).await
+        Ok(())
     }
 
-    async fn cleanup_expired_files(&self) -> Result<(), DataError> {
-        let expired_files = self.file_repo.get_expired_files().await?;
+    async fn remove_deleted_file(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<(), DataError> {
+        let file = self
+            .file_repo
+            .get_deleted_by_id(file_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
 
-        if expired_files.is_empty() {
-            return Ok(());
+        self.access_control
+            .authorize_file(caller_id, &file, FileAccessLevel::Owner)
+            .await?;
+
+        self.remove_deleted_files(vec![file]).await
+    }
+
+    async fn list_file_versions(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<Vec<FileVersion>, DataError> {
+        let f = self
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Read)
+            .await?;
+
+        self.file_version_repo.get_by_file_id(f.id).await
+    }
+
+    async fn restore_file_version(
+        &self,
+        file_id: Uuid,
+        version_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<File, DataError> {
+        let mut f = self
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Edit)
+            .await?;
+
+        // Versions hold uncompressed content, the file would still claim to be compressed
+        if f.is_archived() {
+            return Err(DataError::ValidationError(
+                "Cannot restore a version of an archived file".to_string(),
+            ));
         }
 
-        let mut files_by_owner = HashMap::new();
+        let version = self.get_file_version(&f, version_id).await?;
 
-        for file in expired_files.clone() {
-            files_by_owner
-                .entry(file.owner_id.clone())
-                .or_insert_with(Vec::new)
-                .push(file);
+        // The current content is swapped into the version, which needs a blob of its own to point at
+        if f.blob_id.is_none() {
+            f = self.adopt_into_blob(f).await?;
         }
 
-        for (owner_id, files) in files_by_owner {
-            let mut sp = self
-                .storage_profile_repo
-                .get_by_id(owner_id)
-                .await
-                .map_err(|e| DataError::EntityNotFoundException(e.to_string()))?
-                .unwrap();
+        // Both contents stay stored and charged, only which of them is the file's changes
+        let mut f = self.file_version_repo.restore(f, version).await?;
+
+        f.preview_status = self.file_repo.inherit_preview_status(f.id).await?;
+
+        let event: FileUpdatedEvent = FileUpdatedEvent::new(
+            f.id.clone(),
+            f.is_deleted.clone(),
+            f.ttl.clone(),
+            f.size.clone(),
+            f.upload_status.clone(),
+        );
 
-            let total_size_to_reduce: i64 = files.iter().map(|f| f.size).sum();
+        if let Err(e) = self.publisher.publish(&event).await {
+            eprintln!("Failed to publish event: {:?}", e);
+        }
 
-            sp.reduce_taken_storage_size(total_size_to_reduce);
+        Ok(f)
+    }
 
-            self.storage_profile_repo.save(sp.clone()).await?;
+    async fn delete_file_version(
+        &self,
+        file_id: Uuid,
+        version_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<(), DataError> {
+        let f = self
+            .get_authorized_file(file_id, caller_id, FileAccessLevel::Owner)
+            .await?;
 
-            let sp_event: UserUpdatedEvent = UserUpdatedEvent::new(
-                sp.user_id.clone(),
-                None,
-                None,
-                Some(sp.allowed_storage.clone()),
-                Some(sp.taken_storage.clone()),
-                sp.is_blocked.clone(),
-            );
+        let version = self.get_file_version(&f, version_id).await?;
 
-            if let Err(e) = self.publisher.publish(&sp_event).await {
-                eprintln!("Failed to publish event: {:?}", e);
-            }
+        self.remove_file_version(f.owner_id, version.id).await
+    }
+
+    async fn prune_file_versions(&self, policy: VersionRetentionPolicy) -> Result<u64, DataError> {
+        let expired = self
+            .file_version_repo
+            .get_expired(
+                policy.keep_count,
+                OffsetDateTime::now_utc() - policy.keep_for,
+            )
+            .await?;
+
+        let mut owners: HashMap<Uuid, Uuid> = HashMap::new();
+        let mut pruned = 0;
+
+        for version in expired {
+            let owner_id = match owners.get(&version.file_id) {
+                Some(owner_id) => *owner_id,
+                None => match self.file_repo.get_deleted_by_id(version.file_id).await? {
+                    Some(f) => *owners.entry(f.id).or_insert(f.owner_id),
+                    None => continue,
+                },
+            };
+
+            self.remove_file_version(owner_id, version.id).await?;
+            pruned += 1;
         }
 
-        self.remove_deleted_files(expired_files).await
+        Ok(pruned)
     }
+}
 
-    async fn remove_deleted_file(&self, file_id: Uuid) -> Result<(), DataError> {
+impl FileServiceImpl {
+    async fn get_authorized_file(
+        &self,
+        file_id: Uuid,
+        caller_id: Uuid,
+        required: FileAccessLevel,
+    ) -> Result<File, DataError> {
         let file = self
             .file_repo
-            .get_deleted_by_id(file_id)
+            .get_by_id(file_id)
             .await?
             .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
 
-        let mut sp = self
-            .storage_profile_repo
-            .get_by_id(file.owner_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("Storage Profile".to_string()))?;
+        self.access_control
+            .authorize_file(caller_id, &file, required)
+            .await?;
 
-        sp.reduce_taken_storage_size(file.size);
+        Ok(file)
+    }
 
-        self.storage_profile_repo.save(sp.clone()).await?;
+    async fn get_or_create_upload_session(&self, f: &File) -> Result<UploadSession, DataError> {
+        if let Some(session) = self.upload_session_repo.get_by_file_id(f.id).await? {
+            return Ok(session);
+        }
 
-        let sp_event: UserUpdatedEvent = UserUpdatedEvent::new(
-            sp.user_id.clone(),
-            None,
-            None,
-            Some(sp.allowed_storage.clone()),
-            Some(sp.taken_storage.clone()),
-            sp.is_blocked.clone(),
+        self.upload_session_repo
+            .create(UploadSession::new(f.id, f.owner_id, f.size))
+            .await
+    }
+
+    // A mismatch means the content got corrupted on the way, so the upload is failed and discarded
+    async fn verify_checksum(
+        &self,
+        f: &mut File,
+        checksum: String,
+        path: &Path,
+    ) -> Result<(), DataError> {
+        if !f.matches_checksum(&checksum) {
+            self.file_repo.release_reservation(f.clone()).await?;
+            self.upload_session_repo.delete_by_file_id(f.id).await?;
+            let _ = fs::remove_file(path).await;
+            return Err(DataError::ChecksumMismatchError);
+        }
+
+        f.update_checksum(checksum);
+
+        Ok(())
+    }
+
+    // Marks a fully written file as completed and turns its reservation into taken storage
+    async fn complete_upload(&self, mut f: File, path: &Path) -> Result<(), DataError> {
+        let checksum = f
+            .checksum
+            .clone()
+            .ok_or_else(|| DataError::UnknownError("Upload has no checksum".to_string()))?;
+
+        // Content that is already stored is reused, the uploaded copy is dropped then
+        let blob = self.store_blob(path, &checksum, f.size).await?;
+
+        f.update_blob(blob.id);
+
+        let f = match self.file_repo.complete_reservation(f).await {
+            Ok(f) => f,
+            Err(e) => {
+                self.release_blob(blob.id).await?;
+                return Err(e);
+            }
+        };
+
+        // The file is whole now, so any resumable progress is obsolete
+        self.upload_session_repo.delete_by_file_id(f.id).await?;
+        let _ = fs::remove_file(path).await;
+        let _ = fs::remove_file(f.build_partial_file_path(&self.storage_path)).await;
+
+        let event: FileUpdatedEvent = FileUpdatedEvent::new(
+            f.id.clone(),
+            f.is_deleted.clone(),
+            f.ttl.clone(),
+            f.size.clone(),
+            f.upload_status.clone(),
         );
 
-        if let Err(e) = self.publisher.publish(&sp_event).await {
+        if let Err(e) = self.publisher.publish(&event).await {
             eprintln!("Failed to publish event: {:?}", e);
         }
 
-        self.remove_deleted_files(vec![file]).await
+        // After the file has been uploaded we need to create a preview of this file, reused content already has one
+        self.refresh_preview(f, &blob).await?;
+
+        Ok(())
+    }
+
+    async fn get_authorized_folder(
+        &self,
+        folder_id: Uuid,
+        caller_id: Uuid,
+        required: FileAccessLevel,
+    ) -> Result<Folder, DataError> {
+        let folder = self
+            .folder_repo
+            .get_by_id(folder_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Folder".to_string()))?;
+
+        self.access_control
+            .authorize_folder(caller_id, &folder, required)
+            .await?;
+
+        Ok(folder)
     }
-}
 
-impl FileServiceImpl {
     async fn remove_deleted_files(&self, deleted_files: Vec<File>) -> Result<(), DataError> {
         const CONCURRENCY_LIMIT: usize = 10;
 
         let results = stream::iter(deleted_files)
             .map(|file| async move {
-                let path = file.build_file_path(&self.storage_path);
-
-                let remove_result = match fs::remove_file(&path).await {
-                    Ok(_) => Ok(file.id),
-                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                        let mut gz_path = path.clone();
-                        gz_path.set_extension("gz");
-
-                        match fs::remove_file(gz_path).await {
-                            Ok(_) => Ok(file.id),
-                            Err(e2) => Err((file.id, e2)),
-                        }
-                    }
-                    Err(e) => Err((file.id, e)),
+                let _ = fs::remove_file(file.build_partial_file_path(&self.storage_path)).await;
+
+                // Blob content is shared, it is removed once the rows are gone and nothing points at it anymore
+                if file.blob_id.is_some() {
+                    return Ok(file);
+                }
+
+                let key = file.upload_key();
+                let gz_key = Path::new(&key).with_extension("gz");
+
+                // Missing content is not an error, the row can still be dropped
+                let remove_result = match self.storage.delete(&key).await {
+                    Ok(_) => self.storage.delete(&gz_key.to_string_lossy()).await,
+                    Err(e) => Err(e),
                 };
 
                 if remove_result.is_ok() {
-                    if let Some(bucket2) = path.parent() {
-                        if fs::remove_dir(bucket2).await.is_ok() {
-                            if let Some(bucket1) = bucket2.parent() {
-                                let _ = fs::remove_dir(bucket1).await;
-                            }
-                        }
-                    }
+                    let _ = self.storage.delete(&build_preview_key(&key)).await;
                 }
 
-                remove_result
+                match remove_result {
+                    Ok(_) => Ok(file),
+                    Err(e) => Err((file.id, e)),
+                }
             })
             .buffer_unordered(CONCURRENCY_LIMIT)
             .collect::<Vec<_>>()
@@ -898,13 +1487,22 @@ impl FileServiceImpl {
 
         for res in results {
             match res {
-                Ok(id) => success_results.push(id),
+                Ok(file) => success_results.push(file),
                 Err(e) => error_results.push(e),
             }
         }
 
         if !success_results.is_empty() {
-            self.file_repo.delete_by_ids(&success_results).await?;
+            let ids: Vec<Uuid> = success_results.iter().map(|f| f.id).collect();
+
+            let (released, versions) = self.file_repo.delete_by_ids(&ids).await?;
+
+            for blob in &released {
+                self.remove_blob_content(blob).await;
+            }
+
+            // Storage is only given back for rows that are actually gone, so a retry cannot release it twice
+            self.release_storage(&success_results, &versions).await?;
         }
 
         if !error_results.is_empty() {
@@ -916,4 +1514,259 @@ impl FileServiceImpl {
 
         Ok(())
     }
+
+    // Completed files and their versions are charged to taken storage, pending ones only hold a reservation
+    async fn release_storage(
+        &self,
+        files: &[File],
+        versions: &[FileVersion],
+    ) -> Result<(), DataError> {
+        let mut sizes_by_owner: HashMap<Uuid, (i64, i64)> = HashMap::new();
+
+        for file in files {
+            let (taken, reserved) = sizes_by_owner.entry(file.owner_id).or_insert((0, 0));
+
+            match file.upload_status {
+                UploadStatus::Completed => *taken += file.size,
+                UploadStatus::Pending => *reserved += file.size,
+                UploadStatus::Failed => {}
+            }
+        }
+
+        for version in versions {
+            let owner_id = match files.iter().find(|f| f.id == version.file_id) {
+                Some(f) => f.owner_id,
+                None => continue,
+            };
+
+            let (taken, _) = sizes_by_owner.entry(owner_id).or_insert((0, 0));
+            *taken += version.size;
+        }
+
+        for (owner_id, (taken, reserved)) in sizes_by_owner {
+            if reserved > 0 {
+                self.storage_profile_repo
+                    .release_reserved(owner_id, reserved)
+                    .await?;
+            }
+
+            if taken > 0 {
+                if let Some(sp) = self.storage_profile_repo.release(owner_id, taken).await? {
+                    self.publish_storage_profile(sp).await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn publish_storage_profile(&self, sp: StorageProfile) {
+        let sp_event: UserUpdatedEvent = UserUpdatedEvent::new(
+            sp.user_id.clone(),
+            None,
+            None,
+            Some(sp.allowed_storage.clone()),
+            Some(sp.taken_storage.clone()),
+            sp.is_blocked.clone(),
+        );
+
+        if let Err(e) = self.publisher.publish(&sp_event).await {
+            eprintln!("Failed to publish event: {:?}", e);
+        }
+    }
+
+    // Temporary content is written next to the file's upload path, where the reconciliation expects it
+    async fn prepare_temp_path(&self, f: &File) -> Result<PathBuf, DataError> {
+        let upload_path = f.build_upload_path(&self.storage_path);
+
+        let parent = upload_path
+            .parent()
+            .ok_or_else(|| DataError::UnknownError("Invalid file path structure".to_string()))?;
+
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| DataError::IOError(format!("Failed to create buckets: {}", e)))?;
+
+        Ok(parent.join(format!("{}.tmp", f.id)))
+    }
+
+    // Hands a finished temporary file over to its blob, the temporary file is gone afterwards either way
+    async fn store_temp_content(&self, temp_path: &Path) -> Result<Blob, DataError> {
+        let stored = match checksum_file(temp_path).await {
+            Ok(checksum) => match fs::metadata(temp_path).await {
+                Ok(metadata) => {
+                    self.store_blob(temp_path, &checksum, metadata.len() as i64)
+                        .await
+                }
+                Err(e) => Err(DataError::IOError(e.to_string())),
+            },
+            Err(e) => Err(e),
+        };
+
+        let _ = fs::remove_file(temp_path).await;
+
+        stored
+    }
+
+    // Takes a reference on the blob for this content and moves the staged bytes at `path` into it, unless they are stored already.
+    // The staged file is gone afterwards either way
+    async fn store_blob(&self, path: &Path, checksum: &str, size: i64) -> Result<Blob, DataError> {
+        let blob = self.blob_repo.acquire(checksum, size).await?;
+        let blob_key = Blob::storage_key(blob.id);
+
+        let stored = match self.storage.stat(&blob_key).await {
+            Ok(Some(_)) => {
+                let _ = fs::remove_file(path).await;
+                Ok(())
+            }
+            Ok(None) => self.storage.put_file(&blob_key, path).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = stored {
+            let _ = fs::remove_file(path).await;
+            self.release_blob(blob.id).await?;
+            return Err(e);
+        }
+
+        Ok(blob)
+    }
+
+    // Takes another reference on the file's blob, files stored before blobs existed are moved into one first
+    async fn share_blob(&self, file: File) -> Result<Blob, DataError> {
+        let file = match file.blob_id {
+            Some(_) => file,
+            None => self.adopt_into_blob(file).await?,
+        };
+
+        let blob_id = file
+            .blob_id
+            .ok_or_else(|| DataError::EntityNotFoundException("Blob".to_string()))?;
+
+        self.blob_repo
+            .add_ref(blob_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Blob".to_string()))
+    }
+
+    async fn adopt_into_blob(&self, mut file: File) -> Result<File, DataError> {
+        if file.upload_status != UploadStatus::Completed {
+            return Err(DataError::ValidationError(
+                "File is not completed".to_string(),
+            ));
+        }
+
+        let key = file.upload_key();
+
+        let checksum = match &file.checksum {
+            Some(checksum) => checksum.clone(),
+            None => checksum_reader(self.storage.get_range(&key, None).await?).await?,
+        };
+
+        let blob = self.blob_repo.acquire(&checksum, file.size).await?;
+
+        // Only copied, the content stays readable under its old key until the row is switched over
+        if let Err(e) = self
+            .storage
+            .copy(&key, &Blob::storage_key(blob.id))
+            .await
+        {
+            self.release_blob(blob.id).await?;
+            return Err(e);
+        }
+
+        file.update_checksum(checksum);
+        file.update_blob(blob.id);
+
+        self.replace_file_content(file, None, &blob).await
+    }
+
+    // Switches the row over to `blob` and cleans up whatever the previous content leaves behind
+    async fn replace_file_content(
+        &self,
+        f: File,
+        previous_blob_id: Option<Uuid>,
+        blob: &Blob,
+    ) -> Result<File, DataError> {
+        let upload_key = f.upload_key();
+
+        let (f, released) = match self.file_repo.replace_content(f, previous_blob_id).await {
+            Ok(result) => result,
+            Err(e) => {
+                self.release_blob(blob.id).await?;
+                return Err(e);
+            }
+        };
+
+        if let Some(released) = released {
+            self.remove_blob_content(&released).await;
+        }
+
+        // Content stored before blobs existed sits under the file's own id
+        if previous_blob_id.is_none() {
+            let _ = self.storage.delete(&upload_key).await;
+            let _ = self.storage.delete(&build_preview_key(&upload_key)).await;
+        }
+
+        self.refresh_preview(f.clone(), blob).await?;
+
+        Ok(f)
+    }
+
+    // Content stored for the first time gets a preview made, reused content shares the one it has
+    async fn refresh_preview(&self, f: File, blob: &Blob) -> Result<(), DataError> {
+        if blob.ref_count > 1 {
+            self.file_repo.inherit_preview_status(f.id).await?;
+            return Ok(());
+        }
+
+        self.preview_service.enqueue(&f).await
+    }
+
+    async fn get_file_version(&self, f: &File, version_id: Uuid) -> Result<FileVersion, DataError> {
+        self.file_version_repo
+            .get_by_id(version_id)
+            .await?
+            .filter(|v| v.file_id == f.id)
+            .ok_or_else(|| DataError::EntityNotFoundException("File version".to_string()))
+    }
+
+    // Drops the version and gives its size back to the owner, the content goes once nothing refers to it anymore
+    async fn remove_file_version(&self, owner_id: Uuid, version_id: Uuid) -> Result<(), DataError> {
+        let (version, released) = match self.file_version_repo.delete(version_id).await? {
+            Some(result) => result,
+            None => return Ok(()),
+        };
+
+        if let Some(blob) = released {
+            self.remove_blob_content(&blob).await;
+        }
+
+        if let Some(sp) = self
+            .storage_profile_repo
+            .release(owner_id, version.size)
+            .await?
+        {
+            self.publish_storage_profile(sp).await;
+        }
+
+        Ok(())
+    }
+
+    async fn release_blob(&self, blob_id: Uuid) -> Result<(), DataError> {
+        if let Some(blob) = self.blob_repo.release(blob_id).await? {
+            self.remove_blob_content(&blob).await;
+        }
+
+        Ok(())
+    }
+
+    // Only called once the last reference on the blob is gone
+    async fn remove_blob_content(&self, blob: &Blob) {
+        if let Err(e) = self.storage.delete(&Blob::storage_key(blob.id)).await {
+            tracing::error!("Failed to remove blob {}: {:?}", blob.id, e);
+        }
+
+        let _ = self.storage.delete(&blob.preview_key()).await;
+    }
 }
@@ -2,45 +2,103 @@ use crate::data::create_folder_command::CreateFolderCommand;
 use crate::data::move_folder_command::MoveFolderCommand;
 use crate::data::update_folder_name_command::UpdateFolderNameCommand;
 use crate::db::folder_repository::FolderRepository;
+use crate::db::shared_file_repository::SharedFileRepository;
+use crate::db::shared_folder_repository::SharedFolderRepository;
 use crate::helpers::data_error::DataError;
+use crate::service::access_control_service::{AccessControlService, FileAccessLevel};
 use async_recursion::async_recursion;
 use async_trait::async_trait;
 use homelab_core::file::{File, FileType};
-use homelab_core::folder::Folder;
+use homelab_core::folder::{Folder, SHARED_WITH_ME_FOLDER_ID};
 use std::sync::Arc;
 use uuid::Uuid;
 
 #[async_trait]
 pub trait FolderService: Send + Sync {
     async fn get_root(&self, user_id: Uuid) -> Result<Option<Folder>, DataError>;
-    async fn get_by_id(&self, folder_id: Uuid) -> Result<Option<Folder>, DataError>;
-    async fn get_children_by_id(&self, folder_id: Uuid) -> Result<Vec<Folder>, DataError>;
-    async fn search_folder(&self, search_query: String) -> Result<Vec<Folder>, DataError>;
+    async fn get_shared_root(&self, user_id: Uuid) -> Result<Folder, DataError>;
+    async fn get_by_id(
+        &self,
+        folder_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<Option<Folder>, DataError>;
+    async fn get_children_by_id(
+        &self,
+        folder_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<Vec<Folder>, DataError>;
+    async fn search_folder(
+        &self,
+        search_query: String,
+        caller_id: Uuid,
+    ) -> Result<Vec<Folder>, DataError>;
     async fn filter_files_by_folder(
         &self,
         file_types: &[FileType],
         folder_id: Uuid,
+        caller_id: Uuid,
     ) -> Result<Vec<File>, DataError>;
-    async fn get_folder_path(&self, folder_id: Uuid) -> Result<String, DataError>;
-    async fn get_by_folder(&self, folder_id: Uuid) -> Result<Vec<File>, DataError>;
+    async fn get_folder_path(&self, folder_id: Uuid, caller_id: Uuid) -> Result<String, DataError>;
+    async fn get_by_folder(&self, folder_id: Uuid, caller_id: Uuid) -> Result<Vec<File>, DataError>;
     async fn update_folder_name(
         &self,
         command: UpdateFolderNameCommand,
         folder_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<Folder, DataError>;
+    async fn delete_chosen_folders(
+        &self,
+        folder_ids: &[Uuid],
+        caller_id: Uuid,
+    ) -> Result<(), DataError>;
+    async fn delete(&self, folder_id: Uuid, caller_id: Uuid) -> Result<(), DataError>;
+    async fn create(&self, command: CreateFolderCommand, owner_id: Uuid) -> Result<Folder, DataError>;
+    async fn move_folder(
+        &self,
+        command: MoveFolderCommand,
+        caller_id: Uuid,
     ) -> Result<Folder, DataError>;
-    async fn delete_chosen_folders(&self, folder_ids: &[Uuid]) -> Result<(), DataError>;
-    async fn delete(&self, folder_id: Uuid) -> Result<(), DataError>;
-    async fn create(&self, command: CreateFolderCommand) -> Result<Folder, DataError>;
-    async fn move_folder(&self, command: MoveFolderCommand) -> Result<Folder, DataError>;
 }
 
 pub struct FolderServiceImpl {
     folder_repo: Arc<dyn FolderRepository>,
+    shared_folder_repo: Arc<dyn SharedFolderRepository>,
+    shared_file_repo: Arc<dyn SharedFileRepository>,
+    access_control: Arc<dyn AccessControlService>,
 }
 
 impl FolderServiceImpl {
-    pub fn new(folder_repo: Arc<dyn FolderRepository>) -> Self {
-        Self { folder_repo }
+    pub fn new(
+        folder_repo: Arc<dyn FolderRepository>,
+        shared_folder_repo: Arc<dyn SharedFolderRepository>,
+        shared_file_repo: Arc<dyn SharedFileRepository>,
+        access_control: Arc<dyn AccessControlService>,
+    ) -> Self {
+        Self {
+            folder_repo,
+            shared_folder_repo,
+            shared_file_repo,
+            access_control,
+        }
+    }
+
+    async fn get_authorized_folder(
+        &self,
+        folder_id: Uuid,
+        caller_id: Uuid,
+        required: FileAccessLevel,
+    ) -> Result<Folder, DataError> {
+        let folder = self
+            .folder_repo
+            .get_by_id(folder_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Folder".to_string()))?;
+
+        self.access_control
+            .authorize_folder(caller_id, &folder, required)
+            .await?;
+
+        Ok(folder)
     }
 
     #[async_recursion]
@@ -66,17 +124,54 @@ impl FolderService for FolderServiceImpl {
         self.folder_repo.get_root(user_id).await
     }
 
-    async fn get_by_id(&self, folder_id: Uuid) -> Result<Option<Folder>, DataError> {
-        self.folder_repo.get_by_id(folder_id).await
+    async fn get_shared_root(&self, user_id: Uuid) -> Result<Folder, DataError> {
+        Ok(Folder::shared_with_me_root(user_id))
     }
 
-    async fn get_children_by_id(&self, folder_id: Uuid) -> Result<Vec<Folder>, DataError> {
+    async fn get_by_id(
+        &self,
+        folder_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<Option<Folder>, DataError> {
+        if folder_id == SHARED_WITH_ME_FOLDER_ID {
+            return Ok(Some(Folder::shared_with_me_root(caller_id)));
+        }
+
+        let folder = match self.folder_repo.get_by_id(folder_id).await? {
+            Some(folder) => folder,
+            None => return Ok(None),
+        };
+
+        self.access_control
+            .authorize_folder(caller_id, &folder, FileAccessLevel::Read)
+            .await?;
+
+        Ok(Some(folder))
+    }
+
+    async fn get_children_by_id(
+        &self,
+        folder_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<Vec<Folder>, DataError> {
+        // The top level of "Shared with me" is every folder someone shared with the caller
+        if folder_id == SHARED_WITH_ME_FOLDER_ID {
+            return self.shared_folder_repo.get_folders_shared_with(caller_id).await;
+        }
+
+        self.get_authorized_folder(folder_id, caller_id, FileAccessLevel::Read)
+            .await?;
+
         self.folder_repo.get_children_by_id(folder_id).await
     }
 
-    async fn search_folder(&self, search_query: String) -> Result<Vec<Folder>, DataError> {
+    async fn search_folder(
+        &self,
+        search_query: String,
+        caller_id: Uuid,
+    ) -> Result<Vec<Folder>, DataError> {
         self.folder_repo
-            .search_by_name(format!("%{}%", search_query))
+            .search_by_name(format!("%{}%", search_query), caller_id)
             .await
     }
 
@@ -84,18 +179,50 @@ impl FolderService for FolderServiceImpl {
         &self,
         file_types: &[FileType],
         folder_id: Uuid,
+        caller_id: Uuid,
     ) -> Result<Vec<File>, DataError> {
+        if folder_id == SHARED_WITH_ME_FOLDER_ID {
+            let files = self.shared_file_repo.get_files_shared_with(caller_id).await?;
+
+            return Ok(files
+                .into_iter()
+                .filter(|f| file_types.contains(&f.file_type))
+                .collect());
+        }
+
+        self.get_authorized_folder(folder_id, caller_id, FileAccessLevel::Read)
+            .await?;
+
         self.folder_repo
             .filter_files_in_folder(file_types, folder_id)
             .await
     }
 
-    async fn get_folder_path(&self, folder_id: Uuid) -> Result<String, DataError> {
+    async fn get_folder_path(&self, folder_id: Uuid, caller_id: Uuid) -> Result<String, DataError> {
+        if folder_id == SHARED_WITH_ME_FOLDER_ID {
+            return Ok(Folder::shared_with_me_root(caller_id).name);
+        }
+
+        self.get_authorized_folder(folder_id, caller_id, FileAccessLevel::Read)
+            .await?;
+
         let path = self.get_parent_folder_name(folder_id).await?;
         Ok(path)
     }
 
-    async fn get_by_folder(&self, folder_id: Uuid) -> Result<Vec<File>, DataError> {
+    async fn get_by_folder(
+        &self,
+        folder_id: Uuid,
+        caller_id: Uuid,
+    ) -> Result<Vec<File>, DataError> {
+        // Individually shared files live directly under "Shared with me"
+        if folder_id == SHARED_WITH_ME_FOLDER_ID {
+            return self.shared_file_repo.get_files_shared_with(caller_id).await;
+        }
+
+        self.get_authorized_folder(folder_id, caller_id, FileAccessLevel::Read)
+            .await?;
+
         self.folder_repo.get_by_folder_id(folder_id).await
     }
 
@@ -103,43 +230,62 @@ impl FolderService for FolderServiceImpl {
         &self,
         command: UpdateFolderNameCommand,
         folder_id: Uuid,
+        caller_id: Uuid,
     ) -> Result<Folder, DataError> {
         let mut folder: Folder = self
-            .folder_repo
-            .get_by_id(folder_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+            .get_authorized_folder(folder_id, caller_id, FileAccessLevel::Owner)
+            .await?;
 
         folder.rename(command.new_name);
 
         self.folder_repo.update_folder(folder).await
     }
 
-    async fn delete_chosen_folders(&self, folder_ids: &[Uuid]) -> Result<(), DataError> {
+    async fn delete_chosen_folders(
+        &self,
+        folder_ids: &[Uuid],
+        caller_id: Uuid,
+    ) -> Result<(), DataError> {
+        for folder_id in folder_ids {
+            self.get_authorized_folder(*folder_id, caller_id, FileAccessLevel::Owner)
+                .await?;
+        }
+
         self.folder_repo.delete_all(folder_ids).await
     }
 
-    async fn delete(&self, folder_id: Uuid) -> Result<(), DataError> {
+    async fn delete(&self, folder_id: Uuid, caller_id: Uuid) -> Result<(), DataError> {
+        self.get_authorized_folder(folder_id, caller_id, FileAccessLevel::Owner)
+            .await?;
+
         self.folder_repo.delete_by_id(folder_id).await
     }
 
-    async fn create(&self, command: CreateFolderCommand) -> Result<Folder, DataError> {
+    async fn create(&self, command: CreateFolderCommand, owner_id: Uuid) -> Result<Folder, DataError> {
+        self.get_authorized_folder(command.parent_folder_id, owner_id, FileAccessLevel::Owner)
+            .await?;
+
         let f = Folder::new(
             Uuid::new_v4(),
             Some(command.parent_folder_id),
             command.name,
-            command.owner_id,
+            owner_id,
         );
 
         self.folder_repo.create(f).await
     }
 
-    async fn move_folder(&self, command: MoveFolderCommand) -> Result<Folder, DataError> {
+    async fn move_folder(
+        &self,
+        command: MoveFolderCommand,
+        caller_id: Uuid,
+    ) -> Result<Folder, DataError> {
         let mut folder = self
-            .folder_repo
-            .get_by_id(command.folder_id)
-            .await?
-            .ok_or_else(|| DataError::EntityNotFoundException("Folder".to_string()))?;
+            .get_authorized_folder(command.folder_id, caller_id, FileAccessLevel::Owner)
+            .await?;
+
+        self.get_authorized_folder(command.target_folder, caller_id, FileAccessLevel::Owner)
+            .await?;
 
         folder.update_parent_folder(command.target_folder);
 
@@ -0,0 +1,150 @@
+use crate::data::scrub_summary::ScrubSummary;
+use crate::db::blob_repository::BlobRepository;
+use crate::db::file_integrity_repository::FileIntegrityRepository;
+use crate::db::file_repository::FileRepository;
+use crate::events::rabbitmq::RabbitMqPublisher;
+use crate::helpers::checksum::checksum_reader;
+use crate::helpers::data_error::DataError;
+use crate::storage::storage_backend::StorageBackend;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::events::FileIntegrityFailedEvent;
+use homelab_core::file::File;
+use homelab_core::file_integrity::{FileIntegrity, IntegrityStatus};
+use sqlx::types::time::OffsetDateTime;
+use std::sync::Arc;
+
+#[async_trait]
+pub trait IntegrityService: Send + Sync {
+    async fn scrub(&self, batch_size: i64) -> Result<ScrubSummary, DataError>;
+}
+
+#[derive(new)]
+pub struct IntegrityServiceImpl {
+    file_repo: Arc<dyn FileRepository>,
+    integrity_repo: Arc<dyn FileIntegrityRepository>,
+    blob_repo: Arc<dyn BlobRepository>,
+    storage: Arc<dyn StorageBackend>,
+    publisher: Arc<RabbitMqPublisher>,
+}
+
+#[async_trait]
+impl IntegrityService for IntegrityServiceImpl {
+    async fn scrub(&self, batch_size: i64) -> Result<ScrubSummary, DataError> {
+        let files = self
+            .integrity_repo
+            .get_files_due_for_check(batch_size)
+            .await?;
+
+        let mut summary = ScrubSummary::default();
+
+        for file in files {
+            let integrity = match self.check_file(file).await {
+                Ok(Some(integrity)) => integrity,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::error!("Failed to scrub a file: {:?}", e);
+                    continue;
+                }
+            };
+
+            summary.checked += 1;
+
+            match integrity.status {
+                IntegrityStatus::Ok => {}
+                IntegrityStatus::Missing => summary.missing += 1,
+                IntegrityStatus::Corrupted => summary.corrupted += 1,
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+impl IntegrityServiceImpl {
+    async fn check_file(&self, file: File) -> Result<Option<FileIntegrity>, DataError> {
+        let storage_key = file.storage_key();
+        let mut expected_checksum = self.stored_checksum(&file).await?;
+
+        let (status, actual_checksum) = if self.storage.stat(&storage_key).await?.is_none() {
+            (IntegrityStatus::Missing, None)
+        } else {
+            let actual = checksum_reader(self.storage.get_range(&storage_key, None).await?).await?;
+
+            let status = match &expected_checksum {
+                Some(expected) if !expected.eq_ignore_ascii_case(&actual) => {
+                    IntegrityStatus::Corrupted
+                }
+                _ => IntegrityStatus::Ok,
+            };
+
+            (status, Some(actual))
+        };
+
+        if status != IntegrityStatus::Ok && !self.is_unchanged(&file).await? {
+            // The file was renamed, archived or deleted while we were reading it
+            return Ok(None);
+        }
+
+        // Files stored before checksums existed get their first digest here
+        if expected_checksum.is_none() && !file.is_archived() {
+            if let Some(actual) = &actual_checksum {
+                self.file_repo.backfill_checksum(file.id, actual).await?;
+                expected_checksum = Some(actual.clone());
+            }
+        }
+
+        let integrity = FileIntegrity::new(
+            file.id,
+            status,
+            expected_checksum,
+            actual_checksum,
+            OffsetDateTime::now_utc(),
+        );
+
+        let integrity = self.integrity_repo.save(&integrity).await?;
+
+        if integrity.is_issue() {
+            tracing::warn!(
+                "Integrity check failed for file {}: {:?}",
+                file.id,
+                integrity.status
+            );
+
+            let event = FileIntegrityFailedEvent::new(
+                file.id,
+                file.owner_id,
+                integrity.status.clone(),
+                integrity.expected_checksum.clone(),
+                integrity.actual_checksum.clone(),
+                integrity.checked_at,
+            );
+
+            if let Err(e) = self.publisher.publish(&event).await {
+                eprintln!("Failed to publish event: {:?}", e);
+            }
+        }
+
+        Ok(Some(integrity))
+    }
+
+    // Archived content is stored compressed, the digest of what is stored is kept on its blob
+    async fn stored_checksum(&self, file: &File) -> Result<Option<String>, DataError> {
+        match (file.is_archived(), file.blob_id) {
+            (true, Some(blob_id)) => Ok(self
+                .blob_repo
+                .get_by_id(blob_id)
+                .await?
+                .map(|blob| blob.checksum)),
+            _ => Ok(file.checksum.clone()),
+        }
+    }
+
+    async fn is_unchanged(&self, file: &File) -> Result<bool, DataError> {
+        let current = self.file_repo.get_by_id(file.id).await?;
+
+        Ok(current.is_some_and(|current| {
+            current.storage_key() == file.storage_key() && current.checksum == file.checksum
+        }))
+    }
+}
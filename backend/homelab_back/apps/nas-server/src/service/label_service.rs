@@ -3,6 +3,7 @@ use crate::data::create_label_command::CreateLabelCommand;
 use crate::db::label_repository::LabelRepository;
 use crate::db::storage_profile_repository::StorageProfileRepository;
 use crate::helpers::data_error::DataError;
+use crate::service::access_control_service::AccessControlService;
 use async_trait::async_trait;
 use derive_new::new;
 use homelab_core::label::Label;
@@ -11,28 +12,41 @@ use uuid::Uuid;
 
 #[async_trait]
 pub trait LabelService: Send + Sync {
-    async fn get_all(&self) -> Result<Vec<Label>, DataError>;
-    async fn create_label(&self, command: CreateLabelCommand) -> Result<Label, DataError>;
-    async fn delete_label(&self, label_id: Uuid) -> Result<(), DataError>;
-    async fn change_label(&self, command: ChangeLabelCommand) -> Result<Label, DataError>;
+    async fn get_all(&self, owner_id: Uuid) -> Result<Vec<Label>, DataError>;
+    async fn create_label(
+        &self,
+        command: CreateLabelCommand,
+        owner_id: Uuid,
+    ) -> Result<Label, DataError>;
+    async fn delete_label(&self, label_id: Uuid, caller_id: Uuid) -> Result<(), DataError>;
+    async fn change_label(
+        &self,
+        command: ChangeLabelCommand,
+        caller_id: Uuid,
+    ) -> Result<Label, DataError>;
 }
 
 #[derive(new)]
 pub struct LabelServiceImpl {
     label_repo: Arc<dyn LabelRepository>,
     storage_profile_repo: Arc<dyn StorageProfileRepository>,
+    access_control: Arc<dyn AccessControlService>,
 }
 
 #[async_trait]
 impl LabelService for LabelServiceImpl {
-    async fn get_all(&self) -> Result<Vec<Label>, DataError> {
-        self.label_repo.get_all().await
+    async fn get_all(&self, owner_id: Uuid) -> Result<Vec<Label>, DataError> {
+        self.label_repo.get_all_by_owner(owner_id).await
     }
 
-    async fn create_label(&self, command: CreateLabelCommand) -> Result<Label, DataError> {
+    async fn create_label(
+        &self,
+        command: CreateLabelCommand,
+        owner_id: Uuid,
+    ) -> Result<Label, DataError> {
         let sp = self
             .storage_profile_repo
-            .get_by_id(command.owner_id)
+            .get_by_id(owner_id)
             .await?
             .ok_or_else(|| DataError::EntityNotFoundException("User".to_string()))?;
 
@@ -41,17 +55,31 @@ impl LabelService for LabelServiceImpl {
         Ok(self.label_repo.create(label).await?)
     }
 
-    async fn delete_label(&self, label_id: Uuid) -> Result<(), DataError> {
-        self.label_repo.delete(label_id).await
+    async fn delete_label(&self, label_id: Uuid, caller_id: Uuid) -> Result<(), DataError> {
+        let label = self
+            .label_repo
+            .get_by_id(label_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Label".to_string()))?;
+
+        self.access_control.authorize_label(caller_id, &label)?;
+
+        self.label_repo.delete(label.id).await
     }
 
-    async fn change_label(&self, command: ChangeLabelCommand) -> Result<Label, DataError> {
+    async fn change_label(
+        &self,
+        command: ChangeLabelCommand,
+        caller_id: Uuid,
+    ) -> Result<Label, DataError> {
         let mut label = self
             .label_repo
             .get_by_id(command.id)
             .await?
             .ok_or_else(|| DataError::EntityNotFoundException("Label".to_string()))?;
 
+        self.access_control.authorize_label(caller_id, &label)?;
+
         label.update(command.name, command.color);
 
         Ok(self.label_repo.update(label).await?)
@@ -1,8 +1,15 @@
+pub mod access_control_service;
+pub mod archive_content_service;
+pub mod bundle_service;
+pub mod compression_policy_service;
 pub mod file_label_service;
 pub mod file_service;
 pub mod folder_service;
 pub mod global_file_service;
+pub mod integrity_service;
 pub mod label_service;
 pub mod preview_service;
+pub mod reconciliation_service;
+pub mod share_link_service;
 pub mod shared_file_service;
 pub mod storage_profile_service;
@@ -1,108 +1,340 @@
+use crate::data::regenerate_previews_command::RegeneratePreviewsCommand;
+use crate::db::file_repository::FileRepository;
+use crate::db::preview_job_repository::PreviewJobRepository;
+use crate::events::rabbitmq::RabbitMqPublisher;
+use crate::helpers::data_error::DataError;
+use crate::storage::storage_backend::StorageBackend;
+use async_trait::async_trait;
 use derive_new::new;
 use fast_image_resize::{FilterType, Image, PixelType, ResizeAlg, Resizer};
-use homelab_core::file::{File, FileType};
+use homelab_core::events::FilePreviewFailedEvent;
+use homelab_core::file::{File, FileType, PreviewStatus};
+use homelab_core::preview_job::PreviewJob;
+use image::ImageReader;
+use sqlx::types::time::OffsetDateTime;
 use std::num::NonZeroU32;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::process::Command;
+use tokio::sync::Notify;
 use tokio::task;
-
-pub trait PreviewService {
-    fn spawn_generation(file: File, storage_path: PathBuf);
+use uuid::Uuid;
+
+// A job that keeps failing is given up on after this many runs
+const MAX_ATTEMPTS: i32 = 5;
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+// Idle workers look for due jobs this often, retries become due without anyone waking them
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+const PREVIEW_STAGING_DIR: &str = "preview-staging";
+
+#[async_trait]
+pub trait PreviewService: Send + Sync {
+    async fn enqueue(&self, file: &File) -> Result<(), DataError>;
+    async fn regenerate(&self, command: RegeneratePreviewsCommand) -> Result<u64, DataError>;
+    // Never returns, each worker makes one preview at a time
+    async fn run_workers(&self, concurrency: usize);
 }
 
 #[derive(new)]
-pub struct PreviewServiceImpl;
+pub struct PreviewServiceImpl {
+    preview_job_repo: Arc<dyn PreviewJobRepository>,
+    file_repo: Arc<dyn FileRepository>,
+    storage: Arc<dyn StorageBackend>,
+    staging_path: PathBuf,
+    publisher: Arc<RabbitMqPublisher>,
+    #[new(default)]
+    wake: Notify,
+}
 
+#[async_trait]
 impl PreviewService for PreviewServiceImpl {
-    fn spawn_generation(file: File, storage_path: PathBuf) {
-        tokio::spawn(async move {
-            let file_path = file.build_file_path(&storage_path);
-            let preview_path = file_path.with_extension("preview");
-
-            let ffmpeg_binary = "/usr/lib/jellyfin-ffmpeg/ffmpeg";
-
-            let thread_result = match file.file_type {
-                FileType::Image => {
-                    task::spawn_blocking(move || {
-                        Self::generate_image_preview(&file_path, &preview_path)
-                    })
-                    .await
-                }
-                FileType::Video => {
-                    let f_path = file_path.to_string_lossy().to_string();
-                    let p_path = preview_path.to_string_lossy().to_string();
-
-                    let video_result =
-                        match Self::try_extract_cover(ffmpeg_binary, &f_path, &p_path).await {
-                            Ok(_) => Ok(()),
-                            Err(_) => {
-                                match Self::extract_frame_gpu(ffmpeg_binary, &f_path, &p_path).await
-                                {
-                                    Ok(_) => Ok(()),
-                                    Err(gpu_err) => {
-                                        eprintln!(
-                                            "GPU Preciew failed for {}: {}. Retrying with CPU",
-                                            file.id, gpu_err
-                                        );
-                                        Self::extract_frame_cpu(ffmpeg_binary, &f_path, &p_path)
-                                            .await
-                                    }
-                                }
-                            }
-                        };
+    async fn enqueue(&self, file: &File) -> Result<(), DataError> {
+        if !Self::is_supported(file) {
+            return self
+                .file_repo
+                .set_preview_status(file, PreviewStatus::Unsupported)
+                .await;
+        }
 
-                    Ok(video_result)
-                }
-                FileType::Audio => {
-                    let f_path = file_path.to_string_lossy().to_string();
-                    let p_path = preview_path.to_string_lossy().to_string();
+        self.preview_job_repo.enqueue(file.id).await?;
+        self.wake.notify_one();
 
-                    let _ = Self::try_extract_cover(ffmpeg_binary, &f_path, &p_path).await;
-                    Ok(Ok(()))
-                }
-                FileType::Pdf if file.name.ends_with(".pdf") => {
-                    let f_path = file_path.to_string_lossy().to_string();
+        Ok(())
+    }
+
+    async fn regenerate(&self, command: RegeneratePreviewsCommand) -> Result<u64, DataError> {
+        let queued = self
+            .preview_job_repo
+            .enqueue_matching(&command.file_ids, command.only_failed)
+            .await?;
+
+        self.wake.notify_waiters();
 
-                    match Self::generate_pdf_preview(&f_path, &preview_path).await {
-                        Ok(_) => Ok(Ok(())),
-                        Err(e) => Ok(Err(e)),
+        Ok(queued)
+    }
+
+    async fn run_workers(&self, concurrency: usize) {
+        futures::future::join_all((0..concurrency.max(1)).map(|_| self.work())).await;
+    }
+}
+
+impl PreviewServiceImpl {
+    async fn work(&self) {
+        loop {
+            match self.preview_job_repo.claim_next().await {
+                Ok(Some(job)) => {
+                    let file_id = job.file_id;
+
+                    if let Err(e) = self.process(job).await {
+                        tracing::error!(
+                            "Failed to process preview job for file {}: {:?}",
+                            file_id,
+                            e
+                        );
                     }
                 }
+                Ok(None) => {
+                    tokio::select! {
+                        _ = self.wake.notified() => {}
+                        _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to claim a preview job: {:?}", e);
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    async fn process(&self, job: PreviewJob) -> Result<(), DataError> {
+        // The file went away while it waited, there is nothing to show a preview for
+        let Some(file) = self
+            .file_repo
+            .get_by_id(job.file_id)
+            .await?
+            .filter(|f| !f.is_deleted)
+        else {
+            return self.preview_job_repo.complete(&job).await;
+        };
+
+        match self.generate(&file).await {
+            Ok(status) => {
+                self.file_repo.set_preview_status(&file, status).await?;
+                self.preview_job_repo.complete(&job).await
+            }
+            Err(error) if job.attempts < MAX_ATTEMPTS => {
+                let delay = Self::retry_delay(job.attempts);
 
-                // For text/unknown, we just return "Success" (Ok) doing nothing
-                _ => Ok(Ok(())),
-            };
-
-            match thread_result {
-                Err(join_err) => {
-                    // TODO: send to admin console
-                    eprintln!(
-                        "CRITICAL: Preview thread crashed for file {}: {}",
-                        file.id, join_err
-                    );
+                tracing::warn!(
+                    "Preview generation failed for file {} (attempt {}), retrying in {}s: {}",
+                    file.id,
+                    job.attempts,
+                    delay.as_secs(),
+                    error
+                );
+
+                let run_after = OffsetDateTime::now_utc() + delay;
+
+                self.preview_job_repo.retry(&job, run_after, &error).await
+            }
+            Err(error) => {
+                tracing::error!(
+                    "Preview generation failed for file {} after {} attempts: {}",
+                    file.id,
+                    job.attempts,
+                    error
+                );
+
+                self.file_repo
+                    .set_preview_status(&file, PreviewStatus::Failed)
+                    .await?;
+                self.preview_job_repo.complete(&job).await?;
+
+                let event = FilePreviewFailedEvent::new(
+                    file.id,
+                    file.owner_id,
+                    file.name.clone(),
+                    job.attempts,
+                    error,
+                    OffsetDateTime::now_utc(),
+                );
+
+                if let Err(e) = self.publisher.publish(&event).await {
+                    tracing::error!("Failed to report a preview failure: {:?}", e);
                 }
-                Ok(logic_result) => {
-                    match logic_result {
-                        Ok(_) => {
-                            // TODO: Maybe set some kind of flag in the database for a given file
-                            println!("Preview generated for {}", file.id);
-                        }
-                        Err(app_err) => {
-                            // TODO: Send error to admin console
-                            eprintln!(
-                                "Preview generation failed for file {}: {}",
-                                file.id, app_err
-                            );
+
+                Ok(())
+            }
+        }
+    }
+
+    // Doubles with every failed run, so a tool that is down for a while is not hammered
+    fn retry_delay(attempts: i32) -> Duration {
+        let exponent = (attempts - 1).clamp(0, 16) as u32;
+
+        RETRY_BASE_DELAY
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(MAX_RETRY_DELAY)
+    }
+
+    // Archived content has to be unpacked before anything can be made of it
+    fn is_supported(file: &File) -> bool {
+        if file.is_archived() {
+            return false;
+        }
+
+        match file.file_type {
+            FileType::Image | FileType::Video | FileType::Audio => true,
+            FileType::Pdf => file.name.ends_with(".pdf"),
+            _ => false,
+        }
+    }
+
+    async fn generate(&self, file: &File) -> Result<PreviewStatus, String> {
+        if !Self::is_supported(file) {
+            return Ok(PreviewStatus::Unsupported);
+        }
+
+        let storage_key = file.storage_key();
+        let preview_key = file.preview_key();
+
+        // Files sharing a blob share its keys, so every run stages under an id of its own
+        let staging_id = Uuid::new_v4();
+        let preview_path = self
+            .staging_path
+            .join(PREVIEW_STAGING_DIR)
+            .join(format!("{}.preview", staging_id));
+
+        // The tools below need a local file, remote content is fetched into the staging area first
+        let (file_path, fetched_path) = match self.storage.local_path(&storage_key) {
+            Some(path) => (path, None),
+            None => {
+                let fetched_path = self
+                    .staging_path
+                    .join(PREVIEW_STAGING_DIR)
+                    .join(format!("{}.tmp", staging_id));
+
+                Self::fetch_content(self.storage.as_ref(), &storage_key, &fetched_path).await?;
+
+                (fetched_path.clone(), Some(fetched_path))
+            }
+        };
+
+        if let Some(parent) = preview_path.parent() {
+            let _ = tokio::fs::create_dir_all(parent).await;
+        }
+
+        let ffmpeg_binary = "/usr/lib/jellyfin-ffmpeg/ffmpeg";
+
+        let thread_result = match file.file_type {
+            FileType::Image => {
+                let (f_path, p_path) = (file_path.clone(), preview_path.clone());
+
+                task::spawn_blocking(move || Self::generate_image_preview(&f_path, &p_path)).await
+            }
+            FileType::Video => {
+                let f_path = file_path.to_string_lossy().to_string();
+                let p_path = preview_path.to_string_lossy().to_string();
+
+                let video_result =
+                    match Self::try_extract_cover(ffmpeg_binary, &f_path, &p_path).await {
+                        Ok(_) => Ok(()),
+                        Err(_) => {
+                            match Self::extract_frame_gpu(ffmpeg_binary, &f_path, &p_path).await {
+                                Ok(_) => Ok(()),
+                                Err(gpu_err) => {
+                                    tracing::warn!(
+                                        "GPU preview failed for {}: {}. Retrying with CPU",
+                                        file.id,
+                                        gpu_err
+                                    );
+                                    Self::extract_frame_cpu(ffmpeg_binary, &f_path, &p_path).await
+                                }
+                            }
                         }
-                    }
+                    };
+
+                Ok(video_result)
+            }
+            FileType::Audio => {
+                let f_path = file_path.to_string_lossy().to_string();
+                let p_path = preview_path.to_string_lossy().to_string();
+
+                let _ = Self::try_extract_cover(ffmpeg_binary, &f_path, &p_path).await;
+                Ok(Ok(()))
+            }
+            FileType::Pdf => {
+                let f_path = file_path.to_string_lossy().to_string();
+
+                match Self::generate_pdf_preview(&f_path, &preview_path).await {
+                    Ok(_) => Ok(Ok(())),
+                    Err(e) => Ok(Err(e)),
                 }
             }
-        });
+            _ => Ok(Ok(())),
+        };
+
+        if let Some(fetched_path) = fetched_path {
+            let _ = tokio::fs::remove_file(fetched_path).await;
+        }
+
+        let generated = match thread_result {
+            Err(join_err) => Err(format!("Preview thread crashed: {}", join_err)),
+            Ok(result) => result,
+        };
+
+        if let Err(e) = generated {
+            let _ = tokio::fs::remove_file(&preview_path).await;
+            return Err(e);
+        }
+
+        // Not every file yields a preview, e.g. audio without a cover
+        if !tokio::fs::try_exists(&preview_path).await.unwrap_or(false) {
+            return Ok(PreviewStatus::Unsupported);
+        }
+
+        if let Err(e) = self.storage.put_file(&preview_key, &preview_path).await {
+            let _ = tokio::fs::remove_file(&preview_path).await;
+            return Err(format!("Failed to store preview: {:?}", e));
+        }
+
+        tracing::debug!("Preview generated for {}", file.id);
+
+        Ok(PreviewStatus::Ready)
+    }
+
+    async fn fetch_content(
+        storage: &dyn StorageBackend,
+        key: &str,
+        path: &Path,
+    ) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        let mut reader = storage
+            .get_range(key, None)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let mut dest_file = tokio::fs::File::create(path)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if let Err(e) = tokio::io::copy(&mut reader, &mut dest_file).await {
+            let _ = tokio::fs::remove_file(path).await;
+            return Err(e.to_string());
+        }
+
+        Ok(())
     }
-}
 
-impl PreviewServiceImpl {
     async fn generate_pdf_preview(input: &str, output_path: &PathBuf) -> Result<(), String> {
         let parent = output_path.parent().ok_or("Invalid parent dir")?;
         let temp_prefix = output_path
@@ -142,7 +374,11 @@ impl PreviewServiceImpl {
     }
 
     fn generate_image_preview(input_path: &PathBuf, output_path: &PathBuf) -> Result<(), String> {
-        let img = image::open(input_path)
+        // Stored content is named after its blob, so the format is guessed from the bytes instead of the extension
+        let img = ImageReader::open(input_path)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(|e| format!("Failed to read image: {}", e))?
+            .decode()
             .map_err(|e| format!("Corrupt or unsupported image format: {}", e))?;
 
         let width = img.width() as f32;
@@ -0,0 +1,388 @@
+use crate::data::reconciliation_options::ReconciliationOptions;
+use crate::data::reconciliation_report::ReconciliationReport;
+use crate::db::blob_repository::BlobRepository;
+use crate::db::file_repository::FileRepository;
+use crate::db::file_version_repository::FileVersionRepository;
+use crate::db::storage_profile_repository::StorageProfileRepository;
+use crate::events::rabbitmq::RabbitMqPublisher;
+use crate::helpers::data_error::DataError;
+use crate::storage::local_storage_backend::LocalStorageBackend;
+use crate::storage::storage_backend::{StorageBackend, StoredObject};
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::blob::Blob;
+use homelab_core::events::UserUpdatedEvent;
+use homelab_core::file::{File, UploadStatus};
+use sqlx::types::time::OffsetDateTime;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait ReconciliationService: Send + Sync {
+    async fn reconcile(
+        &self,
+        options: ReconciliationOptions,
+    ) -> Result<ReconciliationReport, DataError>;
+}
+
+#[derive(new)]
+pub struct ReconciliationServiceImpl {
+    file_repo: Arc<dyn FileRepository>,
+    storage_profile_repo: Arc<dyn StorageProfileRepository>,
+    blob_repo: Arc<dyn BlobRepository>,
+    file_version_repo: Arc<dyn FileVersionRepository>,
+    storage: Arc<dyn StorageBackend>,
+    storage_path: PathBuf,
+    publisher: Arc<RabbitMqPublisher>,
+}
+
+#[derive(Debug, PartialEq)]
+enum StoredEntryKind {
+    Blob,
+    SharedBlob,
+    Temp,
+    Partial,
+    Preview,
+}
+
+impl StoredEntryKind {
+    // Unfinished content never leaves the local staging area, everything else is kept by the storage backend
+    fn is_staged(&self) -> bool {
+        matches!(self, StoredEntryKind::Temp | StoredEntryKind::Partial)
+    }
+}
+
+#[derive(Debug)]
+struct StoredEntry {
+    key: String,
+    file_id: Uuid,
+    kind: StoredEntryKind,
+    is_stale: bool,
+}
+
+impl StoredEntry {
+    // Everything in the storage tree is named after a file id, `<id>[.ext][.part|.tmp|.preview]`,
+    // or after a blob id, `<id>.blob|.preview`
+    fn parse(object: StoredObject, stale_after: Duration) -> Option<Self> {
+        let name = object.key.rsplit('/').next()?.to_string();
+        let (stem, suffix) = name.split_once('.').unwrap_or((name.as_str(), ""));
+        let file_id = Uuid::parse_str(stem).ok()?;
+
+        let kind = match suffix {
+            s if s == "tmp" || s.ends_with(".tmp") => StoredEntryKind::Temp,
+            "blob" => StoredEntryKind::SharedBlob,
+            "preview" => StoredEntryKind::Preview,
+            s if s == "part" || s.ends_with(".part") => StoredEntryKind::Partial,
+            _ => StoredEntryKind::Blob,
+        };
+
+        let is_stale = object
+            .modified
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= stale_after);
+
+        Some(Self {
+            key: object.key,
+            file_id,
+            kind,
+            is_stale,
+        })
+    }
+}
+
+#[async_trait]
+impl ReconciliationService for ReconciliationServiceImpl {
+    async fn reconcile(
+        &self,
+        options: ReconciliationOptions,
+    ) -> Result<ReconciliationReport, DataError> {
+        let files: HashMap<Uuid, File> = self
+            .file_repo
+            .get_all_including_deleted()
+            .await?
+            .into_iter()
+            .map(|f| (f.id, f))
+            .collect();
+
+        let blobs: HashMap<Uuid, Blob> = self
+            .blob_repo
+            .get_all()
+            .await?
+            .into_iter()
+            .map(|b| (b.id, b))
+            .collect();
+
+        let entries = self.scan_storage(options.stale_after).await?;
+
+        let stored_keys: HashSet<&str> = entries
+            .iter()
+            .filter(|e| !e.kind.is_staged())
+            .map(|e| e.key.as_str())
+            .collect();
+
+        let mut report = ReconciliationReport::default();
+
+        let stuck_before = OffsetDateTime::now_utc() - options.stale_after;
+
+        let stuck_uploads: HashSet<Uuid> = files
+            .values()
+            .filter(|f| f.upload_status == UploadStatus::Pending && f.created_at < stuck_before)
+            .map(|f| f.id)
+            .collect();
+
+        report.stuck_uploads = stuck_uploads.iter().cloned().collect();
+
+        let versions = self.file_version_repo.get_all().await?;
+
+        let referenced_blobs: HashSet<Uuid> = files
+            .values()
+            .filter_map(|f| f.blob_id)
+            .chain(versions.iter().map(|v| v.blob_id))
+            .collect();
+
+        // Left behind when a blob was acquired but the file never got switched over to it
+        report.unreferenced_blobs = blobs
+            .values()
+            .filter(|b| !referenced_blobs.contains(&b.id) && b.created_at < stuck_before)
+            .map(|b| b.id)
+            .collect();
+
+        let mut blobs_by_id: HashMap<Uuid, Vec<&StoredEntry>> = HashMap::new();
+
+        for entry in entries.iter().filter(|e| e.kind == StoredEntryKind::Blob) {
+            blobs_by_id.entry(entry.file_id).or_default().push(entry);
+        }
+
+        let mut misplaced: Vec<(&File, String)> = Vec::new();
+
+        for file in files.values() {
+            if file.upload_status != UploadStatus::Completed {
+                continue;
+            }
+
+            if stored_keys.contains(file.storage_key().as_str()) {
+                continue;
+            }
+
+            match blobs_by_id.get(&file.id) {
+                // The blob is still on disk under an older name, e.g. after a rename that changed the extension
+                Some(candidates) if file.blob_id.is_none() => {
+                    if let Some(candidate) = candidates.iter().find(|e| e.is_stale) {
+                        misplaced.push((file, candidate.key.clone()));
+                    }
+                }
+                // Soft-deleted rows are picked up by the regular cleanup
+                _ if !file.is_deleted => report.missing_blobs.push(file.id),
+                _ => {}
+            }
+        }
+
+        report.misplaced_blobs = misplaced.iter().map(|(f, _)| f.id).collect();
+
+        for entry in entries.iter().filter(|e| e.is_stale) {
+            if stuck_uploads.contains(&entry.file_id) {
+                continue;
+            }
+
+            let file = files.get(&entry.file_id);
+
+            if file.is_some_and(|f| f.storage_key() == entry.key) {
+                continue;
+            }
+
+            match entry.kind {
+                StoredEntryKind::Blob => {
+                    let is_leftover = file.is_none_or(|f| {
+                        stored_keys.contains(f.storage_key().as_str())
+                            || f.upload_status != UploadStatus::Completed
+                    });
+
+                    if is_leftover {
+                        report.orphan_blobs.push(entry.key.clone());
+                    }
+                }
+                StoredEntryKind::SharedBlob => {
+                    if !blobs.contains_key(&entry.file_id) {
+                        report.orphan_blobs.push(entry.key.clone());
+                    }
+                }
+                StoredEntryKind::Temp => report.stale_temp_files.push(entry.key.clone()),
+                StoredEntryKind::Partial => {
+                    if file.is_none_or(|f| f.upload_status != UploadStatus::Pending) {
+                        report.orphan_partial_uploads.push(entry.key.clone());
+                    }
+                }
+                StoredEntryKind::Preview => {
+                    if file.is_none() && !blobs.contains_key(&entry.file_id) {
+                        report.stray_previews.push(entry.key.clone());
+                    }
+                }
+            }
+        }
+
+        if options.repair {
+            self.repair(&report, &files, &misplaced).await;
+            report.repaired = true;
+        }
+
+        Ok(report)
+    }
+}
+
+impl ReconciliationServiceImpl {
+    async fn scan_storage(&self, stale_after: Duration) -> Result<Vec<StoredEntry>, DataError> {
+        let mut entries = Vec::new();
+
+        for object in self.storage.list("").await? {
+            if let Some(entry) = StoredEntry::parse(object, stale_after) {
+                if !entry.kind.is_staged() {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        for object in self.staging().list("").await? {
+            if let Some(entry) = StoredEntry::parse(object, stale_after) {
+                if entry.kind.is_staged() {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn staging(&self) -> LocalStorageBackend {
+        LocalStorageBackend::new(self.storage_path.clone())
+    }
+
+    async fn repair(
+        &self,
+        report: &ReconciliationReport,
+        files: &HashMap<Uuid, File>,
+        misplaced: &[(&File, String)],
+    ) {
+        let staging = self.staging();
+
+        let stored_keys = report
+            .orphan_blobs
+            .iter()
+            .chain(report.stray_previews.iter());
+
+        for key in stored_keys {
+            Self::remove_stored_file(self.storage.as_ref(), key).await;
+        }
+
+        let staged_keys = report
+            .stale_temp_files
+            .iter()
+            .chain(report.orphan_partial_uploads.iter());
+
+        for key in staged_keys {
+            Self::remove_stored_file(&staging, key).await;
+        }
+
+        for (file, source) in misplaced {
+            if let Err(e) = self.storage.rename(source, &file.storage_key()).await {
+                tracing::error!("Failed to relink blob of file {}: {:?}", file.id, e);
+            }
+        }
+
+        for file_id in &report.stuck_uploads {
+            if let Some(file) = files.get(file_id) {
+                if let Err(e) = self.remove_stuck_upload(file).await {
+                    tracing::error!("Failed to remove stuck upload {}: {:?}", file_id, e);
+                }
+            }
+        }
+
+        for file_id in &report.missing_blobs {
+            if let Err(e) = self.remove_missing_blob_row(*file_id).await {
+                tracing::error!("Failed to remove row of missing blob {}: {:?}", file_id, e);
+            }
+        }
+
+        for blob_id in &report.unreferenced_blobs {
+            match self.blob_repo.delete_unreferenced(*blob_id).await {
+                Ok(Some(blob)) => self.remove_blob_content(&blob).await,
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::error!("Failed to remove unreferenced blob {}: {:?}", blob_id, e)
+                }
+            }
+        }
+    }
+
+    async fn remove_stuck_upload(&self, file: &File) -> Result<(), DataError> {
+        // Only deletes the row while it is still pending and hands its reservation back
+        if self.file_repo.delete_pending(file.id).await?.is_none() {
+            return Ok(());
+        }
+
+        let staging = self.staging();
+        let partial_key = format!("{}.part", file.upload_key());
+
+        Self::remove_stored_file(&staging, &partial_key).await;
+        Self::remove_stored_file(&staging, &file.upload_key()).await;
+
+        Ok(())
+    }
+
+    async fn remove_missing_blob_row(&self, file_id: Uuid) -> Result<(), DataError> {
+        // Re-read the row, the file may have been archived or re-uploaded since the scan
+        let file = match self.file_repo.get_by_id(file_id).await? {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+        if file.upload_status != UploadStatus::Completed
+            || self.storage.stat(&file.storage_key()).await?.is_some()
+        {
+            return Ok(());
+        }
+
+        let (released, versions) = self.file_repo.delete_by_ids(&[file.id]).await?;
+
+        for blob in &released {
+            self.remove_blob_content(blob).await;
+        }
+
+        // The file's versions went with it, their size is given back as well
+        let size = file.size + versions.iter().map(|v| v.size).sum::<i64>();
+
+        if let Some(sp) = self
+            .storage_profile_repo
+            .release(file.owner_id, size)
+            .await?
+        {
+            let sp_event: UserUpdatedEvent = UserUpdatedEvent::new(
+                sp.user_id.clone(),
+                None,
+                None,
+                Some(sp.allowed_storage.clone()),
+                Some(sp.taken_storage.clone()),
+                sp.is_blocked.clone(),
+            );
+
+            if let Err(e) = self.publisher.publish(&sp_event).await {
+                eprintln!("Failed to publish event: {:?}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn remove_blob_content(&self, blob: &Blob) {
+        Self::remove_stored_file(self.storage.as_ref(), &blob.preview_key()).await;
+        Self::remove_stored_file(self.storage.as_ref(), &Blob::storage_key(blob.id)).await;
+    }
+
+    async fn remove_stored_file(storage: &dyn StorageBackend, key: &str) {
+        if let Err(e) = storage.delete(key).await {
+            tracing::error!("Failed to remove {}: {:?}", key, e);
+        }
+    }
+}
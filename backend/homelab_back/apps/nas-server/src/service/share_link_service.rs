@@ -0,0 +1,259 @@
+use crate::data::create_share_link_command::CreateShareLinkCommand;
+use crate::data::share_link_content::ShareLinkContent;
+use crate::db::file_repository::FileRepository;
+use crate::db::folder_repository::FolderRepository;
+use crate::db::share_link_repository::ShareLinkRepository;
+use crate::helpers::data_error::DataError;
+use crate::service::access_control_service::{AccessControlService, FileAccessLevel};
+use crate::service::file_service::FileService;
+use crate::storage::storage_backend::ObjectReader;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::file::{File, UploadStatus};
+use homelab_core::share_link::ShareLink;
+use sqlx::types::time::OffsetDateTime;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait ShareLinkService: Send + Sync {
+    async fn create_share_link(
+        &self,
+        command: CreateShareLinkCommand,
+        owner_id: Uuid,
+    ) -> Result<ShareLink, DataError>;
+    async fn get_share_links(&self, owner_id: Uuid) -> Result<Vec<ShareLink>, DataError>;
+    async fn delete_share_link(&self, id: Uuid, caller_id: Uuid) -> Result<(), DataError>;
+    async fn get_content(
+        &self,
+        token: &str,
+        password: Option<&str>,
+        folder_id: Option<Uuid>,
+    ) -> Result<ShareLinkContent, DataError>;
+    async fn get_file_for_download(
+        &self,
+        token: &str,
+        password: Option<&str>,
+        file_id: Option<Uuid>,
+    ) -> Result<(File, ObjectReader), DataError>;
+}
+
+#[derive(new)]
+pub struct ShareLinkServiceImpl {
+    share_link_repo: Arc<dyn ShareLinkRepository>,
+    file_repo: Arc<dyn FileRepository>,
+    folder_repo: Arc<dyn FolderRepository>,
+    file_service: Arc<dyn FileService>,
+    access_control: Arc<dyn AccessControlService>,
+}
+
+#[async_trait]
+impl ShareLinkService for ShareLinkServiceImpl {
+    async fn create_share_link(
+        &self,
+        command: CreateShareLinkCommand,
+        owner_id: Uuid,
+    ) -> Result<ShareLink, DataError> {
+        if command.max_downloads.is_some_and(|max| max <= 0) {
+            return Err(DataError::ValidationError(
+                "Max downloads must be greater than zero".to_string(),
+            ));
+        }
+
+        if command
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= OffsetDateTime::now_utc())
+        {
+            return Err(DataError::ValidationError(
+                "Expiry date must be in the future".to_string(),
+            ));
+        }
+
+        let password = command.password.as_deref().filter(|p| !p.is_empty());
+
+        let share_link = match (command.file_id, command.folder_id) {
+            (Some(file_id), None) => {
+                let file = self
+                    .file_repo
+                    .get_by_id(file_id)
+                    .await?
+                    .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
+
+                self.access_control
+                    .authorize_file(owner_id, &file, FileAccessLevel::Owner)
+                    .await?;
+
+                ShareLink::new_for_file(
+                    owner_id,
+                    file.id,
+                    password,
+                    command.expires_at,
+                    command.max_downloads,
+                )
+            }
+            (None, Some(folder_id)) => {
+                let folder = self
+                    .folder_repo
+                    .get_by_id(folder_id)
+                    .await?
+                    .ok_or_else(|| DataError::EntityNotFoundException("Folder".to_string()))?;
+
+                self.access_control
+                    .authorize_folder(owner_id, &folder, FileAccessLevel::Owner)
+                    .await?;
+
+                ShareLink::new_for_folder(
+                    owner_id,
+                    folder.id,
+                    password,
+                    command.expires_at,
+                    command.max_downloads,
+                )
+            }
+            _ => {
+                return Err(DataError::ValidationError(
+                    "A share link must point to either a file or a folder".to_string(),
+                ));
+            }
+        }
+        .map_err(DataError::EntityCreationError)?;
+
+        self.share_link_repo.create(share_link).await
+    }
+
+    async fn get_share_links(&self, owner_id: Uuid) -> Result<Vec<ShareLink>, DataError> {
+        self.share_link_repo.get_all_for_owner(owner_id).await
+    }
+
+    async fn delete_share_link(&self, id: Uuid, caller_id: Uuid) -> Result<(), DataError> {
+        let share_link = self
+            .share_link_repo
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Share link".to_string()))?;
+
+        if share_link.owner_id != caller_id {
+            return Err(DataError::PermissionDeniedError(format!(
+                "share link {}",
+                share_link.id
+            )));
+        }
+
+        self.share_link_repo.delete_by_id(share_link.id).await
+    }
+
+    async fn get_content(
+        &self,
+        token: &str,
+        password: Option<&str>,
+        folder_id: Option<Uuid>,
+    ) -> Result<ShareLinkContent, DataError> {
+        let share_link = self.get_valid_link(token, password).await?;
+
+        if let Some(file_id) = share_link.file_id {
+            let file = self.get_shared_file(file_id).await?;
+
+            return Ok(ShareLinkContent::new(Some(file), Vec::new(), Vec::new()));
+        }
+
+        let root_id = share_link
+            .folder_id
+            .ok_or_else(|| DataError::UnknownError("Share link has no target".to_string()))?;
+
+        let target_id = folder_id.unwrap_or(root_id);
+
+        self.ensure_in_shared_tree(target_id, root_id).await?;
+
+        let folders = self.folder_repo.get_children_by_id(target_id).await?;
+
+        let files = self
+            .folder_repo
+            .get_by_folder_id(target_id)
+            .await?
+            .into_iter()
+            .filter(|f| f.upload_status == UploadStatus::Completed)
+            .collect();
+
+        Ok(ShareLinkContent::new(None, folders, files))
+    }
+
+    async fn get_file_for_download(
+        &self,
+        token: &str,
+        password: Option<&str>,
+        file_id: Option<Uuid>,
+    ) -> Result<(File, ObjectReader), DataError> {
+        let share_link = self.get_valid_link(token, password).await?;
+
+        let file = match (share_link.file_id, share_link.folder_id, file_id) {
+            (Some(shared_id), _, None) => self.get_shared_file(shared_id).await?,
+            (Some(shared_id), _, Some(requested_id)) if shared_id == requested_id => {
+                self.get_shared_file(shared_id).await?
+            }
+            (None, Some(root_id), Some(requested_id)) => {
+                let file = self.get_shared_file(requested_id).await?;
+
+                self.ensure_in_shared_tree(file.parent_folder_id, root_id)
+                    .await?;
+
+                file
+            }
+            _ => return Err(DataError::EntityNotFoundException("File".to_string())),
+        };
+
+        // The link acts on behalf of its owner, so streaming goes through the owner's access rights
+        let download = self
+            .file_service
+            .get_file_for_streaming(file.id, share_link.owner_id, None)
+            .await?;
+
+        self.share_link_repo
+            .register_download(share_link.id)
+            .await?
+            .ok_or(DataError::ShareLinkUnavailableError)?;
+
+        Ok(download)
+    }
+}
+
+impl ShareLinkServiceImpl {
+    async fn get_valid_link(
+        &self,
+        token: &str,
+        password: Option<&str>,
+    ) -> Result<ShareLink, DataError> {
+        let share_link = self
+            .share_link_repo
+            .get_by_token(token)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Share link".to_string()))?;
+
+        if share_link.is_expired() || !share_link.has_downloads_left() {
+            return Err(DataError::ShareLinkUnavailableError);
+        }
+
+        if !share_link.verify_password(password) {
+            return Err(DataError::InvalidSharePasswordError);
+        }
+
+        Ok(share_link)
+    }
+
+    async fn get_shared_file(&self, file_id: Uuid) -> Result<File, DataError> {
+        self.file_repo
+            .get_by_id(file_id)
+            .await?
+            .filter(|f| f.upload_status == UploadStatus::Completed)
+            .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))
+    }
+
+    async fn ensure_in_shared_tree(&self, folder_id: Uuid, root_id: Uuid) -> Result<(), DataError> {
+        let ancestors = self.folder_repo.get_ancestor_ids(folder_id).await?;
+
+        if !ancestors.contains(&root_id) {
+            return Err(DataError::EntityNotFoundException("Folder".to_string()));
+        }
+
+        Ok(())
+    }
+}
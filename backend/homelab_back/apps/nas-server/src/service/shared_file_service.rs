@@ -1,12 +1,18 @@
 use crate::data::create_shared_file_command::CreateSharedFileCommand;
+use crate::data::create_shared_folder_command::CreateSharedFolderCommand;
 use crate::db::file_repository::FileRepository;
+use crate::db::folder_repository::FolderRepository;
 use crate::db::shared_file_repository::SharedFileRepository;
+use crate::db::shared_folder_repository::SharedFolderRepository;
 use crate::db::storage_profile_repository::StorageProfileRepository;
 use crate::helpers::data_error::DataError;
+use crate::service::access_control_service::{AccessControlService, FileAccessLevel};
 use async_trait::async_trait;
 use derive_new::new;
 use homelab_core::file::File;
-use homelab_core::shared_file::{SharedFile, SharedFileAccessType};
+use homelab_core::folder::Folder;
+use homelab_core::shared_file::SharedFile;
+use homelab_core::shared_folder::SharedFolder;
 use homelab_core::storage_profile::StorageProfile;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -16,11 +22,21 @@ pub trait SharedFileService: Send + Sync {
     async fn create_shared_file(
         &self,
         command: CreateSharedFileCommand,
+        owner_id: Uuid,
     ) -> Result<SharedFile, DataError>;
     async fn get_all_shared_files_per_user(
         &self,
         user_id: Uuid,
     ) -> Result<Vec<SharedFile>, DataError>;
+    async fn create_shared_folder(
+        &self,
+        command: CreateSharedFolderCommand,
+        owner_id: Uuid,
+    ) -> Result<SharedFolder, DataError>;
+    async fn get_all_shared_folders_per_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<SharedFolder>, DataError>;
 }
 
 #[derive(new)]
@@ -28,6 +44,9 @@ pub struct SharedFileServiceImpl {
     shared_file_repository: Arc<dyn SharedFileRepository>,
     storage_profile_repository: Arc<dyn StorageProfileRepository>,
     file_repository: Arc<dyn FileRepository>,
+    shared_folder_repository: Arc<dyn SharedFolderRepository>,
+    folder_repository: Arc<dyn FolderRepository>,
+    access_control: Arc<dyn AccessControlService>,
 }
 
 #[async_trait]
@@ -35,6 +54,7 @@ impl SharedFileService for SharedFileServiceImpl {
     async fn create_shared_file(
         &self,
         command: CreateSharedFileCommand,
+        owner_id: Uuid,
     ) -> Result<SharedFile, DataError> {
         let sp: StorageProfile = self
             .storage_profile_repository
@@ -44,7 +64,7 @@ impl SharedFileService for SharedFileServiceImpl {
 
         let owner_sp: StorageProfile = self
             .storage_profile_repository
-            .get_by_id(command.owner_id)
+            .get_by_id(owner_id)
             .await?
             .ok_or_else(|| DataError::EntityNotFoundException("User".to_string()))?;
 
@@ -54,12 +74,35 @@ impl SharedFileService for SharedFileServiceImpl {
             .await?
             .ok_or_else(|| DataError::EntityNotFoundException("File".to_string()))?;
 
+        // Only the owner can hand out access to a file
+        self.access_control
+            .authorize_file(owner_sp.user_id, &shared_file, FileAccessLevel::Owner)
+            .await?;
+
+        if sp.user_id == owner_sp.user_id {
+            return Err(DataError::ValidationError(
+                "Cannot share a file with its owner".to_string(),
+            ));
+        }
+
+        // Sharing the same file with the same user again only changes the access type
+        if let Some(existing) = self
+            .shared_file_repository
+            .get_for_user_and_file(sp.user_id, shared_file.id)
+            .await?
+        {
+            return self
+                .shared_file_repository
+                .update_access_type(existing.id, command.access_type)
+                .await;
+        }
+
         let shared_file = SharedFile::new(
             Uuid::new_v4(),
             shared_file.id,
             sp.user_id,
             owner_sp.user_id,
-            SharedFileAccessType::ReadOnly,
+            command.access_type,
         );
 
         self.shared_file_repository
@@ -73,4 +116,68 @@ impl SharedFileService for SharedFileServiceImpl {
     ) -> Result<Vec<SharedFile>, DataError> {
         self.shared_file_repository.get_all_for_user(user_id).await
     }
+
+    async fn create_shared_folder(
+        &self,
+        command: CreateSharedFolderCommand,
+        owner_id: Uuid,
+    ) -> Result<SharedFolder, DataError> {
+        let sp: StorageProfile = self
+            .storage_profile_repository
+            .get_by_id(command.user_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("User".to_string()))?;
+
+        let folder: Folder = self
+            .folder_repository
+            .get_by_id(command.folder_id)
+            .await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Folder".to_string()))?;
+
+        self.access_control
+            .authorize_folder(owner_id, &folder, FileAccessLevel::Owner)
+            .await?;
+
+        if sp.user_id == owner_id {
+            return Err(DataError::ValidationError(
+                "Cannot share a folder with its owner".to_string(),
+            ));
+        }
+
+        if folder.parent_folder_id.is_none() {
+            return Err(DataError::ValidationError(
+                "Cannot share a root folder".to_string(),
+            ));
+        }
+
+        if let Some(existing) = self
+            .shared_folder_repository
+            .get_for_user_and_folder(sp.user_id, folder.id)
+            .await?
+        {
+            return self
+                .shared_folder_repository
+                .update_access_type(existing.id, command.access_type)
+                .await;
+        }
+
+        let shared_folder = SharedFolder::new(
+            Uuid::new_v4(),
+            folder.id,
+            sp.user_id,
+            owner_id,
+            command.access_type,
+        );
+
+        self.shared_folder_repository.create(&shared_folder).await
+    }
+
+    async fn get_all_shared_folders_per_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<SharedFolder>, DataError> {
+        self.shared_folder_repository
+            .get_all_for_user(user_id)
+            .await
+    }
 }
@@ -2,21 +2,33 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use derive_new::new;
 use uuid::Uuid;
-use homelab_core::events::{UserBlockedEvent, UserCreatedEvent};
+use homelab_core::blob::Blob;
+use homelab_core::events::{UserBlockedEvent, UserCreatedEvent, UserUpdatedEvent};
 use homelab_core::storage_profile::StorageProfile;
+use crate::data::usage_recalculation_summary::UsageRecalculationSummary;
+use crate::db::file_repository::FileRepository;
+use crate::db::file_version_repository::FileVersionRepository;
 use crate::db::storage_profile_repository::StorageProfileRepository;
+use crate::events::rabbitmq::RabbitMqPublisher;
 use crate::helpers::data_error::DataError;
+use crate::storage::storage_backend::StorageBackend;
 
 #[async_trait]
 pub trait StorageProfileService: Send + Sync {
     async fn save_storage_profile (&self, event: UserCreatedEvent) -> Result<StorageProfile, DataError>;
     async fn toggle_storage_profile(&self, event: UserBlockedEvent) -> Result<(), DataError>;
     async fn get_by_id(&self, id: Uuid) -> Result<Option<StorageProfile>, DataError>;
+    async fn recalculate_usage(&self, user_id: Uuid, from_disk: bool) -> Result<StorageProfile, DataError>;
+    async fn recalculate_all_usage(&self, from_disk: bool) -> Result<UsageRecalculationSummary, DataError>;
 }
 
 #[derive(new)]
 pub struct StorageProfileServiceImpl {
     storage_profile_repo: Arc<dyn StorageProfileRepository>,
+    file_repo: Arc<dyn FileRepository>,
+    file_version_repo: Arc<dyn FileVersionRepository>,
+    storage: Arc<dyn StorageBackend>,
+    publisher: Arc<RabbitMqPublisher>,
 }
 
 #[async_trait]
@@ -26,6 +38,7 @@ impl StorageProfileService for StorageProfileServiceImpl {
             event.user_id,
             event.default_storage,
             0i64,
+            0i64,
             false
         );
 
@@ -46,4 +59,101 @@ impl StorageProfileService for StorageProfileServiceImpl {
     async fn get_by_id(&self, id: Uuid) -> Result<Option<StorageProfile>, DataError> {
         self.storage_profile_repo.get_by_id(id).await
     }
-}
\ No newline at end of file
+
+    async fn recalculate_usage(&self, user_id: Uuid, from_disk: bool) -> Result<StorageProfile, DataError> {
+        let (sp, _) = self.recalculate(user_id, from_disk).await?;
+
+        Ok(sp)
+    }
+
+    async fn recalculate_all_usage(&self, from_disk: bool) -> Result<UsageRecalculationSummary, DataError> {
+        let profiles = self.storage_profile_repo.get_all().await?;
+
+        let mut summary = UsageRecalculationSummary::default();
+
+        for profile in profiles {
+            match self.recalculate(profile.user_id, from_disk).await {
+                Ok((_, corrected)) => {
+                    summary.checked += 1;
+
+                    if corrected {
+                        summary.corrected += 1;
+                    }
+                }
+                Err(e) => tracing::error!("Failed to recalculate usage of {}: {:?}", profile.user_id, e),
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+impl StorageProfileServiceImpl {
+    // Returns the corrected profile and whether the stored counters had drifted
+    async fn recalculate(&self, user_id: Uuid, from_disk: bool) -> Result<(StorageProfile, bool), DataError> {
+        let before = self.storage_profile_repo.get_by_id(user_id).await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Storage Profile".to_string()))?;
+
+        let measured = if from_disk {
+            Some(self.measure_disk_usage(user_id).await?)
+        } else {
+            None
+        };
+
+        let after = self.storage_profile_repo.recalculate(user_id, measured).await?
+            .ok_or_else(|| DataError::EntityNotFoundException("Storage Profile".to_string()))?;
+
+        let corrected = before.taken_storage != after.taken_storage
+            || before.reserved_storage != after.reserved_storage;
+
+        if before.taken_storage != after.taken_storage {
+            tracing::warn!(
+                "Storage usage of {} drifted from {} to {} bytes",
+                user_id,
+                before.taken_storage,
+                after.taken_storage
+            );
+
+            let sp_event: UserUpdatedEvent = UserUpdatedEvent::new(
+                after.user_id.clone(),
+                None,
+                None,
+                Some(after.allowed_storage.clone()),
+                Some(after.taken_storage.clone()),
+                after.is_blocked.clone(),
+            );
+
+            if let Err(e) = self.publisher.publish(&sp_event).await {
+                eprintln!("Failed to publish event: {:?}", e);
+            }
+        }
+
+        Ok((after, corrected))
+    }
+
+    // Content that is gone from the storage does not take any space
+    async fn measure_disk_usage(&self, user_id: Uuid) -> Result<i64, DataError> {
+        let files = self.file_repo.get_all_completed_by_owner(user_id).await?;
+
+        let mut total = 0i64;
+
+        for file in files {
+            if let Ok(Some(object)) = self.storage.stat(&file.storage_key()).await {
+                total += object.size as i64;
+            }
+        }
+
+        // Versions are charged as well, each one points at a blob of its own
+        let versions = self.file_version_repo.get_all_by_owner(user_id).await?;
+
+        for version in versions {
+            let key = Blob::storage_key(version.blob_id);
+
+            if let Ok(Some(object)) = self.storage.stat(&key).await {
+                total += object.size as i64;
+            }
+        }
+
+        Ok(total)
+    }
+}
@@ -0,0 +1,278 @@
+use crate::helpers::data_error::DataError;
+use crate::storage::encryption::{
+    is_sealed, plaintext_size, segment_count, MasterKey, ObjectCipher, HEADER_LEN,
+    SEALED_SEGMENT_LEN, SEGMENT_LEN,
+};
+use crate::storage::storage_backend::{ObjectReader, StorageBackend, StoredObject};
+use async_trait::async_trait;
+use bytes::Bytes;
+use derive_new::new;
+use futures::stream::{self, StreamExt};
+use std::io;
+use std::ops::Range;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tokio_util::io::StreamReader;
+
+/// Encrypts everything written to the wrapped backend and decrypts it again on read.
+/// Content stored before encryption was turned on is passed through as it is.
+#[derive(new)]
+pub struct EncryptedStorageBackend {
+    inner: Arc<dyn StorageBackend>,
+    master_key: MasterKey,
+}
+
+struct SealState {
+    reader: ObjectReader,
+    cipher: ObjectCipher,
+    position: u32,
+    current: Option<Vec<u8>>,
+    finished: bool,
+}
+
+struct OpenState {
+    reader: ObjectReader,
+    cipher: ObjectCipher,
+    position: u64,
+    segments: u64,
+    skip: usize,
+    remaining: u64,
+}
+
+#[async_trait]
+impl StorageBackend for EncryptedStorageBackend {
+    async fn put_stream(&self, key: &str, reader: ObjectReader) -> Result<u64, DataError> {
+        let (cipher, header) = ObjectCipher::generate(&self.master_key)?;
+
+        let state = SealState {
+            reader,
+            cipher,
+            position: 0,
+            current: None,
+            finished: false,
+        };
+
+        let sealed = stream::once(async move { Ok(Bytes::from(header)) }).chain(stream::unfold(
+            state,
+            |mut state| async move {
+                if state.finished {
+                    return None;
+                }
+
+                match Self::seal_next(&mut state).await {
+                    Ok(segment) => Some((Ok(Bytes::from(segment)), state)),
+                    Err(e) => {
+                        state.finished = true;
+                        Some((Err(e), state))
+                    }
+                }
+            },
+        ));
+
+        self.inner
+            .put_stream(key, Box::new(StreamReader::new(Box::pin(sealed))))
+            .await
+    }
+
+    async fn get_range(
+        &self,
+        key: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<ObjectReader, DataError> {
+        let object = self
+            .inner
+            .stat(key)
+            .await?
+            .ok_or_else(|| DataError::IOError(format!("Stored object {} is missing", key)))?;
+
+        let cipher = match self.read_cipher(&object).await? {
+            Some(cipher) => cipher,
+            None => return self.inner.get_range(key, range).await,
+        };
+
+        let size = plaintext_size(object.size);
+        let range = range.unwrap_or(0..size);
+        let (start, end) = (range.start.min(size), range.end.min(size));
+
+        if start >= end {
+            return Ok(Box::new(tokio::io::empty()));
+        }
+
+        // Only the segments covering the range are fetched, each one is authenticated on its own
+        let first = start / SEGMENT_LEN as u64;
+        let last = (end - 1) / SEGMENT_LEN as u64;
+
+        let sealed_start = HEADER_LEN as u64 + first * SEALED_SEGMENT_LEN as u64;
+        let sealed_end =
+            (HEADER_LEN as u64 + (last + 1) * SEALED_SEGMENT_LEN as u64).min(object.size);
+
+        let reader = self
+            .inner
+            .get_range(key, Some(sealed_start..sealed_end))
+            .await?;
+
+        let state = OpenState {
+            reader,
+            cipher,
+            position: first,
+            segments: segment_count(object.size),
+            skip: (start - first * SEGMENT_LEN as u64) as usize,
+            remaining: end - start,
+        };
+
+        let opened = stream::unfold(state, |mut state| async move {
+            if state.remaining == 0 {
+                return None;
+            }
+
+            match Self::open_next(&mut state).await {
+                Ok(segment) => Some((Ok(Bytes::from(segment)), state)),
+                Err(e) => {
+                    state.remaining = 0;
+                    Some((Err(e), state))
+                }
+            }
+        });
+
+        Ok(Box::new(StreamReader::new(Box::pin(opened))))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), DataError> {
+        self.inner.delete(key).await
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> Result<(), DataError> {
+        self.inner.rename(from, to).await
+    }
+
+    // The data key travels in the header, so copies stay readable
+    async fn copy(&self, from: &str, to: &str) -> Result<(), DataError> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn stat(&self, key: &str) -> Result<Option<StoredObject>, DataError> {
+        let mut object = match self.inner.stat(key).await? {
+            Some(object) => object,
+            None => return Ok(None),
+        };
+
+        if is_sealed(&self.read_header(&object).await?) {
+            object.size = plaintext_size(object.size);
+        }
+
+        Ok(Some(object))
+    }
+
+    // Sizes are left as stored, listing is only used to find stray objects
+    async fn list(&self, prefix: &str) -> Result<Vec<StoredObject>, DataError> {
+        self.inner.list(prefix).await
+    }
+}
+
+impl EncryptedStorageBackend {
+    /// Rewrites a plaintext object encrypted, returns false when there was nothing to do
+    pub async fn encrypt_in_place(&self, key: &str) -> Result<bool, DataError> {
+        let object = match self.inner.stat(key).await? {
+            Some(object) => object,
+            None => return Ok(false),
+        };
+
+        if is_sealed(&self.read_header(&object).await?) {
+            return Ok(false);
+        }
+
+        // Backends only replace the object once the new content is complete
+        let reader = self.inner.get_range(key, None).await?;
+
+        self.put_stream(key, reader).await?;
+
+        Ok(true)
+    }
+
+    async fn read_header(&self, object: &StoredObject) -> Result<Vec<u8>, DataError> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+
+        // Anything shorter cannot be encrypted, and empty objects do not support ranges
+        if object.size < HEADER_LEN as u64 {
+            return Ok(header);
+        }
+
+        self.inner
+            .get_range(&object.key, Some(0..HEADER_LEN as u64))
+            .await?
+            .read_to_end(&mut header)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        Ok(header)
+    }
+
+    async fn read_cipher(&self, object: &StoredObject) -> Result<Option<ObjectCipher>, DataError> {
+        let header = self.read_header(object).await?;
+
+        ObjectCipher::from_header(&self.master_key, &header)
+    }
+
+    // The segment after the current one is read ahead, only then it is known whether the current one is the last
+    async fn seal_next(state: &mut SealState) -> io::Result<Vec<u8>> {
+        let current = match state.current.take() {
+            Some(current) => current,
+            None => Self::read_segment(&mut state.reader, SEGMENT_LEN).await?,
+        };
+
+        let next = Self::read_segment(&mut state.reader, SEGMENT_LEN).await?;
+        let last = next.is_empty();
+
+        let sealed = state
+            .cipher
+            .seal(state.position, last, current)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+
+        if last {
+            state.finished = true;
+        } else {
+            state.position = state
+                .position
+                .checked_add(1)
+                .ok_or_else(|| io::Error::other("Object is too large"))?;
+            state.current = Some(next);
+        }
+
+        Ok(sealed)
+    }
+
+    async fn open_next(state: &mut OpenState) -> io::Result<Vec<u8>> {
+        let sealed = Self::read_segment(&mut state.reader, SEALED_SEGMENT_LEN).await?;
+
+        if sealed.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Stored content is truncated",
+            ));
+        }
+
+        let last = state.position + 1 == state.segments;
+
+        let mut segment = state
+            .cipher
+            .open(state.position as u32, last, sealed)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        segment.drain(..state.skip.min(segment.len()));
+        segment.truncate(state.remaining.min(segment.len() as u64) as usize);
+
+        state.skip = 0;
+        state.remaining -= segment.len() as u64;
+        state.position += 1;
+
+        Ok(segment)
+    }
+
+    async fn read_segment(reader: &mut ObjectReader, len: usize) -> io::Result<Vec<u8>> {
+        let mut segment = Vec::with_capacity(len);
+
+        reader.take(len as u64).read_to_end(&mut segment).await?;
+
+        Ok(segment)
+    }
+}
@@ -0,0 +1,137 @@
+use crate::helpers::data_error::DataError;
+use chacha20poly1305::aead::generic_array::GenericArray;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::stream::{NewStream, StreamBE32, StreamPrimitive};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+// Encrypted objects start with a header, `magic | key nonce | wrapped data key | stream nonce`,
+// followed by the content sealed in segments of `SEGMENT_LEN` bytes
+const MAGIC: &[u8; 8] = b"HLBLOB\x00\x01";
+const KEY_NONCE_LEN: usize = 24;
+const WRAPPED_KEY_LEN: usize = 32 + TAG_LEN;
+const STREAM_NONCE_LEN: usize = 19;
+const TAG_LEN: usize = 16;
+
+pub const HEADER_LEN: usize = MAGIC.len() + KEY_NONCE_LEN + WRAPPED_KEY_LEN + STREAM_NONCE_LEN;
+pub const SEGMENT_LEN: usize = 64 * 1024;
+pub const SEALED_SEGMENT_LEN: usize = SEGMENT_LEN + TAG_LEN;
+
+/// Wraps the per-object data keys, loaded from `ENCRYPTION_MASTER_KEY`
+pub struct MasterKey(Key);
+
+impl MasterKey {
+    pub fn from_hex(value: &str) -> Result<Self, DataError> {
+        let bytes = hex::decode(value.trim()).map_err(|_| {
+            DataError::ValidationError("Master key must be hex encoded".to_string())
+        })?;
+
+        if bytes.len() != 32 {
+            return Err(DataError::ValidationError(
+                "Master key must be 32 bytes long".to_string(),
+            ));
+        }
+
+        Ok(Self(*Key::from_slice(&bytes)))
+    }
+}
+
+/// Seals and opens the segments of a single object with its own data key
+pub struct ObjectCipher {
+    stream: StreamBE32<XChaCha20Poly1305>,
+}
+
+impl ObjectCipher {
+    // A fresh data key for every object written, returned together with the header that carries it
+    pub fn generate(master_key: &MasterKey) -> Result<(Self, Vec<u8>), DataError> {
+        let data_key = XChaCha20Poly1305::generate_key(&mut OsRng);
+        let key_nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let wrapped_key = XChaCha20Poly1305::new(&master_key.0)
+            .encrypt(&key_nonce, data_key.as_slice())
+            .map_err(|_| DataError::IOError("Failed to wrap data key".to_string()))?;
+
+        let mut stream_nonce = [0u8; STREAM_NONCE_LEN];
+        OsRng.fill_bytes(&mut stream_nonce);
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&key_nonce);
+        header.extend_from_slice(&wrapped_key);
+        header.extend_from_slice(&stream_nonce);
+
+        Ok((Self::new(&data_key, &stream_nonce), header))
+    }
+
+    // Returns None for content stored before encryption was turned on
+    pub fn from_header(master_key: &MasterKey, header: &[u8]) -> Result<Option<Self>, DataError> {
+        if !is_sealed(header) {
+            return Ok(None);
+        }
+
+        let (key_nonce, rest) = header[MAGIC.len()..HEADER_LEN].split_at(KEY_NONCE_LEN);
+        let (wrapped_key, stream_nonce) = rest.split_at(WRAPPED_KEY_LEN);
+
+        let data_key = XChaCha20Poly1305::new(&master_key.0)
+            .decrypt(XNonce::from_slice(key_nonce), wrapped_key)
+            .map_err(|_| {
+                DataError::IOError(
+                    "Failed to unwrap data key, is the master key right?".to_string(),
+                )
+            })?;
+
+        Ok(Some(Self::new(Key::from_slice(&data_key), stream_nonce)))
+    }
+
+    fn new(data_key: &Key, stream_nonce: &[u8]) -> Self {
+        Self {
+            stream: StreamBE32::from_aead(
+                XChaCha20Poly1305::new(data_key),
+                GenericArray::from_slice(stream_nonce),
+            ),
+        }
+    }
+
+    // The position and the last flag are authenticated, so segments cannot be reordered or cut off
+    pub fn seal(
+        &self,
+        position: u32,
+        last: bool,
+        mut segment: Vec<u8>,
+    ) -> Result<Vec<u8>, DataError> {
+        self.stream
+            .encrypt_in_place(position, last, &[], &mut segment)
+            .map_err(|_| DataError::IOError("Failed to encrypt content".to_string()))?;
+
+        Ok(segment)
+    }
+
+    pub fn open(
+        &self,
+        position: u32,
+        last: bool,
+        mut segment: Vec<u8>,
+    ) -> Result<Vec<u8>, DataError> {
+        self.stream
+            .decrypt_in_place(position, last, &[], &mut segment)
+            .map_err(|_| DataError::IOError("Stored content failed authentication".to_string()))?;
+
+        Ok(segment)
+    }
+}
+
+pub fn is_sealed(header: &[u8]) -> bool {
+    header.len() >= HEADER_LEN && header.starts_with(MAGIC)
+}
+
+pub fn segment_count(sealed_size: u64) -> u64 {
+    sealed_size
+        .saturating_sub(HEADER_LEN as u64)
+        .div_ceil(SEALED_SEGMENT_LEN as u64)
+}
+
+pub fn plaintext_size(sealed_size: u64) -> u64 {
+    sealed_size
+        .saturating_sub(HEADER_LEN as u64)
+        .saturating_sub(segment_count(sealed_size) * TAG_LEN as u64)
+}
@@ -0,0 +1,254 @@
+use crate::helpers::data_error::DataError;
+use crate::storage::storage_backend::{ObjectReader, StorageBackend, StoredObject};
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::constants::MB;
+use std::io::{ErrorKind, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufWriter};
+
+/// The bucketed directory tree under `ROOT_FOLDER_PATH`
+#[derive(new)]
+pub struct LocalStorageBackend {
+    root: PathBuf,
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorageBackend {
+    async fn put_stream(&self, key: &str, mut reader: ObjectReader) -> Result<u64, DataError> {
+        let path = self.root.join(key);
+
+        Self::create_parent(&path).await?;
+
+        // Readers never see a half written object, it only appears under its key once complete
+        let temp_path = PathBuf::from(format!("{}.tmp", path.display()));
+
+        let file_handle = fs::File::create(&temp_path)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        let mut writer = BufWriter::with_capacity(MB as usize, file_handle);
+
+        let written = match tokio::io::copy(&mut reader, &mut writer).await {
+            Ok(written) => written,
+            Err(e) => {
+                let _ = fs::remove_file(&temp_path).await;
+                return Err(DataError::IOError(e.to_string()));
+            }
+        };
+
+        if let Err(e) = writer.flush().await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(DataError::IOError(e.to_string()));
+        }
+
+        if let Err(e) = writer.get_ref().sync_all().await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(DataError::IOError(e.to_string()));
+        }
+
+        fs::rename(&temp_path, &path)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        Ok(written)
+    }
+
+    async fn get_range(
+        &self,
+        key: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<ObjectReader, DataError> {
+        let mut file = fs::File::open(self.root.join(key))
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        match range {
+            Some(range) => {
+                file.seek(SeekFrom::Start(range.start))
+                    .await
+                    .map_err(|e| DataError::IOError(e.to_string()))?;
+
+                Ok(Box::new(file.take(range.end.saturating_sub(range.start))))
+            }
+            None => Ok(Box::new(file)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), DataError> {
+        let path = self.root.join(key);
+
+        match fs::remove_file(&path).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(DataError::IOError(e.to_string())),
+        }
+
+        Self::remove_empty_buckets(&path).await;
+
+        Ok(())
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> Result<(), DataError> {
+        let source = self.root.join(from);
+        let target = self.root.join(to);
+
+        Self::create_parent(&target).await?;
+
+        fs::rename(&source, &target)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        Self::remove_empty_buckets(&source).await;
+
+        Ok(())
+    }
+
+    async fn copy(&self, from: &str, to: &str) -> Result<(), DataError> {
+        let source = self.root.join(from);
+        let target = self.root.join(to);
+
+        Self::create_parent(&target).await?;
+
+        // Linking is free on the same disk, a plain copy is the fallback for filesystems without hard links
+        match fs::hard_link(&source, &target).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(()),
+            Err(_) => {
+                let file = fs::File::open(&source)
+                    .await
+                    .map_err(|e| DataError::IOError(e.to_string()))?;
+
+                self.put_stream(to, Box::new(file)).await?;
+
+                Ok(())
+            }
+        }
+    }
+
+    async fn stat(&self, key: &str) -> Result<Option<StoredObject>, DataError> {
+        match fs::metadata(self.root.join(key)).await {
+            Ok(metadata) if metadata.is_file() => Ok(Some(StoredObject {
+                key: key.to_string(),
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+            })),
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(DataError::IOError(e.to_string())),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<StoredObject>, DataError> {
+        let mut objects = Vec::new();
+
+        for bucket1 in Self::list_buckets(&self.root).await? {
+            for bucket2 in Self::list_buckets(&bucket1).await? {
+                let mut dir = fs::read_dir(&bucket2)
+                    .await
+                    .map_err(|e| DataError::IOError(e.to_string()))?;
+
+                while let Some(item) = dir
+                    .next_entry()
+                    .await
+                    .map_err(|e| DataError::IOError(e.to_string()))?
+                {
+                    let metadata = match item.metadata().await {
+                        Ok(metadata) if metadata.is_file() => metadata,
+                        _ => continue,
+                    };
+
+                    let key = match item.path().strip_prefix(&self.root) {
+                        Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+                        Err(_) => continue,
+                    };
+
+                    if key.starts_with(prefix) {
+                        objects.push(StoredObject {
+                            key,
+                            size: metadata.len(),
+                            modified: metadata.modified().ok(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(objects)
+    }
+
+    // The staging area and the store share the disk, so finished files are simply moved into place
+    async fn put_file(&self, key: &str, path: &Path) -> Result<u64, DataError> {
+        let target = self.root.join(key);
+
+        let metadata = fs::metadata(path)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        if target != path {
+            Self::create_parent(&target).await?;
+
+            fs::rename(path, &target)
+                .await
+                .map_err(|e| DataError::IOError(e.to_string()))?;
+        }
+
+        Ok(metadata.len())
+    }
+
+    fn local_path(&self, key: &str) -> Option<PathBuf> {
+        Some(self.root.join(key))
+    }
+}
+
+impl LocalStorageBackend {
+    async fn create_parent(path: &Path) -> Result<(), DataError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| DataError::IOError(format!("Failed to create buckets: {}", e)))?;
+        }
+
+        Ok(())
+    }
+
+    async fn remove_empty_buckets(path: &Path) {
+        if let Some(bucket2) = path.parent() {
+            if fs::remove_dir(bucket2).await.is_ok() {
+                if let Some(bucket1) = bucket2.parent() {
+                    let _ = fs::remove_dir(bucket1).await;
+                }
+            }
+        }
+    }
+
+    // Only the two character hex buckets created by `File::storage_key` are looked at
+    async fn list_buckets(path: &Path) -> Result<Vec<PathBuf>, DataError> {
+        let mut buckets = Vec::new();
+
+        let mut dir = match fs::read_dir(path).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(buckets),
+            Err(e) => return Err(DataError::IOError(e.to_string())),
+        };
+
+        while let Some(item) = dir
+            .next_entry()
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?
+        {
+            let is_bucket = item
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.len() == 2 && name.chars().all(|c| c.is_ascii_hexdigit()));
+
+            if is_bucket && item.path().is_dir() {
+                buckets.push(item.path());
+            }
+        }
+
+        Ok(buckets)
+    }
+}
@@ -0,0 +1,6 @@
+pub mod encrypted_storage_backend;
+pub mod encryption;
+pub mod local_storage_backend;
+pub mod s3_storage_backend;
+pub mod storage_backend;
+pub mod storage_config;
@@ -0,0 +1,290 @@
+use crate::helpers::data_error::DataError;
+use crate::storage::storage_backend::{ObjectReader, StorageBackend, StoredObject};
+use async_trait::async_trait;
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::error::DisplayErrorContext;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use aws_sdk_s3::Client;
+use homelab_core::constants::MB;
+use std::ops::Range;
+use std::time::SystemTime;
+use tokio::io::AsyncReadExt;
+
+// S3 requires every part but the last one to be at least 5MB
+const PART_SIZE: u64 = 8 * MB as u64;
+
+/// Any S3 compatible object store, e.g. MinIO running next to the server
+pub struct S3StorageBackend {
+    client: Client,
+    bucket: String,
+}
+
+impl S3StorageBackend {
+    pub fn new(
+        endpoint: &str,
+        region: &str,
+        bucket: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> Self {
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .endpoint_url(endpoint)
+            .region(Region::new(region.to_string()))
+            .credentials_provider(Credentials::new(
+                access_key, secret_key, None, None, "homelab",
+            ))
+            // MinIO and most self hosted stores do not serve virtual-hosted style buckets
+            .force_path_style(true)
+            .build();
+
+        Self {
+            client: Client::from_conf(config),
+            bucket: bucket.to_string(),
+        }
+    }
+
+    async fn read_part(reader: &mut ObjectReader) -> Result<Vec<u8>, DataError> {
+        let mut buffer = Vec::with_capacity(PART_SIZE as usize);
+
+        reader
+            .take(PART_SIZE)
+            .read_to_end(&mut buffer)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        Ok(buffer)
+    }
+
+    async fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        first_part: Vec<u8>,
+        reader: &mut ObjectReader,
+    ) -> Result<(Vec<CompletedPart>, u64), DataError> {
+        let mut parts = Vec::new();
+        let mut written = 0u64;
+        let mut part = first_part;
+
+        while !part.is_empty() {
+            let part_number = parts.len() as i32 + 1;
+            written += part.len() as u64;
+
+            let output = self
+                .client
+                .upload_part()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(part))
+                .send()
+                .await
+                .map_err(|e| Self::map_err("upload part of", key, e))?;
+
+            parts.push(
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(output.e_tag().map(|t| t.to_string()))
+                    .build(),
+            );
+
+            part = Self::read_part(reader).await?;
+        }
+
+        Ok((parts, written))
+    }
+
+    fn map_err<E: std::error::Error>(action: &str, key: &str, e: E) -> DataError {
+        DataError::IOError(format!(
+            "Failed to {} {}: {}",
+            action,
+            key,
+            DisplayErrorContext(&e)
+        ))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for S3StorageBackend {
+    async fn put_stream(&self, key: &str, mut reader: ObjectReader) -> Result<u64, DataError> {
+        let first_part = Self::read_part(&mut reader).await?;
+
+        // Small objects fit into a single request
+        if (first_part.len() as u64) < PART_SIZE {
+            let written = first_part.len() as u64;
+
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .body(ByteStream::from(first_part))
+                .send()
+                .await
+                .map_err(|e| Self::map_err("upload", key, e))?;
+
+            return Ok(written);
+        }
+
+        let upload = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| Self::map_err("start upload of", key, e))?;
+
+        let upload_id = upload
+            .upload_id()
+            .ok_or_else(|| DataError::IOError(format!("No upload id returned for {}", key)))?
+            .to_string();
+
+        let (parts, written) = match self
+            .upload_parts(key, &upload_id, first_part, &mut reader)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                let _ = self
+                    .client
+                    .abort_multipart_upload()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .upload_id(&upload_id)
+                    .send()
+                    .await;
+                return Err(e);
+            }
+        };
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(&upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| Self::map_err("complete upload of", key, e))?;
+
+        Ok(written)
+    }
+
+    async fn get_range(
+        &self,
+        key: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<ObjectReader, DataError> {
+        let mut request = self.client.get_object().bucket(&self.bucket).key(key);
+
+        if let Some(range) = range {
+            if range.end <= range.start {
+                return Ok(Box::new(tokio::io::empty()));
+            }
+
+            request = request.range(format!("bytes={}-{}", range.start, range.end - 1));
+        }
+
+        let output = request
+            .send()
+            .await
+            .map_err(|e| Self::map_err("download", key, e))?;
+
+        Ok(Box::new(output.body.into_async_read()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), DataError> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| Self::map_err("delete", key, e))?;
+
+        Ok(())
+    }
+
+    // S3 has no rename, the object is copied server side and the original removed
+    async fn rename(&self, from: &str, to: &str) -> Result<(), DataError> {
+        self.copy(from, to).await?;
+
+        self.delete(from).await
+    }
+
+    // Only written when the target is missing, an existing target answers with 412
+    async fn copy(&self, from: &str, to: &str) -> Result<(), DataError> {
+        match self
+            .client
+            .copy_object()
+            .bucket(&self.bucket)
+            .copy_source(format!("{}/{}", self.bucket, from))
+            .key(to)
+            .if_none_match("*")
+            .send()
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) if e.raw_response().is_some_and(|r| r.status().as_u16() == 412) => Ok(()),
+            Err(e) => Err(Self::map_err("copy", from, e)),
+        }
+    }
+
+    async fn stat(&self, key: &str) -> Result<Option<StoredObject>, DataError> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(output) => Ok(Some(StoredObject {
+                key: key.to_string(),
+                size: output.content_length().unwrap_or(0).max(0) as u64,
+                modified: output
+                    .last_modified()
+                    .and_then(|t| SystemTime::try_from(*t).ok()),
+            })),
+            Err(e) if e.as_service_error().is_some_and(|se| se.is_not_found()) => Ok(None),
+            Err(e) => Err(Self::map_err("stat", key, e)),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<StoredObject>, DataError> {
+        let mut objects = Vec::new();
+
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(prefix)
+            .into_paginator()
+            .send();
+
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| Self::map_err("list", prefix, e))?;
+
+            for object in page.contents() {
+                if let Some(key) = object.key() {
+                    objects.push(StoredObject {
+                        key: key.to_string(),
+                        size: object.size().unwrap_or(0).max(0) as u64,
+                        modified: object
+                            .last_modified()
+                            .and_then(|t| SystemTime::try_from(*t).ok()),
+                    });
+                }
+            }
+        }
+
+        Ok(objects)
+    }
+}
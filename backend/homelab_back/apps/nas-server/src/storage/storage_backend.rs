@@ -0,0 +1,52 @@
+use crate::helpers::data_error::DataError;
+use async_trait::async_trait;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::fs;
+use tokio::io::AsyncRead;
+
+pub type ObjectReader = Box<dyn AsyncRead + Send + Unpin>;
+
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub key: String,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// Where file content, blobs and previews are kept. Keys are the bucketed
+/// relative paths built by `File::storage_key`, e.g. `ab/cd/<id>.blob`.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    async fn put_stream(&self, key: &str, reader: ObjectReader) -> Result<u64, DataError>;
+    async fn get_range(
+        &self,
+        key: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<ObjectReader, DataError>;
+    async fn delete(&self, key: &str) -> Result<(), DataError>;
+    async fn rename(&self, from: &str, to: &str) -> Result<(), DataError>;
+    // The source stays readable, an existing target is left as it is
+    async fn copy(&self, from: &str, to: &str) -> Result<(), DataError>;
+    async fn stat(&self, key: &str) -> Result<Option<StoredObject>, DataError>;
+    async fn list(&self, prefix: &str) -> Result<Vec<StoredObject>, DataError>;
+
+    // Moves a finished file from the local staging area into the store, the local file is gone afterwards
+    async fn put_file(&self, key: &str, path: &Path) -> Result<u64, DataError> {
+        let file = fs::File::open(path)
+            .await
+            .map_err(|e| DataError::IOError(e.to_string()))?;
+
+        let size = self.put_stream(key, Box::new(file)).await?;
+
+        let _ = fs::remove_file(path).await;
+
+        Ok(size)
+    }
+
+    // Backends that keep objects on the local disk expose them, so tools like ffmpeg can read them in place
+    fn local_path(&self, _key: &str) -> Option<PathBuf> {
+        None
+    }
+}
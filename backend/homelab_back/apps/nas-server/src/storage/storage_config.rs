@@ -0,0 +1,58 @@
+use crate::storage::encrypted_storage_backend::EncryptedStorageBackend;
+use crate::storage::encryption::MasterKey;
+use crate::storage::local_storage_backend::LocalStorageBackend;
+use crate::storage::s3_storage_backend::S3StorageBackend;
+use crate::storage::storage_backend::StorageBackend;
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// The configured backend, encrypting everything it stores once `ENCRYPTION_MASTER_KEY` is set
+pub fn build_storage_backend(root: PathBuf) -> Arc<dyn StorageBackend> {
+    let backend = build_base_storage_backend(root);
+
+    match load_master_key() {
+        Some(master_key) => Arc::new(EncryptedStorageBackend::new(backend, master_key)),
+        None => backend,
+    }
+}
+
+/// Picks the backend from `STORAGE_BACKEND` (`local` or `s3`), local storage
+/// lives under `root`, which also stays the staging area for uploads
+pub fn build_base_storage_backend(root: PathBuf) -> Arc<dyn StorageBackend> {
+    let backend = env::var("STORAGE_BACKEND")
+        .unwrap_or_else(|_| "local".to_string())
+        .to_lowercase();
+
+    match backend.as_str() {
+        "local" => Arc::new(LocalStorageBackend::new(root)),
+        "s3" => {
+            let endpoint = env::var("S3_ENDPOINT").expect("S3_ENDPOINT must be set in .env file");
+            let region = env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+            let bucket = env::var("S3_BUCKET").expect("S3_BUCKET must be set in .env file");
+            let access_key =
+                env::var("S3_ACCESS_KEY").expect("S3_ACCESS_KEY must be set in .env file");
+            let secret_key =
+                env::var("S3_SECRET_KEY").expect("S3_SECRET_KEY must be set in .env file");
+
+            Arc::new(S3StorageBackend::new(
+                &endpoint,
+                &region,
+                &bucket,
+                &access_key,
+                &secret_key,
+            ))
+        }
+        _ => panic!("Invalid STORAGE_BACKEND: {}. Use 'local' or 's3'", backend),
+    }
+}
+
+// A hex encoded 32 byte key, e.g. from `openssl rand -hex 32`
+pub fn load_master_key() -> Option<MasterKey> {
+    let value = env::var("ENCRYPTION_MASTER_KEY").ok()?;
+
+    match MasterKey::from_hex(&value) {
+        Ok(master_key) => Some(master_key),
+        Err(e) => panic!("Invalid ENCRYPTION_MASTER_KEY: {:?}", e),
+    }
+}
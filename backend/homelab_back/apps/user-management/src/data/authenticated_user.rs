@@ -0,0 +1,10 @@
+use derive_new::new;
+use homelab_core::auth::jwt::TokenPair;
+use homelab_core::user::User;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, new)]
+pub struct AuthenticatedUser {
+    pub user: User,
+    pub tokens: TokenPair,
+}
@@ -0,0 +1,8 @@
+use derive_new::new;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, new)]
+pub struct LoginCommand {
+    pub email: String,
+    pub password: String,
+}
@@ -1,3 +1,6 @@
+pub mod authenticated_user;
 pub mod create_user_command;
 pub mod create_white_listed_user_command;
+pub mod login_command;
+pub mod refresh_token_command;
 pub mod update_password_command;
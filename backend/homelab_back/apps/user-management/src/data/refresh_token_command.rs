@@ -0,0 +1,7 @@
+use derive_new::new;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, new)]
+pub struct RefreshTokenCommand {
+    pub refresh_token: String,
+}
@@ -1,9 +1,12 @@
 use crate::data::create_user_command::CreateUserCommand;
-use crate::helpers::proto_mappers::{map_entity_id, map_user_to_proto};
+use crate::data::login_command::LoginCommand;
+use crate::data::refresh_token_command::RefreshTokenCommand;
+use crate::helpers::proto_mappers::{map_entity_id, map_tokens_to_proto, map_user_to_proto};
 use crate::AppState;
 use derive_new::new;
+use homelab_core::auth::grpc_interceptor::{require_admin, require_caller};
 use homelab_proto::user::user_service_server::UserService;
-use homelab_proto::user::{CreateUserRequest, GetUserByEmailRequest, GetUserByIdRequest, ToggleBlockStatusRequest, UpdatePasswordRequest, UserList, UserResponse};
+use homelab_proto::user::{CreateUserRequest, GetUserByEmailRequest, GetUserByIdRequest, LoginRequest, LoginResponse, RefreshTokenRequest, ToggleBlockStatusRequest, TokenResponse, UpdatePasswordRequest, UserList, UserResponse};
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
 
@@ -15,10 +18,16 @@ pub struct GrpcUserService {
 #[tonic::async_trait]
 impl UserService for GrpcUserService {
     async fn get_by_id(&self, request: Request<GetUserByIdRequest>) -> Result<Response<UserResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let user_id = map_entity_id(req.id)?;
 
+        if !caller.can_access_user(user_id) {
+            return Err(Status::permission_denied("Not allowed to access this user"));
+        }
+
         let user = self
             .app_state
             .user_service
@@ -33,6 +42,8 @@ impl UserService for GrpcUserService {
         &self,
         request: Request<GetUserByEmailRequest>,
     ) -> Result<Response<UserResponse>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let user = self
@@ -42,10 +53,16 @@ impl UserService for GrpcUserService {
             .await?
             .ok_or_else(|| Status::not_found(format!("No user found with email: {}", req.email)))?;
 
+        if !caller.can_access_user(user.id) {
+            return Err(Status::permission_denied("Not allowed to access this user"));
+        }
+
         Ok(Response::new(map_user_to_proto(user)))
     }
 
-    async fn get_all(&self, _request: Request<()>) -> Result<Response<UserList>, Status> {
+    async fn get_all(&self, request: Request<()>) -> Result<Response<UserList>, Status> {
+        require_admin(&request)?;
+
         let users = self.app_state.user_service.get_all().await?;
 
         let proto_users = users.into_iter().map(|u| map_user_to_proto(u)).collect();
@@ -70,10 +87,16 @@ impl UserService for GrpcUserService {
         &self,
         request: Request<UpdatePasswordRequest>,
     ) -> Result<Response<()>, Status> {
+        let caller = require_caller(&request)?;
+
         let req = request.into_inner();
 
         let user_id = map_entity_id(req.id)?;
 
+        if !caller.can_access_user(user_id) {
+            return Err(Status::permission_denied("Not allowed to change this password"));
+        }
+
         self.app_state
             .user_service
             .update_password(user_id, &req.password)
@@ -86,6 +109,8 @@ impl UserService for GrpcUserService {
         &self,
         request: Request<ToggleBlockStatusRequest>,
     ) -> Result<Response<()>, Status> {
+        require_admin(&request)?;
+
         let req = request.into_inner();
 
         let user_id = map_entity_id(req.id)?;
@@ -97,4 +122,30 @@ impl UserService for GrpcUserService {
 
         Ok(Response::new(()))
     }
+
+    async fn login(&self, request: Request<LoginRequest>) -> Result<Response<LoginResponse>, Status> {
+        let req = request.into_inner();
+
+        let command = LoginCommand::new(req.email, req.password);
+
+        let authenticated = self.app_state.auth_service.login(command).await?;
+
+        Ok(Response::new(LoginResponse {
+            user: Some(map_user_to_proto(authenticated.user)),
+            tokens: Some(map_tokens_to_proto(authenticated.tokens)),
+        }))
+    }
+
+    async fn refresh_token(
+        &self,
+        request: Request<RefreshTokenRequest>,
+    ) -> Result<Response<TokenResponse>, Status> {
+        let req = request.into_inner();
+
+        let command = RefreshTokenCommand::new(req.refresh_token);
+
+        let tokens = self.app_state.auth_service.refresh(command).await?;
+
+        Ok(Response::new(map_tokens_to_proto(tokens)))
+    }
 }
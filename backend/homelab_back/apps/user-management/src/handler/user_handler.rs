@@ -1,17 +1,27 @@
 use crate::data::create_user_command::CreateUserCommand;
+use crate::data::login_command::LoginCommand;
+use crate::data::refresh_token_command::RefreshTokenCommand;
 use crate::data::update_password_command::UpdatePasswordCommand;
 use crate::helpers::error_mapping::map_data_err_to_http;
 use crate::AppState;
 use actix_web::web::{Data, Json, Path};
 use actix_web::{get, patch, post, web, HttpResponse, Responder};
+use homelab_core::auth::caller::CallerIdentity;
 use tracing;
 use uuid::Uuid;
 
 #[get("/users/{email}")]
-pub async fn get_user_by_email(app_state: Data<AppState>, path: Path<String>) -> impl Responder {
+pub async fn get_user_by_email(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+    path: Path<String>,
+) -> impl Responder {
     let email = path.into_inner();
 
     match app_state.user_service.get_by_email(&email).await {
+        Ok(Some(user)) if !caller.can_access_user(user.id) => {
+            HttpResponse::Forbidden().body("Not allowed to access this user")
+        }
         Ok(Some(user)) => HttpResponse::Ok().json(user),
         Ok(None) => {
             HttpResponse::NotFound().body(format!("No user was found with email {}", &email))
@@ -24,7 +34,11 @@ pub async fn get_user_by_email(app_state: Data<AppState>, path: Path<String>) ->
 }
 
 #[get("/users")]
-pub async fn get_users(app_state: Data<AppState>) -> impl Responder {
+pub async fn get_users(app_state: Data<AppState>, caller: CallerIdentity) -> impl Responder {
+    if !caller.is_admin() {
+        return HttpResponse::Forbidden().body("Admin access is required");
+    }
+
     match app_state.user_service.get_all().await {
         Ok(users) => HttpResponse::Ok().json(users),
         Err(e) => {
@@ -53,6 +67,7 @@ pub async fn create_user(
 #[patch("/users/{id}/password")]
 pub async fn update_password(
     app_state: Data<AppState>,
+    caller: CallerIdentity,
     id: Path<Uuid>,
     req: Json<UpdatePasswordCommand>,
 ) -> impl Responder {
@@ -60,6 +75,10 @@ pub async fn update_password(
 
     let user_id = id.into_inner();
 
+    if !caller.can_access_user(user_id) {
+        return HttpResponse::Forbidden().body("Not allowed to change this password");
+    }
+
     match app_state
         .user_service
         .update_password(user_id, &command.password)
@@ -73,9 +92,43 @@ pub async fn update_password(
     }
 }
 
+#[post("/users/login")]
+pub async fn login(app_state: Data<AppState>, req: Json<LoginCommand>) -> impl Responder {
+    let command = req.into_inner();
+
+    match app_state.auth_service.login(command).await {
+        Ok(authenticated) => HttpResponse::Ok().json(authenticated),
+        Err(e) => {
+            tracing::error!("Failed to log in user: {}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
+#[post("/users/refresh")]
+pub async fn refresh_token(
+    app_state: Data<AppState>,
+    req: Json<RefreshTokenCommand>,
+) -> impl Responder {
+    let command = req.into_inner();
+
+    match app_state.auth_service.refresh(command).await {
+        Ok(tokens) => HttpResponse::Ok().json(tokens),
+        Err(e) => {
+            tracing::error!("Failed to refresh token: {}", e);
+            map_data_err_to_http(e)
+        }
+    }
+}
+
+pub fn public_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(login);
+    cfg.service(refresh_token);
+    cfg.service(create_user);
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(get_user_by_email);
     cfg.service(get_users);
-    cfg.service(create_user);
     cfg.service(update_password);
 }
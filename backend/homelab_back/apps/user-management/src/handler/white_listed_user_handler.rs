@@ -3,10 +3,18 @@ use crate::helpers::error_mapping::map_data_err_to_http;
 use crate::AppState;
 use actix_web::web::{Data, Json, Path};
 use actix_web::{get, post, web, HttpResponse, Responder};
+use homelab_core::auth::caller::CallerIdentity;
 use uuid::Uuid;
 
 #[get("/white_listed_users")]
-pub async fn get_white_listed_users(app_state: Data<AppState>) -> impl Responder {
+pub async fn get_white_listed_users(
+    app_state: Data<AppState>,
+    caller: CallerIdentity,
+) -> impl Responder {
+    if !caller.is_admin() {
+        return HttpResponse::Forbidden().body("Admin access is required");
+    }
+
     match app_state.white_listed_user_service.get_all().await {
         Ok(users) => HttpResponse::Ok().json(users),
         Err(e) => {
@@ -19,8 +27,13 @@ pub async fn get_white_listed_users(app_state: Data<AppState>) -> impl Responder
 #[post("/white_listed_users/confirm/{userid}")]
 pub async fn confirm_white_listed_user(
     app_state: Data<AppState>,
+    caller: CallerIdentity,
     user_id: Path<Uuid>,
 ) -> impl Responder {
+    if !caller.is_admin() {
+        return HttpResponse::Forbidden().body("Admin access is required");
+    }
+
     match app_state
         .white_listed_user_service
         .confirm(user_id.into_inner())
@@ -50,8 +63,11 @@ pub async fn create_white_listed_user(
     }
 }
 
+pub fn public_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_white_listed_user);
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(get_white_listed_users);
     cfg.service(confirm_white_listed_user);
-    cfg.service(create_white_listed_user);
 }
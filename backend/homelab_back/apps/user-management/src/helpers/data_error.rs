@@ -12,6 +12,16 @@ pub enum DataError {
     EntityCreationError(String),
     #[error("Failed to validate data")]
     ValidationError(String),
+    #[error("Invalid email or password")]
+    InvalidCredentialsError,
+    #[error("This account has been blocked")]
+    UserIsBlockedError,
+    #[error("Failed to process password: {0}")]
+    PasswordHashingError(String),
+    #[error("Invalid or expired token")]
+    InvalidTokenError,
+    #[error("Failed to issue token: {0}")]
+    TokenIssuingError(String),
     #[error("{0}")]
     UnknownError(String),
 }
@@ -8,6 +8,9 @@ pub fn map_data_err_to_http(e: DataError) -> HttpResponse {
         DataError::EntityCreationError(msg) => HttpResponse::BadRequest().body(msg),
         DataError::WhiteListedUserDoesNotExist(msg) => HttpResponse::Conflict().body(msg),
         DataError::DatabaseError(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        DataError::InvalidCredentialsError => HttpResponse::Unauthorized().body(e.to_string()),
+        DataError::UserIsBlockedError => HttpResponse::Forbidden().body(e.to_string()),
+        DataError::InvalidTokenError => HttpResponse::Unauthorized().body(e.to_string()),
         _ => {
             tracing::error!("Internal server error: {:?}", e);
             HttpResponse::InternalServerError().finish()
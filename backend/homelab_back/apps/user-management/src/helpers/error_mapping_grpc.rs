@@ -11,6 +11,11 @@ impl From<DataError> for Status {
             DataError::DatabaseError(_) => Status::internal("A database error occurred"),
             DataError::EntityCreationError(msg) => Status::aborted(msg),
             DataError::ValidationError(msg) => Status::invalid_argument(msg),
+            DataError::InvalidCredentialsError => Status::unauthenticated("Invalid email or password"),
+            DataError::UserIsBlockedError => Status::permission_denied("This account has been blocked"),
+            DataError::PasswordHashingError(_) => Status::internal("Failed to process password"),
+            DataError::InvalidTokenError => Status::unauthenticated("Invalid or expired token"),
+            DataError::TokenIssuingError(_) => Status::internal("Failed to issue token"),
             DataError::UnknownError(msg) => Status::internal(msg),
         }
     }
@@ -1,7 +1,8 @@
+use homelab_core::auth::jwt::TokenPair;
 use homelab_core::user::{Role as DomainRole, User};
 use homelab_core::white_listed_user::WhiteListedUser;
 use homelab_proto::common::EntityId;
-use homelab_proto::user::{Role as ProtoRole, UserResponse, WhiteListedUserResponse};
+use homelab_proto::user::{Role as ProtoRole, TokenResponse, UserResponse, WhiteListedUserResponse};
 use tonic::Status;
 use uuid::Uuid;
 
@@ -17,6 +18,14 @@ pub fn map_wlu_to_proto(u: WhiteListedUser) -> WhiteListedUserResponse {
     }
 }
 
+pub fn map_tokens_to_proto(t: TokenPair) -> TokenResponse {
+    TokenResponse {
+        access_token: t.access_token,
+        refresh_token: t.refresh_token,
+        expires_in: t.expires_in,
+    }
+}
+
 pub fn map_user_to_proto(u: User) -> UserResponse {
     UserResponse {
         id: Option::from(map_id_to_proto(u.id)),
@@ -1,10 +1,15 @@
 use crate::db::user_repository::UserRepositoryImpl;
 use crate::db::white_listed_user_repository::WhiteListedUserRepositoryImpl;
 use crate::grpc::user_grpc_service::GrpcUserService;
+use crate::service::auth_service::{AuthService, AuthServiceImpl};
 use crate::service::user_service::{UserService, UserServiceImpl};
 use crate::service::white_listed_user_service::{WhiteListedServiceImpl, WhiteListedUserService};
+use actix_web::middleware::from_fn;
 use actix_web::{web, App, HttpServer};
 use dotenvy::dotenv;
+use homelab_core::auth::grpc_interceptor::AuthInterceptor;
+use homelab_core::auth::http_middleware::require_auth;
+use homelab_core::auth::jwt::JwtKeys;
 use homelab_proto::user::user_service_server::UserServiceServer;
 use sqlx::postgres::PgPoolOptions;
 use std::env;
@@ -24,6 +29,7 @@ pub mod service;
 pub struct AppState {
     pub user_service: Arc<dyn UserService>,
     pub white_listed_user_service: Arc<dyn WhiteListedUserService>,
+    pub auth_service: Arc<dyn AuthService>,
 }
 
 #[tokio::main]
@@ -58,6 +64,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let publisher = Arc::new(RabbitMqPublisher::new(&rabbit_url).await?);
 
+    let jwt_keys = Arc::new(JwtKeys::from_env());
+
     let user_repo = Arc::new(UserRepositoryImpl::new(pool.clone()));
     let wlu_repo = Arc::new(WhiteListedUserRepositoryImpl::new(pool.clone()));
 
@@ -67,12 +75,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         user_repo.clone(),
         publisher.clone()
     ));
+    let auth_service = Arc::new(AuthServiceImpl::new(user_service.clone(), jwt_keys.clone()));
 
     let app_state = web::Data::new(AppState {
         user_service,
         white_listed_user_service,
+        auth_service,
     });
 
+    let jwt_data = web::Data::from(jwt_keys.clone());
+
     let rest_addr = ("0.0.0.0", 8081);
     let grpc_addr: std::net::SocketAddr = "[::1]:50052".parse().unwrap();
 
@@ -90,6 +102,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             HttpServer::new(move || {
                 App::new()
                     .app_data(app_state.clone())
+                    .app_data(jwt_data.clone())
                     .configure(handler_config)
             })
             .bind(rest_addr)?
@@ -103,7 +116,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let user_impl = GrpcUserService::new(app_state_arc.clone());
 
             Server::builder()
-                .add_service(UserServiceServer::new(user_impl))
+                .add_service(UserServiceServer::with_interceptor(
+                    user_impl,
+                    AuthInterceptor::optional(jwt_keys.clone()),
+                ))
                 .serve(grpc_addr)
                 .await?;
         }
@@ -115,7 +131,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let user_impl = GrpcUserService::new(app_state_arc.clone());
 
             let grpc_handle = Server::builder()
-                .add_service(UserServiceServer::new(user_impl))
+                .add_service(UserServiceServer::with_interceptor(
+                    user_impl,
+                    AuthInterceptor::optional(jwt_keys.clone()),
+                ))
                 .serve(grpc_addr);
 
             println!(
@@ -125,6 +144,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             HttpServer::new(move || {
                 App::new()
                     .app_data(app_state.clone())
+                    .app_data(jwt_data.clone())
                     .configure(handler_config)
             })
             .bind(rest_addr)?
@@ -145,7 +165,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 fn handler_config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api")
-            .configure(handler::user_handler::config)
-            .configure(handler::white_listed_user_handler::config),
+            .configure(handler::user_handler::public_config)
+            .configure(handler::white_listed_user_handler::public_config)
+            .service(
+                web::scope("")
+                    .wrap(from_fn(require_auth))
+                    .configure(handler::user_handler::config)
+                    .configure(handler::white_listed_user_handler::config),
+            ),
     );
 }
@@ -0,0 +1,58 @@
+use crate::data::authenticated_user::AuthenticatedUser;
+use crate::data::login_command::LoginCommand;
+use crate::data::refresh_token_command::RefreshTokenCommand;
+use crate::helpers::data_error::DataError;
+use crate::service::user_service::UserService;
+use async_trait::async_trait;
+use derive_new::new;
+use homelab_core::auth::claims::TokenType;
+use homelab_core::auth::jwt::{JwtKeys, TokenPair};
+use std::sync::Arc;
+
+#[async_trait]
+pub trait AuthService: Send + Sync {
+    async fn login(&self, command: LoginCommand) -> Result<AuthenticatedUser, DataError>;
+    async fn refresh(&self, command: RefreshTokenCommand) -> Result<TokenPair, DataError>;
+}
+
+#[derive(new)]
+pub struct AuthServiceImpl {
+    user_service: Arc<dyn UserService>,
+    jwt_keys: Arc<JwtKeys>,
+}
+
+#[async_trait]
+impl AuthService for AuthServiceImpl {
+    async fn login(&self, command: LoginCommand) -> Result<AuthenticatedUser, DataError> {
+        let user = self.user_service.verify_credentials(command).await?;
+
+        let tokens = self
+            .jwt_keys
+            .issue_token_pair(user.id, user.role.clone())
+            .map_err(|e| DataError::TokenIssuingError(e.to_string()))?;
+
+        Ok(AuthenticatedUser::new(user, tokens))
+    }
+
+    async fn refresh(&self, command: RefreshTokenCommand) -> Result<TokenPair, DataError> {
+        let claims = self
+            .jwt_keys
+            .decode(&command.refresh_token, TokenType::Refresh)
+            .map_err(|_| DataError::InvalidTokenError)?;
+
+        // Re-read the user so that blocked accounts or role changes take effect on refresh
+        let user = self
+            .user_service
+            .get_by_id(claims.sub)
+            .await?
+            .ok_or(DataError::InvalidTokenError)?;
+
+        if user.is_blocked {
+            return Err(DataError::UserIsBlockedError);
+        }
+
+        self.jwt_keys
+            .issue_token_pair(user.id, user.role)
+            .map_err(|e| DataError::TokenIssuingError(e.to_string()))
+    }
+}
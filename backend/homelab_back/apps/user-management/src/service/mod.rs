@@ -1,2 +1,3 @@
+pub mod auth_service;
 pub mod user_service;
-pub mod white_listed_user_service;
+pub mod white_listed_user_service;
\ No newline at end of file
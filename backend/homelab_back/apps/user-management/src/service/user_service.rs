@@ -1,4 +1,5 @@
 use crate::data::create_user_command::CreateUserCommand;
+use crate::data::login_command::LoginCommand;
 use crate::db::user_repository::UserRepository;
 use crate::events::rabbitmq::RabbitMqPublisher;
 use crate::helpers::data_error::DataError;
@@ -18,6 +19,7 @@ pub trait UserService: Send + Sync {
     async fn get_by_id(&self, id: Uuid) -> Result<Option<User>, DataError>;
     async fn update_password(&self, id: Uuid, pass: &str) -> Result<(), DataError>;
     async fn toggle_blocked(&self, id: Uuid, blocked: bool) -> Result<(), DataError>;
+    async fn verify_credentials(&self, command: LoginCommand) -> Result<User, DataError>;
 }
 
 #[derive(new)]
@@ -45,9 +47,10 @@ impl UserService for UserServiceImpl {
         let u = User::new_complete(
             Uuid::new_v4(),
             valid_email.into_inner(),
-            command.password,
             cleaned_name,
-        );
+            command.password,
+        )
+        .map_err(|e| DataError::PasswordHashingError(e))?;
 
         let event: UserCreatedEvent = UserCreatedEvent::new(
             u.id.clone(),
@@ -74,7 +77,8 @@ impl UserService for UserServiceImpl {
                 DataError::EntityNotFoundException(format!("User not found: {}", id))
             })?;
 
-        user.set_password(pass);
+        user.set_password(pass)
+            .map_err(|e| DataError::PasswordHashingError(e))?;
 
         self.user_repo.save(user).await?;
 
@@ -115,4 +119,30 @@ impl UserService for UserServiceImpl {
 
         Ok(())
     }
+
+    async fn verify_credentials(&self, command: LoginCommand) -> Result<User, DataError> {
+        let mut user = self
+            .user_repo
+            .get_by_email(command.email.trim().to_string())
+            .await?
+            .ok_or(DataError::InvalidCredentialsError)?;
+
+        // Pending users have no password yet, so they can never log in
+        if !user.verify_password(&command.password) {
+            return Err(DataError::InvalidCredentialsError);
+        }
+
+        if user.is_blocked {
+            return Err(DataError::UserIsBlockedError);
+        }
+
+        if user.needs_rehash() {
+            user.set_password(&command.password)
+                .map_err(|e| DataError::PasswordHashingError(e))?;
+
+            self.user_repo.save(user.clone()).await?;
+        }
+
+        Ok(user)
+    }
 }
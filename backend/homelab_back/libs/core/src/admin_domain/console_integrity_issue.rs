@@ -0,0 +1,17 @@
+use derive_new::new;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use crate::file_integrity::IntegrityStatus;
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, new)]
+pub struct ConsoleIntegrityIssue {
+    pub id: Uuid,
+    pub file_id: Uuid,
+    pub owner_id: Uuid,
+    pub status: IntegrityStatus,
+    pub expected_checksum: Option<String>,
+    pub actual_checksum: Option<String>,
+    pub detected_at: OffsetDateTime
+}
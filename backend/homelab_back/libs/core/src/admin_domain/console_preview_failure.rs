@@ -0,0 +1,16 @@
+use derive_new::new;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, new)]
+pub struct ConsolePreviewFailure {
+    pub id: Uuid,
+    pub file_id: Uuid,
+    pub owner_id: Uuid,
+    pub file_name: String,
+    pub attempts: i32,
+    pub error: String,
+    pub failed_at: OffsetDateTime
+}
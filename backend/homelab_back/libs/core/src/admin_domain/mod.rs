@@ -1,3 +1,5 @@
 pub mod console_user;
 pub mod console_wlu;
-pub mod console_file;
\ No newline at end of file
+pub mod console_file;
+pub mod console_integrity_issue;
+pub mod console_preview_failure;
\ No newline at end of file
@@ -0,0 +1,30 @@
+use crate::auth::claims::Claims;
+use crate::user::Role;
+use uuid::Uuid;
+
+/// Identity of the authenticated caller, injected into the request extensions
+/// by the gRPC interceptor or the actix middleware.
+#[derive(Debug, Clone)]
+pub struct CallerIdentity {
+    pub user_id: Uuid,
+    pub role: Role,
+}
+
+impl CallerIdentity {
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+
+    pub fn can_access_user(&self, user_id: Uuid) -> bool {
+        self.is_admin() || self.user_id == user_id
+    }
+}
+
+impl From<Claims> for CallerIdentity {
+    fn from(claims: Claims) -> Self {
+        CallerIdentity {
+            user_id: claims.sub,
+            role: claims.role,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::auth::caller::CallerIdentity;
+use crate::auth::claims::TokenType;
+use crate::auth::jwt::{extract_bearer, AuthError, JwtKeys};
+use std::sync::Arc;
+use tonic::metadata::MetadataValue;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthPolicy {
+    // Token is validated when present, handlers decide what needs a caller
+    Optional,
+    Required,
+    AdminOnly,
+}
+
+#[derive(Clone)]
+pub struct AuthInterceptor {
+    keys: Arc<JwtKeys>,
+    policy: AuthPolicy,
+}
+
+impl AuthInterceptor {
+    pub fn optional(keys: Arc<JwtKeys>) -> Self {
+        AuthInterceptor { keys, policy: AuthPolicy::Optional }
+    }
+
+    pub fn required(keys: Arc<JwtKeys>) -> Self {
+        AuthInterceptor { keys, policy: AuthPolicy::Required }
+    }
+
+    pub fn admin_only(keys: Arc<JwtKeys>) -> Self {
+        AuthInterceptor { keys, policy: AuthPolicy::AdminOnly }
+    }
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let header = match request.metadata().get("authorization") {
+            Some(value) => value
+                .to_str()
+                .map_err(|_| Status::unauthenticated("Malformed authorization header"))?,
+            None if self.policy == AuthPolicy::Optional => return Ok(request),
+            None => return Err(AuthError::MissingToken.into()),
+        };
+
+        let token = extract_bearer(header)?;
+
+        let caller = CallerIdentity::from(self.keys.decode(token, TokenType::Access)?);
+
+        if self.policy == AuthPolicy::AdminOnly && !caller.is_admin() {
+            return Err(Status::permission_denied("Admin access is required"));
+        }
+
+        request.extensions_mut().insert(caller);
+
+        Ok(request)
+    }
+}
+
+/// Attaches a freshly minted service token to every outgoing request.
+#[derive(Clone)]
+pub struct ServiceTokenInterceptor {
+    keys: Arc<JwtKeys>,
+}
+
+impl ServiceTokenInterceptor {
+    pub fn new(keys: Arc<JwtKeys>) -> Self {
+        ServiceTokenInterceptor { keys }
+    }
+}
+
+impl Interceptor for ServiceTokenInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let token = self
+            .keys
+            .issue_service_token()
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let value = MetadataValue::try_from(format!("Bearer {}", token))
+            .map_err(|_| Status::internal("Failed to build authorization header"))?;
+
+        request.metadata_mut().insert("authorization", value);
+
+        Ok(request)
+    }
+}
+
+#[allow(clippy::result_large_err)]
+pub fn require_caller<T>(request: &Request<T>) -> Result<CallerIdentity, Status> {
+    request
+        .extensions()
+        .get::<CallerIdentity>()
+        .cloned()
+        .ok_or_else(|| AuthError::MissingToken.into())
+}
+
+#[allow(clippy::result_large_err)]
+pub fn require_admin<T>(request: &Request<T>) -> Result<CallerIdentity, Status> {
+    let caller = require_caller(request)?;
+
+    if !caller.is_admin() {
+        return Err(Status::permission_denied("Admin access is required"));
+    }
+
+    Ok(caller)
+}
+
+impl From<AuthError> for Status {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::TokenCreationError(_) => Status::internal(e.to_string()),
+            _ => Status::unauthenticated(e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::auth::caller::CallerIdentity;
+use crate::auth::claims::TokenType;
+use crate::auth::jwt::{extract_bearer, JwtKeys};
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::error::{ErrorInternalServerError, ErrorUnauthorized};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+
+/// Rejects requests without a valid access token. Expects `web::Data<JwtKeys>` in app data.
+/// Usage: `.wrap(actix_web::middleware::from_fn(require_auth))`
+pub async fn require_auth(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let keys = req
+        .app_data::<web::Data<JwtKeys>>()
+        .ok_or_else(|| ErrorInternalServerError("Authentication is not configured"))?;
+
+    let header = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| ErrorUnauthorized("Missing authorization token"))?;
+
+    let token = extract_bearer(header).map_err(|e| ErrorUnauthorized(e.to_string()))?;
+
+    let claims = keys
+        .decode(token, TokenType::Access)
+        .map_err(|e| ErrorUnauthorized(e.to_string()))?;
+
+    req.extensions_mut().insert(CallerIdentity::from(claims));
+
+    next.call(req).await
+}
+
+impl FromRequest for CallerIdentity {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<CallerIdentity>()
+                .cloned()
+                .ok_or_else(|| ErrorUnauthorized("Missing caller identity")),
+        )
+    }
+}
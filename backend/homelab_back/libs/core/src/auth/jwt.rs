@@ -0,0 +1,111 @@
+use crate::auth::claims::{Claims, TokenType};
+use crate::user::Role;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::Serialize;
+use std::env;
+use thiserror::Error;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+pub const ACCESS_TOKEN_TTL_SECONDS: i64 = 15 * 60;
+pub const REFRESH_TOKEN_TTL_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+// Service-to-service calls (e.g. admin-console -> user-management) are made as this account
+pub const SERVICE_ACCOUNT_ID: Uuid = Uuid::nil();
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Missing authorization token")]
+    MissingToken,
+    #[error("Invalid or expired token")]
+    InvalidToken,
+    #[error("Unexpected token type")]
+    WrongTokenType,
+    #[error("Failed to issue token: {0}")]
+    TokenCreationError(String),
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
+}
+
+pub struct JwtKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    validation: Validation,
+}
+
+impl JwtKeys {
+    pub fn new(secret: &[u8]) -> Self {
+        JwtKeys {
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+            validation: Validation::new(Algorithm::HS256),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set in .env file");
+
+        JwtKeys::new(secret.as_bytes())
+    }
+
+    pub fn issue(
+        &self,
+        user_id: Uuid,
+        role: Role,
+        token_type: TokenType,
+    ) -> Result<String, AuthError> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+
+        let ttl = match token_type {
+            TokenType::Access => ACCESS_TOKEN_TTL_SECONDS,
+            TokenType::Refresh => REFRESH_TOKEN_TTL_SECONDS,
+        };
+
+        let claims = Claims {
+            sub: user_id,
+            role,
+            token_type,
+            iat: now,
+            exp: now + ttl,
+        };
+
+        encode(&Header::new(Algorithm::HS256), &claims, &self.encoding)
+            .map_err(|e| AuthError::TokenCreationError(e.to_string()))
+    }
+
+    pub fn issue_token_pair(&self, user_id: Uuid, role: Role) -> Result<TokenPair, AuthError> {
+        Ok(TokenPair {
+            access_token: self.issue(user_id, role.clone(), TokenType::Access)?,
+            refresh_token: self.issue(user_id, role, TokenType::Refresh)?,
+            expires_in: ACCESS_TOKEN_TTL_SECONDS,
+        })
+    }
+
+    pub fn issue_service_token(&self) -> Result<String, AuthError> {
+        self.issue(SERVICE_ACCOUNT_ID, Role::Admin, TokenType::Access)
+    }
+
+    pub fn decode(&self, token: &str, expected: TokenType) -> Result<Claims, AuthError> {
+        let data = decode::<Claims>(token, &self.decoding, &self.validation)
+            .map_err(|_| AuthError::InvalidToken)?;
+
+        if data.claims.token_type != expected {
+            return Err(AuthError::WrongTokenType);
+        }
+
+        Ok(data.claims)
+    }
+}
+
+pub fn extract_bearer(header_value: &str) -> Result<&str, AuthError> {
+    header_value
+        .strip_prefix("Bearer ")
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .ok_or(AuthError::InvalidToken)
+}
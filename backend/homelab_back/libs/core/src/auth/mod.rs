@@ -0,0 +1,5 @@
+pub mod caller;
+pub mod claims;
+pub mod grpc_interceptor;
+pub mod http_middleware;
+pub mod jwt;
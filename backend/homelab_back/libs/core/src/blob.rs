@@ -0,0 +1,32 @@
+use crate::file::build_preview_key;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Content stored once per SHA-256 and shared by every file that holds the
+/// same bytes. `ref_count` is the number of files pointing at it.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct Blob {
+    pub id: Uuid,
+    pub checksum: String,
+    pub size: i64,
+    pub ref_count: i64,
+    pub created_at: OffsetDateTime,
+}
+
+impl Blob {
+    pub fn preview_key(&self) -> String {
+        build_preview_key(&Blob::storage_key(self.id))
+    }
+
+    // Blobs share the bucket layout of files, the suffix tells them apart
+    pub fn storage_key(blob_id: Uuid) -> String {
+        let id_string = blob_id.to_string();
+
+        let bucket1 = &id_string[0..2];
+        let bucket2 = &id_string[2..4];
+
+        format!("{}/{}/{}.blob", bucket1, bucket2, id_string)
+    }
+}
@@ -0,0 +1,50 @@
+use crate::file::{ArchiveCodec, FileType};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+/// When files are compressed in storage without anyone asking. A policy
+/// without `user_id` is the global one, it covers everyone without their own.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct CompressionPolicy {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub min_idle_days: i32,
+    pub min_size: i64,
+    pub codec: ArchiveCodec,
+    pub excluded_types: Vec<FileType>,
+    pub is_enabled: bool,
+    pub updated_at: OffsetDateTime,
+}
+
+impl CompressionPolicy {
+    pub fn new(
+        user_id: Option<Uuid>,
+        min_idle_days: i32,
+        min_size: i64,
+        codec: ArchiveCodec,
+        excluded_types: Vec<FileType>,
+        is_enabled: bool,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            min_idle_days,
+            min_size,
+            codec,
+            excluded_types,
+            is_enabled,
+            updated_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    pub fn is_global(&self) -> bool {
+        self.user_id.is_none()
+    }
+
+    // Files read after this moment are still warm
+    pub fn idle_since(&self, now: OffsetDateTime) -> OffsetDateTime {
+        now - Duration::days(self.min_idle_days as i64)
+    }
+}
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use uuid::Uuid;
 use crate::file::{FileType, UploadStatus};
+use crate::file_integrity::IntegrityStatus;
 
 pub trait DomainEvent {
     fn routing_key (&self) -> &'static str;
@@ -99,6 +100,38 @@ impl DomainEvent for FileUpdatedEvent {
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, new)]
+pub struct FileIntegrityFailedEvent {
+    pub file_id: Uuid,
+    pub owner_id: Uuid,
+    pub status: IntegrityStatus,
+    pub expected_checksum: Option<String>,
+    pub actual_checksum: Option<String>,
+    pub detected_at: OffsetDateTime
+}
+
+impl DomainEvent for FileIntegrityFailedEvent {
+    fn routing_key(&self) -> &'static str {
+        "file.integrity.failed"
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, new)]
+pub struct FilePreviewFailedEvent {
+    pub file_id: Uuid,
+    pub owner_id: Uuid,
+    pub file_name: String,
+    pub attempts: i32,
+    pub error: String,
+    pub failed_at: OffsetDateTime
+}
+
+impl DomainEvent for FilePreviewFailedEvent {
+    fn routing_key(&self) -> &'static str {
+        "file.preview.failed"
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, new)]
 pub struct UserBlockedEvent {
     pub user_id: Uuid,
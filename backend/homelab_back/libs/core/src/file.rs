@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use crate::blob::Blob;
 use std::path::{Path, PathBuf};
 use time::{Duration, OffsetDateTime};
 use uuid::Uuid;
@@ -24,6 +25,35 @@ pub enum UploadStatus {
     Failed,
 }
 
+// How archived content is compressed in storage
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "archive_codec", rename_all = "lowercase")]
+pub enum ArchiveCodec {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+// Where the preview of the current content stands
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "preview_status", rename_all = "lowercase")]
+pub enum PreviewStatus {
+    Pending,
+    Ready,
+    Failed,
+    Unsupported,
+}
+
+impl ArchiveCodec {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveCodec::Gzip => "gz",
+            ArchiveCodec::Zstd => "zst",
+            ArchiveCodec::Xz => "xz",
+        }
+    }
+}
+
 impl FileType {
     pub fn from_filename(name: &str) -> Self {
         let extension = Path::new(name)
@@ -59,7 +89,16 @@ pub struct File {
     pub size: i64,
     pub upload_status: UploadStatus,
     pub created_at: OffsetDateTime,
-    pub updated_at: OffsetDateTime
+    pub updated_at: OffsetDateTime,
+    // Hex encoded SHA-256, holds the digest declared by the client until the upload completes
+    pub checksum: Option<String>,
+    // Set once the upload completes, the content then lives in the shared blob
+    pub blob_id: Option<Uuid>,
+    // Set while the content is archived, size then holds what is actually stored
+    pub archive_codec: Option<ArchiveCodec>,
+    // Size of the content before it was archived, unknown for files archived before it was kept
+    pub original_size: Option<i64>,
+    pub preview_status: PreviewStatus,
 }
 
 impl File {
@@ -91,6 +130,11 @@ impl File {
             upload_status: UploadStatus::Pending,
             created_at,
             updated_at,
+            checksum: None,
+            blob_id: None,
+            archive_codec: None,
+            original_size: None,
+            preview_status: PreviewStatus::Pending,
         }
     }
 
@@ -115,7 +159,16 @@ impl File {
         self.size = new_size
     }
 
-    pub fn build_file_path(&self, storage_path: &Path) -> PathBuf {
+    // Where the content lives inside the storage backend
+    pub fn storage_key(&self) -> String {
+        match self.blob_id {
+            Some(blob_id) => Blob::storage_key(blob_id),
+            None => self.upload_key(),
+        }
+    }
+
+    // Content written before it is handed over to a blob, also used by files stored before blobs existed
+    pub fn upload_key(&self) -> String {
         let id_string = self.id.to_string();
 
         let bucket1 = &id_string[0..2];
@@ -131,7 +184,23 @@ impl File {
             final_filename = format!("{}.{}", final_filename, extension);
         }
 
-        storage_path.join(bucket1).join(bucket2).join(final_filename)
+        format!("{}/{}/{}", bucket1, bucket2, final_filename)
+    }
+
+    pub fn preview_key(&self) -> String {
+        build_preview_key(&self.storage_key())
+    }
+
+    pub fn build_upload_path(&self, storage_path: &Path) -> PathBuf {
+        storage_path.join(self.upload_key())
+    }
+
+    // Chunks of a resumable upload land here until the last one is committed
+    pub fn build_partial_file_path(&self, storage_path: &Path) -> PathBuf {
+        let mut path = self.build_upload_path(storage_path).into_os_string();
+        path.push(".part");
+
+        PathBuf::from(path)
     }
 
     pub fn validate_size(&self, size: i64) -> bool {
@@ -142,6 +211,18 @@ impl File {
         }
     }
 
+    pub fn update_checksum(&mut self, checksum: String) {
+        self.checksum = Some(checksum);
+    }
+
+    // Files without a declared digest accept any content
+    pub fn matches_checksum(&self, checksum: &str) -> bool {
+        match &self.checksum {
+            Some(expected) => expected.eq_ignore_ascii_case(checksum),
+            None => true,
+        }
+    }
+
     pub fn update_status(&mut self, status: UploadStatus) {
         self.upload_status = status
     }
@@ -154,17 +235,36 @@ impl File {
         self.parent_folder_id = new_parent_folder;
     }
 
-    pub fn is_archived(&self, storage_path: &Path) -> bool {
-        let path = self.build_file_path(storage_path);
+    pub fn update_blob(&mut self, blob_id: Uuid) {
+        self.blob_id = Some(blob_id);
+    }
 
-        match path.extension().and_then(|s| s.to_str()) {
-            Some(ext) => {
-                matches!(
-                    ext.to_lowercase().as_str(),
-                    "zip" | "gz" | "tar" | "rar" | "7z" | "bz2" | "xz" | "iso"
-                )
-            }
-            None => false,
-        }
+    pub fn is_archived(&self) -> bool {
+        self.archive_codec.is_some()
+    }
+
+    // Size of the content as it is handed out, which for archived files is before compressing
+    pub fn logical_size(&self) -> i64 {
+        self.original_size.unwrap_or(self.size)
+    }
+
+    pub fn set_as_archived(&mut self, codec: ArchiveCodec, stored_size: i64) {
+        self.original_size = Some(self.size);
+        self.size = stored_size;
+        self.archive_codec = Some(codec);
     }
+
+    pub fn set_as_unarchived(&mut self, size: i64) {
+        self.size = size;
+        self.original_size = None;
+        self.archive_codec = None;
+    }
+}
+
+// Previews sit next to the content they were generated from
+pub fn build_preview_key(storage_key: &str) -> String {
+    Path::new(storage_key)
+        .with_extension("preview")
+        .to_string_lossy()
+        .to_string()
 }
@@ -0,0 +1,29 @@
+use derive_new::new;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "integrity_status", rename_all = "lowercase")]
+pub enum IntegrityStatus {
+    Ok,
+    Missing,
+    Corrupted,
+}
+
+/// Outcome of the last time the scrubber re-hashed a stored file
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, new)]
+pub struct FileIntegrity {
+    pub file_id: Uuid,
+    pub status: IntegrityStatus,
+    pub expected_checksum: Option<String>,
+    pub actual_checksum: Option<String>,
+    pub checked_at: OffsetDateTime,
+}
+
+impl FileIntegrity {
+    pub fn is_issue(&self) -> bool {
+        self.status != IntegrityStatus::Ok
+    }
+}
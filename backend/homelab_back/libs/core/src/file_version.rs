@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Content a file had before it was overwritten. `created_at` is the moment
+/// the content was replaced and became a version.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct FileVersion {
+    pub id: Uuid,
+    pub file_id: Uuid,
+    pub blob_id: Uuid,
+    pub size: i64,
+    pub checksum: String,
+    pub created_at: OffsetDateTime,
+}
+
+impl FileVersion {
+    pub fn new(file_id: Uuid, blob_id: Uuid, size: i64, checksum: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            file_id,
+            blob_id,
+            size,
+            checksum,
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
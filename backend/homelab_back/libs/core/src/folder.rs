@@ -3,6 +3,9 @@ use sqlx::FromRow;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+// Id of the virtual "Shared with me" folder, it has no row in the folders table
+pub const SHARED_WITH_ME_FOLDER_ID: Uuid = Uuid::nil();
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Folder {
     pub id: Uuid,
@@ -23,6 +26,16 @@ impl Folder {
         }
     }
 
+    pub fn shared_with_me_root(user_id: Uuid) -> Self {
+        Self {
+            id: SHARED_WITH_ME_FOLDER_ID,
+            parent_folder_id: None,
+            name: "Shared with me".to_string(),
+            owner_id: user_id,
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+
     pub fn new(id: Uuid, parent_folder_id: Option<Uuid>, name: String, owner_id: Uuid) -> Self {
         Self {
             id,
@@ -1,2 +1,3 @@
 pub mod event_handler;
+pub mod password;
 pub mod rabbitmq_consumer;
\ No newline at end of file
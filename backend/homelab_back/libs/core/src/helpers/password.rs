@@ -0,0 +1,34 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+
+// Rows created before Argon2 was introduced store the password as "hashed_<password>"
+pub const LEGACY_HASH_PREFIX: &str = "hashed_";
+
+/// Hashes a password with Argon2id using a freshly generated per-password salt.
+/// The returned PHC string embeds the algorithm, parameters and salt.
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash password: {}", e))
+}
+
+pub fn verify_password(password: &str, stored_hash: &str) -> bool {
+    if is_legacy_hash(stored_hash) {
+        return stored_hash == format!("{}{}", LEGACY_HASH_PREFIX, password);
+    }
+
+    match PasswordHash::new(stored_hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+pub fn is_legacy_hash(stored_hash: &str) -> bool {
+    stored_hash.starts_with(LEGACY_HASH_PREFIX)
+}
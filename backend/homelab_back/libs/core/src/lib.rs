@@ -1,14 +1,23 @@
 pub mod action_log;
+pub mod blob;
+pub mod compression_policy;
 pub mod constants;
 pub mod file;
+pub mod file_integrity;
 pub mod file_label;
+pub mod file_version;
 pub mod folder;
 pub mod global_file;
 pub mod label;
+pub mod preview_job;
+pub mod share_link;
 pub mod shared_file;
+pub mod shared_folder;
 pub mod storage_profile;
+pub mod upload_session;
 pub mod user;
 pub mod white_listed_user;
 pub mod events;
 pub mod admin_domain;
+pub mod auth;
 pub mod helpers;
\ No newline at end of file
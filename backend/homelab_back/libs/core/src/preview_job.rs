@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// A preview that still has to be made. `attempts` counts the runs started so far,
+/// including the one currently holding the job.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct PreviewJob {
+    pub file_id: Uuid,
+    pub attempts: i32,
+    pub run_after: OffsetDateTime,
+    pub locked_at: Option<OffsetDateTime>,
+    pub last_error: Option<String>,
+    pub created_at: OffsetDateTime,
+}
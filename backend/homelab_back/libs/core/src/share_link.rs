@@ -0,0 +1,116 @@
+use crate::helpers::password::{hash_password, verify_password};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+const TOKEN_BYTES: usize = 32;
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct ShareLink {
+    pub id: Uuid,
+    pub token: String,
+    pub owner_id: Uuid,
+    pub file_id: Option<Uuid>,
+    pub folder_id: Option<Uuid>,
+    #[serde(skip_serializing)]
+    pub password_hash: Option<String>,
+    pub expires_at: Option<OffsetDateTime>,
+    pub max_downloads: Option<i32>,
+    pub download_count: i32,
+    pub created_at: OffsetDateTime,
+}
+
+impl ShareLink {
+    pub fn new_for_file(
+        owner_id: Uuid,
+        file_id: Uuid,
+        password: Option<&str>,
+        expires_at: Option<OffsetDateTime>,
+        max_downloads: Option<i32>,
+    ) -> Result<Self, String> {
+        Self::new(
+            owner_id,
+            Some(file_id),
+            None,
+            password,
+            expires_at,
+            max_downloads,
+        )
+    }
+
+    pub fn new_for_folder(
+        owner_id: Uuid,
+        folder_id: Uuid,
+        password: Option<&str>,
+        expires_at: Option<OffsetDateTime>,
+        max_downloads: Option<i32>,
+    ) -> Result<Self, String> {
+        Self::new(
+            owner_id,
+            None,
+            Some(folder_id),
+            password,
+            expires_at,
+            max_downloads,
+        )
+    }
+
+    fn new(
+        owner_id: Uuid,
+        file_id: Option<Uuid>,
+        folder_id: Option<Uuid>,
+        password: Option<&str>,
+        expires_at: Option<OffsetDateTime>,
+        max_downloads: Option<i32>,
+    ) -> Result<Self, String> {
+        let password_hash = match password {
+            Some(p) => Some(hash_password(p)?),
+            None => None,
+        };
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            token: Self::generate_token(),
+            owner_id,
+            file_id,
+            folder_id,
+            password_hash,
+            expires_at,
+            max_downloads,
+            download_count: 0,
+            created_at: OffsetDateTime::now_utc(),
+        })
+    }
+
+    /// Opaque, URL safe token made of 256 random bits
+    pub fn generate_token() -> String {
+        let mut bytes = [0u8; TOKEN_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= OffsetDateTime::now_utc())
+    }
+
+    pub fn has_downloads_left(&self) -> bool {
+        self.max_downloads
+            .is_none_or(|max| self.download_count < max)
+    }
+
+    pub fn requires_password(&self) -> bool {
+        self.password_hash.is_some()
+    }
+
+    pub fn verify_password(&self, password: Option<&str>) -> bool {
+        match (&self.password_hash, password) {
+            (None, _) => true,
+            (Some(hash), Some(p)) => verify_password(p, hash),
+            (Some(_), None) => false,
+        }
+    }
+}
@@ -3,7 +3,7 @@ use sqlx::FromRow;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, sqlx::Type)]
-#[sqlx(type_name = "shared_file_access_type")]
+#[sqlx(type_name = "access_type")]
 pub enum SharedFileAccessType {
     ReadOnly,
     Edit,
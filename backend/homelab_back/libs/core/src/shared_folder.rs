@@ -0,0 +1,15 @@
+use crate::shared_file::SharedFileAccessType;
+use derive_new::new;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Grants a user access to a folder and everything below it, including files added later
+#[derive(Debug, Serialize, Deserialize, FromRow, new)]
+pub struct SharedFolder {
+    pub id: Uuid,
+    pub folder_id: Uuid,
+    pub user_id: Uuid,
+    pub owner_id: Uuid,
+    pub access_type: SharedFileAccessType,
+}
@@ -8,12 +8,14 @@ pub struct StorageProfile {
     pub user_id: Uuid,
     pub allowed_storage: i64,
     pub taken_storage: i64,
+    pub reserved_storage: i64,
     pub is_blocked: bool,
 }
 
 impl StorageProfile {
+    // Pending uploads have their size reserved up front, so they count as taken here
     pub fn validate_storage_size(&self, file_size: i64) -> bool {
-        let future_size = self.taken_storage + file_size;
+        let future_size = self.taken_storage + self.reserved_storage + file_size;
 
         if future_size > self.allowed_storage {
             false
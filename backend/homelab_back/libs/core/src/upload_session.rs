@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Progress of a resumable upload. The session is keyed by the id of the
+/// `Pending` file it fills and only ever moves forward.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct UploadSession {
+    pub file_id: Uuid,
+    pub owner_id: Uuid,
+    pub committed_offset: i64,
+    pub total_size: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl UploadSession {
+    pub fn new(file_id: Uuid, owner_id: Uuid, total_size: i64) -> Self {
+        Self {
+            file_id,
+            owner_id,
+            committed_offset: 0,
+            total_size,
+            created_at: OffsetDateTime::now_utc(),
+            updated_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    pub fn remaining(&self) -> i64 {
+        self.total_size - self.committed_offset
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.committed_offset == self.total_size
+    }
+}
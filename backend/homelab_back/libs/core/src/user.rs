@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use time::OffsetDateTime;
 use uuid::Uuid;
+use crate::helpers::password;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, sqlx::Type)]
 #[sqlx(type_name = "user_role", rename_all = "lowercase")]
@@ -28,17 +29,21 @@ pub struct User {
 }
 
 impl User {
-    pub fn new_complete(id: Uuid, email: String, full_name: String, password: String) -> User {
-        User {
+    pub fn new_complete(
+        id: Uuid,
+        email: String,
+        full_name: String,
+        password: String,
+    ) -> Result<User, String> {
+        Ok(User {
             id,
             email,
             full_name,
-            //TODO: Implement bcrypt here or something
-            password_hash: Some(Self::hash_password(&password)),
+            password_hash: Some(password::hash_password(&password)?),
             created_at: OffsetDateTime::now_utc(),
             role: Role::User,
             is_blocked: false
-        }
+        })
     }
 
     pub fn new_pending(id: Uuid, email: String, full_name: String) -> User {
@@ -57,15 +62,27 @@ impl User {
         self.password_hash.is_some()
     }
 
-    pub fn set_password(&mut self, pass: &str) {
-        self.password_hash = Some(Self::hash_password(pass))
+    pub fn set_password(&mut self, pass: &str) -> Result<(), String> {
+        self.password_hash = Some(password::hash_password(pass)?);
+        Ok(())
+    }
+
+    pub fn verify_password(&self, pass: &str) -> bool {
+        match &self.password_hash {
+            Some(hash) => password::verify_password(pass, hash),
+            None => false,
+        }
+    }
+
+    // Legacy "hashed_" rows are upgraded to Argon2 the next time the user logs in
+    pub fn needs_rehash(&self) -> bool {
+        match &self.password_hash {
+            Some(hash) => password::is_legacy_hash(hash),
+            None => false,
+        }
     }
     
     pub fn toggle_blocked (&mut self, is_blocked: bool ) {
         self.is_blocked = is_blocked
     }
-
-    fn hash_password(password: &str) -> String {
-        format!("hashed_{}", password)
-    }
 }
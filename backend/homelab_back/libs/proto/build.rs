@@ -15,6 +15,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("cargo:rerun-if-changed=proto/console_file.proto");
     println!("cargo:rerun-if-changed=proto/types.proto");
     println!("cargo:rerun-if-changed=proto/storage_profile.proto");
+    println!("cargo:rerun-if-changed=proto/shared_file.proto");
 
 
     tonic_build::configure().compile_protos(
@@ -31,6 +32,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             "proto/console_file.proto",
             "proto/types.proto",
             "proto/storage_profile.proto",
+            "proto/shared_file.proto",
         ],
         &["proto"],
     )?;
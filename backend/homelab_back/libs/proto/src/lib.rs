@@ -2,6 +2,7 @@ pub mod common {
     tonic::include_proto!("common");
 }
 
+#[allow(clippy::large_enum_variant)]
 pub mod nas {
     tonic::include_proto!("nas");
 }
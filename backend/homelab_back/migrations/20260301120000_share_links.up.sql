@@ -0,0 +1,17 @@
+CREATE TABLE share_links
+(
+    id             UUID PRIMARY KEY,
+    token          TEXT UNIQUE NOT NULL,
+    owner_id       UUID        NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    file_id        UUID REFERENCES files (id) ON DELETE CASCADE,
+    folder_id      UUID REFERENCES folders (id) ON DELETE CASCADE,
+    password_hash  TEXT,
+    expires_at     TIMESTAMPTZ,
+    max_downloads  INTEGER,
+    download_count INTEGER     NOT NULL DEFAULT 0,
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- A link always points to exactly one file or one folder
+    CONSTRAINT share_links_single_target CHECK ((file_id IS NULL) <> (folder_id IS NULL))
+);
+
+CREATE INDEX idx_share_links_owner_id ON share_links (owner_id);
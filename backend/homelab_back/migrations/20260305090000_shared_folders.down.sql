@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_shared_folders_user_id;
+DROP TABLE IF EXISTS shared_folders;
@@ -0,0 +1,11 @@
+CREATE TABLE shared_folders
+(
+    id          UUID PRIMARY KEY,
+    user_id     UUID        NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    owner_id    UUID        NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    folder_id   UUID        NOT NULL REFERENCES folders (id) ON DELETE CASCADE,
+    access_type access_type NOT NULL,
+    UNIQUE (user_id, folder_id)
+);
+
+CREATE INDEX idx_shared_folders_user_id ON shared_folders (user_id);
@@ -0,0 +1,10 @@
+CREATE TABLE upload_sessions
+(
+    file_id          UUID PRIMARY KEY REFERENCES files (id) ON DELETE CASCADE,
+    owner_id         UUID        NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    committed_offset BIGINT      NOT NULL DEFAULT 0,
+    total_size       BIGINT      NOT NULL,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (committed_offset >= 0 AND committed_offset <= total_size)
+);
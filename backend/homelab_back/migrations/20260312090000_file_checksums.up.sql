@@ -0,0 +1,3 @@
+-- Hex encoded SHA-256 of the stored content. While a file is still pending it
+-- holds the digest declared by the client, if any
+ALTER TABLE files ADD COLUMN checksum TEXT;
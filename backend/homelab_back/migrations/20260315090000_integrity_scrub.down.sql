@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS console_integrity_issue;
+DROP INDEX IF EXISTS idx_file_integrity_status;
+DROP TABLE IF EXISTS file_integrity;
+DROP TYPE IF EXISTS integrity_status;
@@ -0,0 +1,27 @@
+CREATE TYPE integrity_status AS ENUM (
+    'ok', 'missing', 'corrupted'
+);
+
+-- Latest scrub result per file, the scrubber works through the least recently checked files first
+CREATE TABLE file_integrity
+(
+    file_id           UUID PRIMARY KEY REFERENCES files (id) ON DELETE CASCADE,
+    status            integrity_status NOT NULL,
+    expected_checksum TEXT,
+    actual_checksum   TEXT,
+    checked_at        TIMESTAMPTZ      NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_file_integrity_status ON file_integrity (status);
+
+-- Issues reported to the admin console, kept even after the file itself is gone
+CREATE TABLE console_integrity_issue
+(
+    id                UUID PRIMARY KEY,
+    file_id           UUID             NOT NULL,
+    owner_id          UUID             NOT NULL,
+    status            integrity_status NOT NULL,
+    expected_checksum TEXT,
+    actual_checksum   TEXT,
+    detected_at       TIMESTAMPTZ      NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE storage_profiles DROP COLUMN IF EXISTS reserved_storage;
@@ -0,0 +1,10 @@
+-- Bytes promised to uploads that have been initialised but not completed yet.
+-- They count against allowed_storage together with taken_storage
+ALTER TABLE storage_profiles
+    ADD COLUMN reserved_storage BIGINT NOT NULL DEFAULT 0 CHECK (reserved_storage >= 0);
+
+UPDATE storage_profiles sp
+SET reserved_storage = COALESCE((SELECT SUM(f.size)
+                                 FROM files f
+                                 WHERE f.owner_id = sp.user_id
+                                   AND f.upload_status = 'pending'), 0);
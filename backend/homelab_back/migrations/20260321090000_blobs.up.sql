@@ -0,0 +1,16 @@
+-- Stored content, shared by every file with the same SHA-256. The bytes are
+-- removed once the last file referencing a blob is gone
+CREATE TABLE blobs
+(
+    id         UUID PRIMARY KEY,
+    checksum   TEXT        NOT NULL UNIQUE,
+    size       BIGINT      NOT NULL,
+    ref_count  BIGINT      NOT NULL DEFAULT 0 CHECK (ref_count >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Files without a blob keep their content under their own id, which is the
+-- case for pending uploads and for files stored before blobs existed
+ALTER TABLE files ADD COLUMN blob_id UUID REFERENCES blobs (id);
+
+CREATE INDEX idx_files_blob_id ON files (blob_id);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_file_versions_blob_id;
+DROP INDEX IF EXISTS idx_file_versions_file_id;
+DROP TABLE IF EXISTS file_versions;
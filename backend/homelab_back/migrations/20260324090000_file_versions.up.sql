@@ -0,0 +1,14 @@
+-- Earlier content of a file. Every version holds its own reference on a blob
+-- and is charged to the owner's storage like the current content
+CREATE TABLE file_versions
+(
+    id         UUID PRIMARY KEY,
+    file_id    UUID        NOT NULL REFERENCES files (id) ON DELETE CASCADE,
+    blob_id    UUID        NOT NULL REFERENCES blobs (id),
+    size       BIGINT      NOT NULL,
+    checksum   TEXT        NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_file_versions_file_id ON file_versions (file_id, created_at DESC);
+CREATE INDEX idx_file_versions_blob_id ON file_versions (blob_id);
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS legacy_archives;
+ALTER TABLE files DROP COLUMN IF EXISTS original_size;
+ALTER TABLE files DROP COLUMN IF EXISTS archive_codec;
+DROP TYPE IF EXISTS archive_codec;
@@ -0,0 +1,24 @@
+CREATE TYPE archive_codec AS ENUM (
+    'gzip', 'zstd', 'xz'
+);
+
+-- Set while the stored content is compressed by archiving, size then holds the
+-- stored bytes and original_size the size the content had before
+ALTER TABLE files ADD COLUMN archive_codec archive_codec;
+ALTER TABLE files ADD COLUMN original_size BIGINT;
+
+-- Archiving used to gzip the content and append ".gz" to the name without
+-- recording it. Those files keep a NULL codec and their name, and are listed
+-- here so unarchiving knows to take them for gzip. Names that already looked
+-- like archives were refused by it, so "backup.tar.gz" was uploaded as it is.
+-- An uploaded "access.log.gz" cannot be told apart and is listed as well
+CREATE TABLE legacy_archives (
+    file_id UUID PRIMARY KEY REFERENCES files (id) ON DELETE CASCADE
+);
+
+INSERT INTO legacy_archives (file_id)
+SELECT id
+FROM files
+WHERE name LIKE '%_.gz'
+  AND file_type = 'zip'
+  AND lower(left(name, -3)) !~ '\.(zip|gz|tar|rar|7z|bz2|xz|iso)$';
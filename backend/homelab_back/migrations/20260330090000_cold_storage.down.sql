@@ -0,0 +1,6 @@
+DROP TABLE compression_policies;
+
+DROP INDEX idx_files_cold;
+
+ALTER TABLE files DROP COLUMN compression_attempted_at;
+ALTER TABLE files DROP COLUMN last_accessed_at;
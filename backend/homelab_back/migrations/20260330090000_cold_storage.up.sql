@@ -0,0 +1,26 @@
+-- Bumped whenever the content is read, cold storage compression goes by it
+ALTER TABLE files ADD COLUMN last_accessed_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+-- Content that did not shrink is not tried again until it changes
+ALTER TABLE files ADD COLUMN compression_attempted_at TIMESTAMPTZ;
+
+UPDATE files SET last_accessed_at = updated_at;
+
+CREATE INDEX idx_files_cold ON files (last_accessed_at)
+    WHERE archive_codec IS NULL AND is_deleted = FALSE;
+
+-- Compresses files nobody has read for a while. The row without a user applies
+-- to everyone who has no row of their own, a disabled row of a user opts them out
+CREATE TABLE compression_policies
+(
+    id             UUID PRIMARY KEY,
+    user_id        UUID UNIQUE REFERENCES users (id) ON DELETE CASCADE,
+    min_idle_days  INT           NOT NULL,
+    min_size       BIGINT        NOT NULL,
+    codec          archive_codec NOT NULL,
+    excluded_types file_type[]   NOT NULL DEFAULT '{}',
+    is_enabled     BOOL          NOT NULL DEFAULT TRUE,
+    updated_at     TIMESTAMPTZ   NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_compression_policies_global ON compression_policies ((user_id IS NULL))
+    WHERE user_id IS NULL;
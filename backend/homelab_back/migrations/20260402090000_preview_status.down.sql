@@ -0,0 +1,2 @@
+ALTER TABLE files DROP COLUMN preview_status;
+DROP TYPE preview_status;
@@ -0,0 +1,12 @@
+CREATE TYPE preview_status AS ENUM (
+    'pending', 'ready', 'failed', 'unsupported'
+);
+
+ALTER TABLE files ADD COLUMN preview_status preview_status NOT NULL DEFAULT 'pending';
+
+-- Nothing was recorded so far. Content no preview is made of is known up front,
+-- the rest stays pending and is settled the first time its preview is asked for
+UPDATE files
+SET preview_status = 'unsupported'
+WHERE archive_codec IS NOT NULL
+   OR file_type IN ('text', 'unknown', 'zip');
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS console_preview_failure;
+DROP INDEX IF EXISTS idx_preview_jobs_run_after;
+DROP TABLE IF EXISTS preview_jobs;
@@ -0,0 +1,25 @@
+-- Previews waiting to be made, a job is gone once its file has a final preview status
+CREATE TABLE preview_jobs
+(
+    file_id    UUID PRIMARY KEY REFERENCES files (id) ON DELETE CASCADE,
+    attempts   INT         NOT NULL DEFAULT 0,
+    run_after  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Set while a worker holds the job, a stale one means the worker died on it
+    locked_at  TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_preview_jobs_run_after ON preview_jobs (run_after);
+
+-- Previews that could not be made after all attempts, reported to the admin console
+CREATE TABLE console_preview_failure
+(
+    id        UUID PRIMARY KEY,
+    file_id   UUID        NOT NULL,
+    owner_id  UUID        NOT NULL,
+    file_name TEXT        NOT NULL,
+    attempts  INT         NOT NULL,
+    error     TEXT        NOT NULL,
+    failed_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,52 @@
+use crate::helpers::auth::Session;
+use crate::types::model::UserProfileView;
+use crate::user::user_service_client::UserServiceClient;
+use crate::user::LoginRequest;
+use crate::utils::format_timestamp;
+use crate::AppState;
+use tauri::State;
+use tonic::Request;
+
+#[tauri::command]
+pub async fn login(
+    email: String,
+    password: String,
+    state: State<'_, AppState>,
+) -> Result<UserProfileView, String> {
+    let mut client = UserServiceClient::new(state.user_grpc_channel.clone());
+
+    let request = Request::new(LoginRequest { email, password });
+
+    let response = client.login(request).await.map_err(|e| {
+        eprintln!("🛑 gRPC Error Code when logging in: {:?}", e.code());
+        format!("Login failed: {}", e.message())
+    })?;
+
+    let login = response.into_inner();
+
+    let user = login.user.ok_or("Login response is missing the user")?;
+    let tokens = login.tokens.ok_or("Login response is missing the tokens")?;
+
+    let user_id = user.id.ok_or("Login response is missing the user id")?.value;
+
+    *state.session.write().await = Some(Session::new(
+        user_id.clone(),
+        tokens.access_token,
+        tokens.refresh_token,
+        tokens.expires_in,
+    ));
+
+    Ok(UserProfileView {
+        id: user_id,
+        email: user.email,
+        name: user.full_name,
+        created_at: format_timestamp(user.created_at),
+    })
+}
+
+#[tauri::command]
+pub async fn logout(state: State<'_, AppState>) -> Result<(), String> {
+    *state.session.write().await = None;
+
+    Ok(())
+}
@@ -1,22 +1,24 @@
 use crate::common::EntityId;
+use crate::helpers::auth::authorized_request;
 use crate::helpers::mappings::map_file_proto_to_view;
+use crate::nas::download_content_response::Data as DownloadContentData;
 use crate::nas::file_chunk::Data;
 use crate::nas::file_service_client::FileServiceClient;
-use crate::nas::{ArchiveFileRequest, CopyFileRequest, DeleteChosenFilesRequest, DeleteFileRequest, FileChunk, GetDeletedFilesRequest, InitFileRequest, MoveFileRequest, RemoveAllDeletedFilesRequest, RemoveDeletedFileRequest, RenameFileRequest, UnarchiveFileRequest, UndeleteFileRequest};
+use crate::nas::update_file_content_request::Data as UpdateContentData;
+use crate::nas::{ArchiveCodec, ArchiveFileRequest, CopyFileRequest, DeleteChosenFilesRequest, DeleteFileRequest, DownloadContentRequest, FileChunk, GetDeletedFilesRequest, InitFileRequest, MoveFileRequest, RemoveAllDeletedFilesRequest, RemoveDeletedFileRequest, RenameFileRequest, UnarchiveFileRequest, UndeleteFileRequest, UpdateFileContentHeader, UpdateFileContentRequest};
 use crate::types::model::FileView;
 use crate::AppState;
 use async_stream::stream;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 use tokio::fs::File;
-use tokio::io::AsyncReadExt;
-use tonic::Request;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 #[tauri::command]
 pub async fn init_file(
     state: tauri::State<'_, AppState>,
     name: String,
     destination: String,
-    owner_id: String,
     local_path: String,
     is_global: bool,
 ) -> Result<FileView, String> {
@@ -28,13 +30,14 @@ pub async fn init_file(
 
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(InitFileRequest {
+    let request = authorized_request(&state, InitFileRequest {
         name,
         destination: Some(EntityId { value: destination }),
-        owner_id: Some(EntityId { value: owner_id }),
         size,
         is_global,
-    });
+        checksum: None,
+    })
+    .await?;
 
     let response = client
         .init_file(request)
@@ -81,7 +84,7 @@ pub async fn upload_content(
         }
     };
 
-    let request = Request::new(outbound_stream);
+    let request = authorized_request(&state, outbound_stream).await?;
 
     client
         .upload_content(request)
@@ -91,15 +94,176 @@ pub async fn upload_content(
     Ok(())
 }
 
+// Saves the whole file to `local_path`
+#[tauri::command]
+pub async fn download_file(
+    state: tauri::State<'_, AppState>,
+    file_id: String,
+    local_path: String,
+) -> Result<FileView, String> {
+    download_to_path(&state, file_id, Path::new(&local_path)).await
+}
+
+// Downloads the file into the temp directory and hands it to the default application
+#[tauri::command]
+pub async fn open_file(
+    state: tauri::State<'_, AppState>,
+    file_id: String,
+) -> Result<FileView, String> {
+    let dir = std::env::temp_dir().join("homelab").join(&file_id);
+
+    fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+
+    let partial_path = dir.join("download.part");
+    let file_view = download_to_path(&state, file_id, &partial_path).await?;
+
+    // Only the last component is used, a name is never allowed to point outside the directory
+    let file_name = Path::new(&file_view.name)
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_else(|| "download".into());
+
+    let local_path: PathBuf = dir.join(file_name);
+
+    fs::rename(&partial_path, &local_path)
+        .await
+        .map_err(|e| format!("Failed to move downloaded file: {}", e))?;
+
+    tauri_plugin_opener::open_path(&local_path, None::<&str>)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+
+    Ok(file_view)
+}
+
+async fn download_to_path(
+    state: &AppState,
+    file_id: String,
+    local_path: &Path,
+) -> Result<FileView, String> {
+    let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
+
+    let request = authorized_request(state, DownloadContentRequest {
+        file_id: Some(EntityId { value: file_id }),
+        offset: 0,
+        length: 0,
+    })
+    .await?;
+
+    let mut inbound = client
+        .download_content(request)
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?
+        .into_inner();
+
+    let file_resp = match inbound.message().await {
+        Ok(Some(msg)) => match msg.data {
+            Some(DownloadContentData::File(file)) => file,
+            _ => return Err("Download did not start with the file".to_string()),
+        },
+        Ok(None) => return Err("Download stream was empty".to_string()),
+        Err(e) => return Err(format!("Download failed: {}", e)),
+    };
+
+    let mut local_file = File::create(local_path)
+        .await
+        .map_err(|e| format!("create file failed: {}", e))?;
+
+    loop {
+        match inbound.message().await {
+            Ok(Some(msg)) => {
+                if let Some(DownloadContentData::Content(bytes)) = msg.data {
+                    local_file
+                        .write_all(&bytes)
+                        .await
+                        .map_err(|e| format!("write file failed: {}", e))?;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                let _ = fs::remove_file(local_path).await;
+                return Err(format!("Download interrupted: {}", e));
+            }
+        }
+    }
+
+    local_file
+        .flush()
+        .await
+        .map_err(|e| format!("write file failed: {}", e))?;
+
+    Ok(map_file_proto_to_view(file_resp))
+}
+
+// Overwrites the file with the local content, the server rejects it if the file changed since `expected_checksum` was seen
+#[tauri::command]
+pub async fn update_file_content(
+    state: tauri::State<'_, AppState>,
+    file_id: String,
+    local_path: String,
+    expected_checksum: String,
+) -> Result<FileView, String> {
+    let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
+
+    let mut file = File::open(&local_path)
+        .await
+        .map_err(|e| format!("open file failed: {}", e))?;
+
+    let outbound_stream = stream! {
+        yield UpdateFileContentRequest {
+            data: Some(UpdateContentData::Header(UpdateFileContentHeader {
+                file_id: Some(EntityId { value: file_id.clone() }),
+                expected_updated_at: None,
+                expected_checksum: Some(expected_checksum.clone()),
+            })),
+        };
+
+        let mut buffer = vec![0; 64 * 1024];
+
+        loop {
+            match file.read(&mut buffer).await {
+                Ok(0) => break,
+                Ok(n) => {
+                    yield UpdateFileContentRequest {
+                        data: Some(UpdateContentData::Content(buffer[..n].to_vec())),
+                    };
+                }
+                Err(e) => {
+                    eprintln!("Error reading file: {}", e);
+                    break;
+                }
+            }
+        }
+    };
+
+    let request = authorized_request(&state, outbound_stream).await?;
+
+    let response = client
+        .update_file_content(request)
+        .await
+        .map_err(|e| match e.code() {
+            tonic::Code::Aborted => {
+                "The file was changed on the server, reload it before saving".to_string()
+            }
+            _ => format!("Update file content failed: {}", e),
+        })?;
+
+    let file_resp = response.into_inner();
+
+    Ok(map_file_proto_to_view(file_resp))
+}
+
 #[tauri::command]
 pub async fn delete_file(state: tauri::State<'_, AppState>, file_id: String) -> Result<(), String> {
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(DeleteFileRequest {
+    let request = authorized_request(&state, DeleteFileRequest {
         id: Some(EntityId {
             value: file_id.clone(),
         }),
-    });
+    })
+    .await?;
 
     let response = client
         .delete_file(request)
@@ -119,10 +283,11 @@ pub async fn rename_file(
 ) -> Result<FileView, String> {
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(RenameFileRequest {
+    let request = authorized_request(&state, RenameFileRequest {
         id: Some(EntityId { value: file_id }),
         new_name: new_name.clone(),
-    });
+    })
+    .await?;
 
     let response = client
         .rename_file(request)
@@ -137,13 +302,10 @@ pub async fn rename_file(
 #[tauri::command]
 pub async fn get_deleted_files(
     state: tauri::State<'_, AppState>,
-    user_id: String,
 ) -> Result<Vec<FileView>, String> {
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(GetDeletedFilesRequest {
-        user_id: Some(EntityId { value: user_id }),
-    });
+    let request = authorized_request(&state, GetDeletedFilesRequest {}).await?;
 
     let response = client
         .get_deleted_files(request)
@@ -168,9 +330,10 @@ pub async fn restore_file(
 ) -> Result<FileView, String> {
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(UndeleteFileRequest {
+    let request = authorized_request(&state, UndeleteFileRequest {
         id: Some(EntityId { value: file_id }),
-    });
+    })
+    .await?;
 
     let response = client
         .undelete_file(request)
@@ -189,9 +352,10 @@ pub async fn delete_chosen_file(
 ) -> Result<(), String> {
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(DeleteChosenFilesRequest {
+    let request = authorized_request(&state, DeleteChosenFilesRequest {
         file_ids: file_id.into_iter().map(|f| EntityId { value: f }).collect(),
-    });
+    })
+    .await?;
 
     let response = client
         .delete_chosen_files(request)
@@ -204,12 +368,10 @@ pub async fn delete_chosen_file(
 }
 
 #[tauri::command]
-pub async fn empty_trash(state: tauri::State<'_, AppState>, user_id: String) -> Result<(), String> {
+pub async fn empty_trash(state: tauri::State<'_, AppState>) -> Result<(), String> {
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(RemoveAllDeletedFilesRequest {
-        user_id: Some(EntityId { value: user_id }),
-    });
+    let request = authorized_request(&state, RemoveAllDeletedFilesRequest {}).await?;
 
     let response = client
         .remove_all_deleted_files(request)
@@ -228,9 +390,10 @@ pub async fn remove_deleted_file(
 ) -> Result<(), String> {
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(RemoveDeletedFileRequest {
+    let request = authorized_request(&state, RemoveDeletedFileRequest {
         file_id: Some(EntityId { value: file_id }),
-    });
+    })
+    .await?;
 
     let response = client
         .remove_delete_file(request)
@@ -250,10 +413,11 @@ pub async fn move_file(
 ) -> Result<FileView, String> {
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(MoveFileRequest {
+    let request = authorized_request(&state, MoveFileRequest {
         folder_id: Some(EntityId { value: folder_id }),
         file_id: Some(EntityId { value: file_id }),
-    });
+    })
+    .await?;
 
     let response = client
         .move_file(request)
@@ -274,10 +438,11 @@ pub async fn copy_file(
 ) -> Result<FileView, String> {
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(CopyFileRequest {
+    let request = authorized_request(&state, CopyFileRequest {
         file_id: Some(EntityId { value: file_id }),
         target_folder_id: Some(EntityId { value: target_folder_id }),
-    });
+    })
+    .await?;
 
     let response = client
         .copy_file(request)
@@ -293,13 +458,23 @@ pub async fn copy_file(
 pub async fn archive_file (
     state: tauri::State<'_, AppState>,
     file_id: String,
+    codec: Option<String>,
 ) -> Result<(), String> {
     println!("arhciving file: {}", file_id);
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(ArchiveFileRequest {
+    let codec = match codec.as_deref() {
+        None | Some("gzip") => ArchiveCodec::Gzip,
+        Some("zstd") => ArchiveCodec::Zstd,
+        Some("xz") => ArchiveCodec::Xz,
+        Some(other) => return Err(format!("Unknown archive codec: {}", other)),
+    };
+
+    let request = authorized_request(&state, ArchiveFileRequest {
         file_id: Some(EntityId { value: file_id }),
-    });
+        codec: codec as i32,
+    })
+    .await?;
 
     let response = client
         .archive_file(request)
@@ -318,9 +493,10 @@ pub async fn unarchive_file(
 ) -> Result<(), String> {
     let mut client = FileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(UnarchiveFileRequest {
+    let request = authorized_request(&state, UnarchiveFileRequest {
         file_id: Some(EntityId { value: file_id }),
-    });
+    })
+    .await?;
 
     let response = client
         .unarchive_file(request)
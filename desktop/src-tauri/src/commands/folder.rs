@@ -1,4 +1,5 @@
 use crate::common::EntityId;
+use crate::helpers::auth::authorized_request;
 use crate::helpers::mappings::{map_file_proto_to_view, map_folder_proto_to_view};
 use crate::nas::folder_service_client::FolderServiceClient;
 use crate::nas::{
@@ -7,18 +8,12 @@ use crate::nas::{
 };
 use crate::types::model::{FileView, FolderView};
 use crate::AppState;
-use tonic::Request;
 
 #[tauri::command]
-pub async fn get_root_folder(
-    user_id: String,
-    state: tauri::State<'_, AppState>,
-) -> Result<FolderView, String> {
+pub async fn get_root_folder(state: tauri::State<'_, AppState>) -> Result<FolderView, String> {
     let mut client = FolderServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(GetRootFolderRequest {
-        user_id: Some(EntityId { value: user_id }),
-    });
+    let request = authorized_request(&state, GetRootFolderRequest {}).await?;
 
     let response = client.get_root_folder(request).await.map_err(|e| {
         eprintln!(
@@ -44,9 +39,10 @@ pub async fn get_files_for_folder(
 ) -> Result<Vec<FileView>, String> {
     let mut client = FolderServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(GetFilesForFolderRequest {
+    let request = authorized_request(&state, GetFilesForFolderRequest {
         id: Some(EntityId { value: folder_id }),
-    });
+    })
+    .await?;
 
     let response = client.get_files_for_folder(request).await.map_err(|e| {
         eprintln!("🛑 gRPC Error Code when fetching files: {:?}", e.code());
@@ -77,9 +73,10 @@ pub async fn get_subfolders(
 ) -> Result<Vec<FolderView>, String> {
     let mut client = FolderServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(GetAllSubfoldersRequest {
+    let request = authorized_request(&state, GetAllSubfoldersRequest {
         id: Some(EntityId { value: folder_id }),
-    });
+    })
+    .await?;
 
     let response = client.get_subfolders(request).await.map_err(|e| {
         eprintln!(
@@ -107,19 +104,18 @@ pub async fn get_subfolders(
 #[tauri::command]
 pub async fn create_folder(
     parent_folder_id: String,
-    user_id: String,
     name: String,
     state: tauri::State<'_, AppState>,
 ) -> Result<FolderView, String> {
     let mut client = FolderServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(CreateFolderRequest {
+    let request = authorized_request(&state, CreateFolderRequest {
         parent_folder_id: Some(EntityId {
             value: parent_folder_id,
         }),
         name,
-        owner_id: Some(EntityId { value: user_id }),
-    });
+    })
+    .await?;
 
     let response = client.create_folder(request).await.map_err(|e| {
         eprintln!(
@@ -145,11 +141,12 @@ pub async fn delete_selected_folder(
 ) -> Result<(), String> {
     let mut client = FolderServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = tonic::Request::new(DeleteFolderRequest {
+    let request = authorized_request(&state, DeleteFolderRequest {
         id: Some(EntityId {
             value: selected_folder_id.clone(),
         }),
-    });
+    })
+    .await?;
 
     client.delete_folder(request).await.map_err(|e| {
         eprintln!("🛑 gRPC Error: [{:?}] {}", e.code(), e.message());
@@ -171,10 +168,11 @@ pub async fn rename_folder(
 ) -> Result<FolderView, String> {
     let mut client = FolderServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = tonic::Request::new(RenameFolderRequest {
+    let request = authorized_request(&state, RenameFolderRequest {
         id: Some(EntityId { value: folder_id }),
         new_name: new_name.clone(),
-    });
+    })
+    .await?;
 
     let response = client.rename_folder(request).await.map_err(|e| {
         eprintln!("🛑 gRPC Error Code when renaming folder: {:?}", e.code());
@@ -1,20 +1,22 @@
 use crate::common::EntityId;
+use crate::helpers::auth::{authorized_request, current_user_id};
 use crate::nas::storage_profile_service_client::StorageProfileServiceClient;
 use crate::nas::GetStorageProfileByIdRequest;
 use crate::types::model::StorageProfileView;
 use crate::AppState;
-use tonic::Request;
 
 #[tauri::command]
 pub async fn get_storage_profile(
-    user_id: String,
     state: tauri::State<'_, AppState>,
 ) -> Result<StorageProfileView, String> {
+    let user_id = current_user_id(&state).await?;
+
     let mut client = StorageProfileServiceClient::new(state.nas_grpc_channel.clone());
 
-    let request = Request::new(GetStorageProfileByIdRequest {
+    let request = authorized_request(&state, GetStorageProfileByIdRequest {
         id: Some(EntityId { value: user_id }),
-    });
+    })
+    .await?;
 
     let response = client.get_by_id(request).await.map_err(|e| {
         eprintln!(
@@ -34,6 +36,7 @@ pub async fn get_storage_profile(
         user_id: sp_data.user_id.unwrap().value,
         allowed_storage: sp_data.allowed_storage,
         taken_storage: sp_data.taken_storage,
+        reserved_storage: sp_data.reserved_storage,
         is_blocked: sp_data.is_blocked,
     })
 }
@@ -1,26 +1,26 @@
 use crate::common::EntityId;
+use crate::helpers::auth::{authorized_request, current_user_id};
 use crate::types::model::UserProfileView;
 use crate::user::user_service_client::UserServiceClient;
 use crate::user::GetUserByIdRequest;
 use crate::utils::format_timestamp;
 use crate::AppState;
 use tauri::State;
-use tonic::Request;
 
 #[tauri::command]
-pub async fn get_user_profile(
-    user_id: String,
-    state: State<'_, AppState>,
-) -> Result<UserProfileView, String> {
+pub async fn get_user_profile(state: State<'_, AppState>) -> Result<UserProfileView, String> {
+    let user_id = current_user_id(&state).await?;
+
     println!(
         "🦀 [RUST] Received request to fetch user profile for ID: {}",
         user_id
     );
     let mut client = UserServiceClient::new(state.user_grpc_channel.clone());
 
-    let request = Request::new(GetUserByIdRequest {
+    let request = authorized_request(&state, GetUserByIdRequest {
         id: Some(EntityId { value: user_id }),
-    });
+    })
+    .await?;
 
     let response = client.get_by_id(request).await.map_err(|e| {
         eprintln!(
@@ -0,0 +1,83 @@
+use crate::user::user_service_client::UserServiceClient;
+use crate::user::RefreshTokenRequest;
+use crate::AppState;
+use std::time::{Duration, Instant};
+use tonic::metadata::MetadataValue;
+use tonic::Request;
+
+// Refresh a bit before the server-side expiry so in-flight calls don't race it
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+pub struct Session {
+    pub user_id: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_at: Instant,
+}
+
+impl Session {
+    pub fn new(user_id: String, access_token: String, refresh_token: String, expires_in: i64) -> Self {
+        Session {
+            user_id,
+            access_token,
+            refresh_token,
+            expires_at: Instant::now() + Duration::from_secs(expires_in.max(0) as u64),
+        }
+    }
+}
+
+pub async fn current_user_id(state: &AppState) -> Result<String, String> {
+    let session = state.session.read().await;
+
+    session
+        .as_ref()
+        .map(|s| s.user_id.clone())
+        .ok_or_else(|| "Not logged in".to_string())
+}
+
+pub async fn authorized_request<T>(state: &AppState, message: T) -> Result<Request<T>, String> {
+    let access_token = valid_access_token(state).await?;
+
+    let value = MetadataValue::try_from(format!("Bearer {}", access_token))
+        .map_err(|e| format!("Invalid access token: {}", e))?;
+
+    let mut request = Request::new(message);
+    request.metadata_mut().insert("authorization", value);
+
+    Ok(request)
+}
+
+async fn valid_access_token(state: &AppState) -> Result<String, String> {
+    let mut session = state.session.write().await;
+
+    let current = session.as_mut().ok_or_else(|| "Not logged in".to_string())?;
+
+    if Instant::now() + REFRESH_MARGIN < current.expires_at {
+        return Ok(current.access_token.clone());
+    }
+
+    let mut client = UserServiceClient::new(state.user_grpc_channel.clone());
+
+    let response = client
+        .refresh_token(Request::new(RefreshTokenRequest {
+            refresh_token: current.refresh_token.clone(),
+        }))
+        .await;
+
+    match response {
+        Ok(tokens) => {
+            let tokens = tokens.into_inner();
+            *current = Session::new(
+                current.user_id.clone(),
+                tokens.access_token,
+                tokens.refresh_token,
+                tokens.expires_in,
+            );
+            Ok(current.access_token.clone())
+        }
+        Err(e) => {
+            *session = None;
+            Err(format!("Session expired, please log in again: {}", e.message()))
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::nas::{FileResponse, FileType, FolderResponse, UploadStatus};
+use crate::nas::{ArchiveCodec, FileResponse, FileType, FolderResponse, UploadStatus};
 use crate::types::model::{FileView, FolderView};
 use crate::utils::format_timestamp;
 
@@ -22,6 +22,16 @@ pub fn map_file_proto_to_view(f: FileResponse) -> FileView {
     }
     .to_string();
 
+    let archive_codec_str = f.archive_codec.map(|codec| {
+        match ArchiveCodec::try_from(codec) {
+            Ok(ArchiveCodec::Gzip) => "Gzip",
+            Ok(ArchiveCodec::Zstd) => "Zstd",
+            Ok(ArchiveCodec::Xz) => "Xz",
+            _ => "Unknown",
+        }
+        .to_string()
+    });
+
     FileView {
         id: f.id.map(|i| i.value).unwrap_or_default(),
         name: f.name,
@@ -38,6 +48,10 @@ pub fn map_file_proto_to_view(f: FileResponse) -> FileView {
 
         created_at: format_timestamp(f.created_at),
         updated_at: format_timestamp(f.updated_at),
+        checksum: f.checksum,
+        archive_codec: archive_codec_str,
+        logical_size: f.logical_size,
+        has_preview: f.has_preview,
     }
 }
 
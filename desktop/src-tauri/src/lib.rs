@@ -14,11 +14,14 @@ pub mod commands;
 pub mod helpers;
 pub mod types;
 pub mod utils;
+use crate::helpers::auth::Session;
+use tokio::sync::RwLock;
 use tonic::transport::{Channel, Endpoint};
 
 pub struct AppState {
     pub user_grpc_channel: Channel,
     pub nas_grpc_channel: Channel,
+    pub session: RwLock<Option<Session>>,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -36,8 +39,11 @@ pub async fn run() {
         .manage(AppState {
             user_grpc_channel: user_channel,
             nas_grpc_channel: nas_channel,
+            session: RwLock::new(None),
         })
         .invoke_handler(tauri::generate_handler![
+            commands::auth::login,
+            commands::auth::logout,
             commands::user::get_user_profile,
             commands::storage_profile::get_storage_profile,
             commands::folder::get_root_folder,
@@ -48,6 +54,9 @@ pub async fn run() {
             commands::folder::rename_folder,
             commands::file::init_file,
             commands::file::upload_content,
+            commands::file::update_file_content,
+            commands::file::download_file,
+            commands::file::open_file,
             commands::file::rename_file,
             commands::file::delete_file,
             commands::file::get_deleted_files,
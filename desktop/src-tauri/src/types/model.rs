@@ -14,6 +14,7 @@ pub struct StorageProfileView {
     pub user_id: String,
     pub allowed_storage: i64,
     pub taken_storage: i64,
+    pub reserved_storage: i64,
     pub is_blocked: bool,
 }
 
@@ -38,4 +39,8 @@ pub struct FileView {
     pub upload_status: String,
     pub created_at: String,
     pub updated_at: String,
+    pub checksum: Option<String>,
+    pub archive_codec: Option<String>,
+    pub logical_size: i64,
+    pub has_preview: bool,
 }
@@ -9,6 +9,7 @@ export interface StorageProfileView {
     user_id: string;
     allowed_storage: number;
     taken_storage: number;
+    reserved_storage: number;
     is_blocked: boolean;
 }
 
@@ -31,5 +32,9 @@ export interface FileView {
     size: number,
     upload_status: string,
     created_at: string,
-    updated_at: string
+    updated_at: string,
+    checksum?: string,
+    archive_codec?: string,
+    logical_size: number,
+    has_preview: boolean
 }
\ No newline at end of file
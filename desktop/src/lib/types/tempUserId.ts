@@ -1 +0,0 @@
-export const userId = "34ff1758-7f0e-4355-ba90-454f18c7f425";
\ No newline at end of file